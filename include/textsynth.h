@@ -0,0 +1,59 @@
+/*
+ * C api of the elikoga-textsynth crate, built with the `ffi` feature
+ *
+ * All strings are nul terminated UTF-8. Strings returned by this api must be
+ * released with textsynth_string_free. See the `ffi` module of the crate for
+ * the documentation of every function.
+ */
+
+#ifndef TEXTSYNTH_H
+#define TEXTSYNTH_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Returned when the call succeeded */
+#define TEXTSYNTH_OK 0
+/* Returned when an argument is invalid */
+#define TEXTSYNTH_INVALID_ARGUMENT -1
+/* Returned when the request failed */
+#define TEXTSYNTH_REQUEST_FAILED -2
+/* Returned when the call panicked */
+#define TEXTSYNTH_PANICKED -3
+
+/* Client handle owned by C code */
+typedef struct TextSynthHandle TextSynthHandle;
+
+/* Callback receiving each piece of streamed text */
+typedef void (*TextSynthCallback)(const char *text, void *user_data);
+
+const char *textsynth_last_error(void);
+
+TextSynthHandle *textsynth_client_new(const char *api_key);
+
+TextSynthHandle *textsynth_client_new_with_endpoint(const char *api_key,
+                                                    const char *endpoint);
+
+void textsynth_client_free(TextSynthHandle *handle);
+
+void textsynth_string_free(char *text);
+
+int textsynth_complete(const TextSynthHandle *handle,
+                       const char *engine,
+                       const char *prompt,
+                       int max_tokens,
+                       TextSynthCallback callback,
+                       void *user_data);
+
+char *textsynth_translate(const TextSynthHandle *handle,
+                          const char *engine,
+                          const char *text,
+                          const char *source_lang,
+                          const char *target_lang);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TEXTSYNTH_H */
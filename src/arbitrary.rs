@@ -0,0 +1,166 @@
+//! Provides proptest strategies generating valid requests
+
+use proptest::{
+    arbitrary::{any, Arbitrary},
+    collection::{hash_map, vec},
+    option,
+    prelude::{prop_oneof, BoxedStrategy, Just, Strategy},
+    sample::select,
+};
+
+use crate::{completions, tokenize, translate};
+
+impl Arbitrary for completions::Request {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        let sampling = (
+            any::<String>(),
+            option::of(1..=1024_u32),
+            option::of(any::<bool>()),
+            option::of(vec(any::<String>(), 0..=5)),
+            option::of(1..=16_u32),
+            option::of(0.0..=2.0_f64),
+            option::of(1..=1000_u32),
+            option::of(0.0..=1.0_f64),
+        );
+        let penalties = (
+            option::of(hash_map(
+                any::<u16>().prop_map(|token| token.to_string()),
+                -100.0..=100.0_f64,
+                0..=4,
+            )),
+            option::of(-2.0..=2.0_f64),
+            option::of(-2.0..=2.0_f64),
+            option::of(0.5..=2.0_f64),
+            option::of(0.01..=1.0_f64),
+        );
+        (sampling, penalties)
+            .prop_map(
+                |(
+                    (prompt, max_tokens, stream, stop, n, temperature, top_k, top_p),
+                    (
+                        logit_bias,
+                        presence_penalty,
+                        frequency_penalty,
+                        repetition_penalty,
+                        typical_p,
+                    ),
+                )| {
+                    let mut request = completions::RequestBuilder::default();
+                    request.prompt(prompt);
+                    if let Some(max_tokens) = max_tokens {
+                        request.max_tokens(max_tokens);
+                    }
+                    if let Some(stream) = stream {
+                        request.stream(stream);
+                    }
+                    if let Some(stop) = stop {
+                        request.stop(stop.into_iter().map(Into::into).collect::<Vec<_>>());
+                    }
+                    if let Some(n) = n {
+                        request.n(n);
+                    }
+                    if let Some(temperature) = temperature {
+                        request.temperature(temperature);
+                    }
+                    if let Some(top_k) = top_k {
+                        request.top_k(top_k);
+                    }
+                    if let Some(top_p) = top_p {
+                        request.top_p(top_p);
+                    }
+                    if let Some(logit_bias) = logit_bias {
+                        request.logit_bias(logit_bias);
+                    }
+                    if let Some(presence_penalty) = presence_penalty {
+                        request.presence_penalty(presence_penalty);
+                    }
+                    if let Some(frequency_penalty) = frequency_penalty {
+                        request.frequency_penalty(frequency_penalty);
+                    }
+                    if let Some(repetition_penalty) = repetition_penalty {
+                        request.repetition_penalty(repetition_penalty);
+                    }
+                    if let Some(typical_p) = typical_p {
+                        request.typical_p(typical_p);
+                    }
+                    request
+                        .build()
+                        .expect("generated completion request should be valid")
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for completions::logprob::Request {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (any::<String>(), "(?s).+")
+            .prop_map(|(context, continuation)| {
+                completions::logprob::RequestBuilder::default()
+                    .context(context)
+                    .continuation(continuation)
+                    .build()
+                    .expect("generated logprob request should be valid")
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for tokenize::Request {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        any::<String>()
+            .prop_map(|text| {
+                tokenize::RequestBuilder::default()
+                    .text(text)
+                    .build()
+                    .expect("generated tokenize request should be valid")
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for translate::Request {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            vec(any::<String>(), 1..=64),
+            prop_oneof![
+                Just(translate::Language::Auto),
+                select(translate::Language::ALL)
+            ],
+            select(translate::Language::ALL),
+            option::of(1..=5_u32),
+            option::of(any::<bool>()),
+        )
+            .prop_map(
+                |(text, source_lang, target_lang, num_beams, split_sentences)| {
+                    let mut request = translate::RequestBuilder::default();
+                    request
+                        .text(text.into_iter().map(Into::into).collect::<Vec<_>>())
+                        .source_lang(source_lang)
+                        .target_lang(target_lang);
+                    if let Some(num_beams) = num_beams {
+                        request.num_beams(num_beams);
+                    }
+                    if let Some(split_sentences) = split_sentences {
+                        request.split_sentences(split_sentences);
+                    }
+                    request
+                        .build()
+                        .expect("generated translation request should be valid")
+                },
+            )
+            .boxed()
+    }
+}
@@ -0,0 +1,261 @@
+//! Provides order preserving concurrent execution of requests
+//!
+//! The concurrent helpers of this crate return [`BatchResults`], holding one
+//! result per input in the order of the inputs, no matter in which order the
+//! requests completed. The [`duplex`] helpers instead yield the results
+//! from a stream as inputs keep arriving.
+
+use std::{ops::Index, slice, sync::Arc, vec};
+
+use futures::{channel::mpsc, stream, Future, Stream, StreamExt};
+
+use crate::{
+    completions::{self, logprob, score::Score, Engine, Request, ResponseChunk},
+    translate, TextSynthClient,
+};
+
+/// Results of a batch of requests, in the order of their inputs
+#[derive(Debug)]
+pub struct BatchResults<T, E> {
+    results: Vec<Result<T, E>>,
+}
+
+impl<T, E> BatchResults<T, E> {
+    /// Number of results
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Returns true if the batch had no inputs
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Result for the input at `index`
+    pub fn get(&self, index: usize) -> Option<&Result<T, E>> {
+        self.results.get(index)
+    }
+
+    /// Iterate over the results in input order
+    pub fn iter(&self) -> slice::Iter<'_, Result<T, E>> {
+        self.results.iter()
+    }
+
+    /// Successful results together with the index of their input
+    pub fn successes(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(index, result)| result.as_ref().ok().map(|value| (index, value)))
+    }
+
+    /// Errors together with the index of their input
+    pub fn errors(&self) -> impl Iterator<Item = (usize, &E)> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(index, result)| result.as_ref().err().map(|err| (index, err)))
+    }
+
+    /// Returns true if every request succeeded
+    pub fn is_all_ok(&self) -> bool {
+        self.results.iter().all(Result::is_ok)
+    }
+
+    /// All values in input order, or the error of the first failed input
+    pub fn into_result(self) -> Result<Vec<T>, E> {
+        self.results.into_iter().collect()
+    }
+
+    /// The results in input order
+    pub fn into_vec(self) -> Vec<Result<T, E>> {
+        self.results
+    }
+}
+
+impl<T, E> From<Vec<Result<T, E>>> for BatchResults<T, E> {
+    fn from(results: Vec<Result<T, E>>) -> Self {
+        BatchResults { results }
+    }
+}
+
+impl<T, E> Index<usize> for BatchResults<T, E> {
+    type Output = Result<T, E>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.results[index]
+    }
+}
+
+impl<T, E> IntoIterator for BatchResults<T, E> {
+    type Item = Result<T, E>;
+    type IntoIter = vec::IntoIter<Result<T, E>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.results.into_iter()
+    }
+}
+
+impl<'a, T, E> IntoIterator for &'a BatchResults<T, E> {
+    type Item = &'a Result<T, E>;
+    type IntoIter = slice::Iter<'a, Result<T, E>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.results.iter()
+    }
+}
+
+/// Run `f` on every input with at most `concurrency` calls in flight,
+/// collecting the results in input order.
+pub async fn run<I, F, Fut, T, E>(inputs: I, concurrency: usize, f: F) -> BatchResults<T, E>
+where
+    I: IntoIterator,
+    F: FnMut(I::Item) -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let results = stream::iter(inputs)
+        .map(f)
+        .buffered(concurrency.max(1))
+        .collect()
+        .await;
+    BatchResults { results }
+}
+
+/// Run `f` on every input pushed into the returned sink with at most
+/// `concurrency` calls in flight, yielding the results from the returned
+/// stream in the order the inputs were pushed.
+///
+/// At most `buffer` inputs wait in the sink before pushing blocks. The stream
+/// ends once the sink is closed or dropped and every result was yielded.
+pub fn duplex<In, F, Fut, T, E>(
+    buffer: usize,
+    concurrency: usize,
+    f: F,
+) -> (mpsc::Sender<In>, impl Stream<Item = Result<T, E>>)
+where
+    F: FnMut(In) -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let (sender, receiver) = mpsc::channel(buffer);
+    let results = receiver.map(f).buffered(concurrency.max(1));
+    (sender, results)
+}
+
+impl TextSynthClient {
+    /// Complete the requests pushed into the returned sink on `engine`, see
+    /// [`duplex`]
+    pub fn complete_duplex(
+        &self,
+        engine: &Engine,
+        buffer: usize,
+        concurrency: usize,
+    ) -> (
+        mpsc::Sender<Request>,
+        impl Stream<Item = Result<ResponseChunk, completions::Error>> + '_,
+    ) {
+        let engine = engine.clone();
+        duplex(buffer, concurrency, move |request: Request| {
+            let engine = engine.clone();
+            async move { self.complete(&engine, &request).await }
+        })
+    }
+
+    /// Complete every request of `requests` on `engine` with at most
+    /// `concurrency` requests in flight, returning the answers in the order
+    /// of the requests
+    ///
+    /// Each request is retried and rate limited like any other request of
+    /// the client. The `stream` option of the requests is ignored.
+    pub async fn completions_batch(
+        &self,
+        engine: &Engine,
+        requests: impl IntoIterator<Item = Request>,
+        concurrency: usize,
+    ) -> BatchResults<ResponseChunk, completions::Error> {
+        run(requests, concurrency, |request| async move {
+            self.complete(engine, &request).await
+        })
+        .await
+    }
+
+    /// Score every `(context, continuation)` pair of `pairs` on `engine` with
+    /// at most `concurrency` logprob requests in flight, see
+    /// [`TextSynthClient::score`]
+    pub async fn score_all<C, T>(
+        &self,
+        engine: &Engine,
+        pairs: impl IntoIterator<Item = (C, T)>,
+        concurrency: usize,
+    ) -> BatchResults<Score, logprob::Error>
+    where
+        C: AsRef<str>,
+        T: AsRef<str>,
+    {
+        run(pairs, concurrency, |(context, continuation)| async move {
+            self.score(engine, context.as_ref(), continuation.as_ref())
+                .await
+        })
+        .await
+    }
+
+    /// Translate any number of `texts` with the options of `request`,
+    /// splitting them into requests of at most [`translate::MAX_TEXTS`] texts
+    /// with at most `concurrency` requests in flight
+    ///
+    /// The translations are returned in the order of `texts`, every text of
+    /// a failed request sharing its error, with the token counts of the
+    /// successful requests summed up.
+    pub async fn translate_all(
+        &self,
+        engine: &translate::Engine,
+        request: &translate::RequestBuilder,
+        texts: Vec<Arc<str>>,
+        concurrency: usize,
+    ) -> TranslateAllResponse {
+        let chunks = texts.chunks(translate::MAX_TEXTS);
+        let responses = run(chunks, concurrency, |chunk| async move {
+            let request = request.clone().text(chunk.to_vec()).build()?;
+            let response = self.translate(engine, &request).await?;
+            if response.translations.len() != chunk.len() {
+                return Err(translate::Error::TranslationCount {
+                    expected: chunk.len(),
+                    got: response.translations.len(),
+                });
+            }
+            Ok(response)
+        })
+        .await;
+        let mut translations = Vec::with_capacity(texts.len());
+        let mut input_tokens = 0;
+        let mut output_tokens = 0;
+        for (chunk, response) in texts.chunks(translate::MAX_TEXTS).zip(responses) {
+            match response {
+                Ok(response) => {
+                    translations.extend(response.translations.into_iter().map(Ok));
+                    input_tokens += response.input_tokens;
+                    output_tokens += response.output_tokens;
+                }
+                Err(err) => {
+                    let err = Arc::new(err);
+                    translations.extend(chunk.iter().map(|_| Err(Arc::clone(&err))));
+                }
+            }
+        }
+        TranslateAllResponse {
+            translations: translations.into(),
+            input_tokens,
+            output_tokens,
+        }
+    }
+}
+
+/// Answer of [`TextSynthClient::translate_all`]
+#[derive(Debug)]
+pub struct TranslateAllResponse {
+    /// Translation of every text in the order of the texts
+    pub translations: BatchResults<translate::Translation, Arc<translate::Error>>,
+    /// Number of input tokens of the successful requests
+    pub input_tokens: u32,
+    /// Number of generated tokens of the successful requests
+    pub output_tokens: u32,
+}
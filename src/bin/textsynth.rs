@@ -0,0 +1,231 @@
+//! Command line client of the TextSynth API
+
+use std::{
+    error::Error,
+    io::{self, Read, Write},
+};
+
+use clap::{Parser, Subcommand};
+use elikoga_textsynth::{
+    completions::{self, logprob},
+    tokenize, translate, TextSynthClient,
+};
+use futures::StreamExt;
+
+/// Command line client of the TextSynth API
+#[derive(Parser)]
+#[command(name = "textsynth", version)]
+struct Cli {
+    /// Api key of the TextSynth account.
+    #[arg(long, env = "TEXT_SYNTH_API_KEY", hide_env_values = true)]
+    api_key: String,
+    /// Endpoint of the api, e.g. of a self-hosted server.
+    #[arg(long, default_value = "https://api.textsynth.com/v1")]
+    endpoint: String,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Complete a prompt, streaming the generated text to stdout
+    Complete {
+        /// Completion engine.
+        #[arg(long, default_value = "gptj_6B", value_parser = completion_engine)]
+        engine: completions::Engine,
+        /// Maximum number of tokens to generate.
+        #[arg(long)]
+        max_tokens: Option<u32>,
+        /// Sampling temperature.
+        #[arg(long)]
+        temperature: Option<f64>,
+        /// Select the next token among the top_k most likely ones.
+        #[arg(long)]
+        top_k: Option<u32>,
+        /// Select the next token among the most probable ones with a
+        /// cumulative probability of top_p.
+        #[arg(long)]
+        top_p: Option<f64>,
+        /// Stop the generation at this string, may be repeated.
+        #[arg(long)]
+        stop: Vec<String>,
+        /// Print the text once it is complete instead of streaming it.
+        #[arg(long)]
+        no_stream: bool,
+        /// The prompt, read from stdin if missing.
+        prompt: Option<String>,
+    },
+    /// Translate texts, printing one translation per line
+    Translate {
+        /// Translation engine.
+        #[arg(long, default_value = "m2m100_1_2B", value_parser = translation_engine)]
+        engine: translate::Engine,
+        /// ISO code of the source language, or "auto".
+        #[arg(long, default_value = "auto")]
+        source_lang: String,
+        /// ISO code of the target language.
+        #[arg(long)]
+        target_lang: String,
+        /// Number of beams used to generate the translations.
+        #[arg(long)]
+        num_beams: Option<u32>,
+        /// Don't split the texts into sentences.
+        #[arg(long)]
+        no_split_sentences: bool,
+        /// The texts, read from the lines of stdin if missing.
+        text: Vec<String>,
+    },
+    /// Print the token indexes of a text
+    Tokenize {
+        /// Engine whose tokenizer is used.
+        #[arg(long, default_value = "gptj_6B", value_parser = completion_engine)]
+        engine: completions::Engine,
+        /// The text, read from stdin if missing.
+        text: Option<String>,
+    },
+    /// Print the log probability of a continuation following a context
+    Logprob {
+        /// Completion engine.
+        #[arg(long, default_value = "gptj_6B", value_parser = completion_engine)]
+        engine: completions::Engine,
+        /// The context, the End-Of-Text token if empty.
+        #[arg(long, default_value = "")]
+        context: String,
+        /// The continuation, read from stdin if missing.
+        continuation: Option<String>,
+    },
+}
+
+/// Built-in engines by their api name, any other name as `Other`
+fn completion_engine(id: &str) -> Result<completions::Engine, String> {
+    Ok(id
+        .parse()
+        .unwrap_or_else(|_| completions::Engine::Other(id.to_string())))
+}
+
+/// Built-in engines by their api name, any other name as `Other`
+fn translation_engine(id: &str) -> Result<translate::Engine, String> {
+    Ok(id
+        .parse()
+        .unwrap_or_else(|_| translate::Engine::Other(id.to_string())))
+}
+
+/// `text`, or stdin if it is missing
+fn or_stdin(text: Option<String>) -> io::Result<String> {
+    match text {
+        Some(text) => Ok(text),
+        None => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            Ok(text)
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+    let client = TextSynthClient::builder(&cli.api_key)
+        .endpoint(&cli.endpoint)
+        .try_build()?;
+    match cli.command {
+        Command::Complete {
+            engine,
+            max_tokens,
+            temperature,
+            top_k,
+            top_p,
+            stop,
+            no_stream,
+            prompt,
+        } => {
+            let mut request = completions::RequestBuilder::default();
+            request.prompt(or_stdin(prompt)?);
+            if let Some(max_tokens) = max_tokens {
+                request.max_tokens(max_tokens);
+            }
+            if let Some(temperature) = temperature {
+                request.temperature(temperature);
+            }
+            if let Some(top_k) = top_k {
+                request.top_k(top_k);
+            }
+            if let Some(top_p) = top_p {
+                request.top_p(top_p);
+            }
+            if !stop.is_empty() {
+                request.stop(stop.into_iter().map(Into::into).collect::<Vec<_>>());
+            }
+            let request = request.stream(!no_stream).build()?;
+            let mut stdout = io::stdout();
+            if no_stream {
+                let response = client.complete(&engine, &request).await?;
+                writeln!(stdout, "{}", response.text.concat())?;
+            } else {
+                let mut chunks = Box::pin(client.completions(&engine, &request).await?);
+                while let Some(chunk) = chunks.next().await {
+                    write!(stdout, "{}", chunk?.text.concat())?;
+                    stdout.flush()?;
+                }
+                writeln!(stdout)?;
+            }
+        }
+        Command::Translate {
+            engine,
+            source_lang,
+            target_lang,
+            num_beams,
+            no_split_sentences,
+            text,
+        } => {
+            let text = if text.is_empty() {
+                or_stdin(None)?.lines().map(str::to_string).collect()
+            } else {
+                text
+            };
+            let mut request = translate::RequestBuilder::default();
+            request
+                .source_lang(source_lang.as_str())
+                .target_lang(target_lang.as_str())
+                .split_sentences(!no_split_sentences);
+            if let Some(num_beams) = num_beams {
+                request.num_beams(num_beams);
+            }
+            let response = client
+                .translate_all(
+                    &engine,
+                    &request,
+                    text.into_iter().map(Into::into).collect(),
+                    1,
+                )
+                .await;
+            for translation in response.translations {
+                println!("{}", translation?.text);
+            }
+        }
+        Command::Tokenize { engine, text } => {
+            let request = tokenize::RequestBuilder::default()
+                .text(or_stdin(text)?)
+                .build()?;
+            let response = client.tokenize(&engine, &request).await?;
+            let tokens: Vec<_> = response.tokens.iter().map(u32::to_string).collect();
+            println!("{}", tokens.join(" "));
+        }
+        Command::Logprob {
+            engine,
+            context,
+            continuation,
+        } => {
+            let request = logprob::RequestBuilder::default()
+                .context(context)
+                .continuation(or_stdin(continuation)?)
+                .build()?;
+            let response = client.logprob(&engine, &request).await?;
+            println!(
+                "logprob: {}\nnum_tokens: {}\nis_greedy: {}",
+                response.logprob, response.num_tokens, response.is_greedy
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,182 @@
+//! Provides a blocking client, for programs without an async runtime
+
+use std::{fmt, io::BufReader};
+
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    completions::{self, logprob, read_values, ResponseChunk},
+    error_answer, new_request_id, normalize_endpoint, parse_body, tokenize, translate, BuildError,
+    IsEngine, ResponseError, DEFAULT_USER_AGENT, REQUEST_ID_HEADER,
+};
+
+/// Blocking TextSynth API Client
+///
+/// Its `Debug` output shows the endpoint but never the api key.
+pub struct TextSynthBlockingClient {
+    /// endpoint of TextSynth API
+    base_url: String,
+    /// Client for making requests to the TextSynth API
+    client: Client,
+}
+
+impl fmt::Debug for TextSynthBlockingClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TextSynthBlockingClient")
+            .field("base_url", &self.base_url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TextSynthBlockingClient {
+    /// Create a new blocking TextSynth API Client with a custom endpoint
+    ///
+    /// # Panics
+    ///
+    /// Panics if the client can't be built, see
+    /// [`TextSynthBlockingClient::try_new_with_endpoint`].
+    pub fn new_with_endpoint(api_key: &str, endpoint: &str) -> Self {
+        Self::try_new_with_endpoint(api_key, endpoint)
+            .expect("failed to build blocking TextSynth client")
+    }
+
+    /// Create a new blocking TextSynth API Client
+    ///
+    /// # Panics
+    ///
+    /// Panics if the client can't be built, see
+    /// [`TextSynthBlockingClient::try_new`].
+    pub fn new(api_key: &str) -> Self {
+        Self::new_with_endpoint(api_key, "https://api.textsynth.com/v1")
+    }
+
+    /// Create a new blocking TextSynth API Client with a custom endpoint,
+    /// failing if the endpoint isn't an http or https url, the api key isn't
+    /// valid in a header or the http client can't be built
+    pub fn try_new_with_endpoint(api_key: &str, endpoint: &str) -> Result<Self, BuildError> {
+        let mut authorization =
+            reqwest::header::HeaderValue::from_str(&format!("Bearer {}", api_key))?;
+        authorization.set_sensitive(true);
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(reqwest::header::AUTHORIZATION, authorization);
+        Ok(TextSynthBlockingClient {
+            base_url: normalize_endpoint(endpoint)?,
+            client: Client::builder()
+                .default_headers(headers)
+                .user_agent(DEFAULT_USER_AGENT)
+                .build()?,
+        })
+    }
+
+    /// Create a new blocking TextSynth API Client, failing if the api key
+    /// isn't valid in a header or the http client can't be built
+    pub fn try_new(api_key: &str) -> Result<Self, BuildError> {
+        Self::try_new_with_endpoint(api_key, "https://api.textsynth.com/v1")
+    }
+
+    /// Send `request`, failing with the error answer of the api if the
+    /// response isn't successful
+    fn send<E>(&self, request: RequestBuilder) -> Result<Response, E>
+    where
+        E: ResponseError,
+    {
+        let request_id = new_request_id();
+        let mut response = request
+            .header(REQUEST_ID_HEADER, request_id.clone())
+            .send()?;
+        response
+            .headers_mut()
+            .entry(REQUEST_ID_HEADER)
+            .or_insert(request_id);
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let headers = response.headers().clone();
+        let body = response.text()?;
+        Err(error_answer(status, &headers, body))
+    }
+
+    /// Post `body` to `url` and parse the json response
+    fn post<T, E>(&self, url: &str, body: String) -> Result<T, E>
+    where
+        T: DeserializeOwned,
+        E: ResponseError,
+    {
+        let response = self.send::<E>(self.client.post(url).body(body))?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        Ok(parse_body(status, &headers, &response.bytes()?)?)
+    }
+
+    /// Perform a completion request and wait for the complete answer. The
+    /// `stream` option of the request is ignored.
+    pub fn complete(
+        &self,
+        engine: &completions::Engine,
+        request: &completions::Request,
+    ) -> Result<ResponseChunk, completions::Error> {
+        let mut request_json = serde_json::to_value(request)?;
+        if let Some(request_json) = request_json.as_object_mut() {
+            request_json.remove("stream");
+        }
+        let request_json = serde_json::to_string(&request_json)?;
+        let url = format!("{}/engines/{}/completions", self.base_url, engine);
+        self.post(&url, request_json)
+    }
+
+    /// Perform a completion request, iterating over the chunks as they are
+    /// streamed
+    pub fn completions(
+        &self,
+        engine: &completions::Engine,
+        request: &completions::Request,
+    ) -> Result<impl Iterator<Item = Result<ResponseChunk, completions::Error>>, completions::Error>
+    {
+        let request_json = serde_json::to_string(&request)?;
+        let url = format!("{}/engines/{}/completions", self.base_url, engine);
+        let response =
+            self.send::<completions::Error>(self.client.post(&url).body(request_json))?;
+        let event_stream = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with("text/event-stream"));
+        let chunks = read_values::<ResponseChunk>(BufReader::new(response), event_stream)?;
+        Ok(chunks.map(|chunk| chunk.map_err(completions::Error::from)))
+    }
+
+    /// Perform a translation request
+    pub fn translate(
+        &self,
+        engine: &translate::Engine,
+        request: &translate::Request,
+    ) -> Result<translate::Response, translate::Error> {
+        let request_json = serde_json::to_string(&request)?;
+        let url = format!("{}/engines/{}/translate", self.base_url, engine);
+        self.post(&url, request_json)
+    }
+
+    /// Perform a tokenization request
+    pub fn tokenize(
+        &self,
+        engine: &impl IsEngine,
+        request: &tokenize::Request,
+    ) -> Result<tokenize::Response, tokenize::Error> {
+        let request_json = serde_json::to_string(&request)?;
+        let url = format!("{}/engines/{}/tokenize", self.base_url, engine);
+        self.post(&url, request_json)
+    }
+
+    /// Perform a logprob request
+    pub fn logprob(
+        &self,
+        engine: &completions::Engine,
+        request: &logprob::Request,
+    ) -> Result<logprob::Response, logprob::Error> {
+        let request_json = serde_json::to_string(&request)?;
+        let url = format!("{}/engines/{}/logprob", self.base_url, engine);
+        self.post(&url, request_json)
+    }
+}
@@ -0,0 +1,318 @@
+//! Provides chat api
+
+use std::{fmt, str::FromStr, sync::Arc};
+
+#[cfg(feature = "streaming")]
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+use thiserror::Error;
+
+use crate::{
+    completions::{check_max_tokens, check_stop, check_top_k, check_top_p, ResponseChunk},
+    engine::{engine_info, EngineInfo, ParseEngineError},
+    record_tokens,
+    validation::ValidationError,
+    IsEngine, TextSynthClient, WithMeta,
+};
+
+/// Enum for the different chat engines available for TextSynth
+#[derive(strum::IntoStaticStr, Debug, Clone, PartialEq, Eq)]
+pub enum Engine {
+    /// Falcon 40B fine tuned for chat. Its main languages are English, German,
+    /// Spanish and French.
+    #[strum(serialize = "falcon_40B-chat")]
+    Falcon40BChat,
+    /// Llama 2 with 70 billion parameters fine tuned for chat. Its main
+    /// language is English.
+    #[strum(serialize = "llama2_70B_chat")]
+    Llama2Chat70B,
+    /// Mistral 7B fine tuned to follow instructions.
+    #[strum(serialize = "mistral_7B_instruct")]
+    Mistral7BInstruct,
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.into())
+    }
+}
+
+impl Engine {
+    /// Metadata of the engine
+    pub fn info(&self) -> EngineInfo {
+        engine_info(self.into())
+            .cloned()
+            .expect("built-in engines are in the registry")
+    }
+}
+
+/// Parses the api name of an engine
+impl FromStr for Engine {
+    type Err = ParseEngineError;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        [
+            Engine::Falcon40BChat,
+            Engine::Llama2Chat70B,
+            Engine::Mistral7BInstruct,
+        ]
+        .into_iter()
+        .find(|engine| engine.to_string() == id)
+        .ok_or_else(|| ParseEngineError::new("chat", id))
+    }
+}
+
+impl TryFrom<&str> for Engine {
+    type Error = ParseEngineError;
+
+    fn try_from(id: &str) -> Result<Self, Self::Error> {
+        id.parse()
+    }
+}
+
+impl IsEngine for Engine {
+    fn is_completion(&self) -> bool {
+        true
+    }
+}
+
+/// Author of a chat message
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Instructions for the assistant.
+    System,
+    /// Input of the user.
+    User,
+    /// Answer of the assistant.
+    Assistant,
+}
+
+/// A message of a conversation
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// Author of the message.
+    pub role: Role,
+    /// Text of the message.
+    pub content: Arc<str>,
+}
+
+impl Message {
+    /// A system message
+    pub fn system(content: impl Into<Arc<str>>) -> Self {
+        Message {
+            role: Role::System,
+            content: content.into(),
+        }
+    }
+
+    /// A user message
+    pub fn user(content: impl Into<Arc<str>>) -> Self {
+        Message {
+            role: Role::User,
+            content: content.into(),
+        }
+    }
+
+    /// An assistant message
+    pub fn assistant(content: impl Into<Arc<str>>) -> Self {
+        Message {
+            role: Role::Assistant,
+            content: content.into(),
+        }
+    }
+}
+
+/// Struct for a chat request
+#[skip_serializing_none]
+#[derive(Serialize, Builder, Debug, Clone)]
+#[builder(setter(into))]
+#[builder(derive(Debug))]
+#[builder(build_fn(validate = "Self::validate", error = "ValidationError"))]
+pub struct Request {
+    /// The conversation so far. The last message must be from the user.
+    messages: Vec<Message>,
+    /// Maximum number of tokens to generate.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    max_tokens: Option<u32>,
+    /// If true, the output is streamed so that it is possible to display the
+    /// result before the complete output is generated.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    stream: Option<bool>,
+    /// Stop the generation when the string(s) are encountered. The generated
+    /// text does not contain the string. The length of the array is at most 5.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    stop: Option<Vec<Arc<str>>>,
+    /// Sampling temperature, see the completion request.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    temperature: Option<f64>,
+    /// Select the next output token among the top_k most likely ones, see the
+    /// completion request.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    top_k: Option<u32>,
+    /// Select the next output token among the most probable ones so that their
+    /// cumulative probability is larger than top_p, see the completion
+    /// request.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    top_p: Option<f64>,
+}
+
+/// Error of building a [`Request`]
+pub type RequestBuilderError = ValidationError;
+
+impl Request {
+    /// Builder with the required `messages` set, so that building fails only on
+    /// invalid parameters
+    pub fn builder(messages: impl Into<Vec<Message>>) -> RequestBuilder {
+        let mut builder = RequestBuilder::default();
+        builder.messages(messages);
+        builder
+    }
+}
+
+fn check_messages(messages: &[Message]) -> Result<(), ValidationError> {
+    match messages.last() {
+        Some(message) if message.role == Role::User => Ok(()),
+        Some(message) => Err(ValidationError::invalid(
+            "messages",
+            "ending with a message from the user",
+            format!("a last message from the {:?}", message.role).to_lowercase(),
+        )),
+        None => Err(ValidationError::invalid(
+            "messages",
+            "not empty",
+            "no messages",
+        )),
+    }
+}
+
+impl RequestBuilder {
+    fn validate(&self) -> Result<(), ValidationError> {
+        if let Some(messages) = &self.messages {
+            check_messages(messages)?;
+        }
+        if let Some(Some(max_tokens)) = self.max_tokens {
+            check_max_tokens(max_tokens)?;
+        }
+        if let Some(Some(stop)) = &self.stop {
+            check_stop(stop)?;
+        }
+        if let Some(Some(top_k)) = self.top_k {
+            check_top_k(top_k)?;
+        }
+        if let Some(Some(top_p)) = self.top_p {
+            check_top_p(top_p)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+/// Error for a chat answer
+pub enum Error {
+    /// Serde error
+    #[error("Serde error: {0}")]
+    SerdeError(#[from] serde_json::Error),
+    /// Error from Reqwest
+    #[error("Reqwest error: {0}")]
+    RequestError(#[from] reqwest::Error),
+    /// Error answer of the api
+    #[error("API error: {0}")]
+    ApiError(#[from] crate::ApiError),
+    /// The api rate limited the request
+    #[error("Rate limited: {0}")]
+    RateLimited(#[from] crate::RateLimited),
+    /// The api rejected the api key
+    #[error("Unauthorized: {0}")]
+    Unauthorized(#[from] crate::Unauthorized),
+    /// The api key isn't allowed to make the request
+    #[error("Forbidden: {0}")]
+    Forbidden(#[from] crate::Forbidden),
+    /// Successful answer that couldn't be parsed
+    #[error("HTTP error: {0}")]
+    Http(#[from] crate::HttpError),
+    /// Couldn't parse the response to chat
+    #[cfg(feature = "streaming")]
+    #[error("Couldn't parse the response to chat")]
+    ParseError(bytes::Bytes),
+    /// No data of the streamed chat arrived within the read timeout
+    #[cfg(feature = "streaming")]
+    #[error("No data received within {0:?}")]
+    StreamTimeout(std::time::Duration),
+    /// More bytes of the streamed chat arrived than allowed without
+    /// completing a chunk
+    #[cfg(feature = "streaming")]
+    #[error("More than {0} bytes received without a complete chunk")]
+    PendingBytesExceeded(usize),
+}
+
+impl TextSynthClient {
+    /// Perform a chat request and wait for the complete answer. The `stream`
+    /// option of the request is ignored.
+    pub async fn chat(&self, engine: &Engine, request: &Request) -> Result<ResponseChunk, Error> {
+        Ok(self.chat_with_meta(engine, request).await?.response)
+    }
+
+    /// Perform a chat request and wait for the complete answer, keeping the
+    /// metadata of the response. The `stream` option of the request is
+    /// ignored.
+    pub async fn chat_with_meta(
+        &self,
+        engine: &Engine,
+        request: &Request,
+    ) -> Result<WithMeta<ResponseChunk>, Error> {
+        let mut request_json = serde_json::to_value(request)?;
+        if let Some(request_json) = request_json.as_object_mut() {
+            request_json.remove("stream");
+        }
+        let request_json = serde_json::to_string(&request_json)?;
+        let url = format!("{}/engines/{}/chat", self.base_url, engine);
+        let response = self
+            .post_with_meta::<ResponseChunk, Error>(&url, request_json)
+            .await?;
+        record_tokens(
+            self.usage.as_ref(),
+            engine,
+            response.response.input_tokens,
+            response.response.output_tokens,
+        );
+        Ok(response)
+    }
+
+    /// Perform a streaming chat request
+    ///
+    /// Dropping the returned stream aborts the request, see
+    /// [`TextSynthClient::completions`].
+    #[cfg(feature = "streaming")]
+    pub async fn chat_stream(
+        &self,
+        engine: &Engine,
+        request: &Request,
+    ) -> Result<impl Stream<Item = Result<ResponseChunk, Error>>, Error> {
+        let request_json = serde_json::to_string(&request)?;
+        let url = format!("{}/engines/{}/chat", self.base_url, engine);
+        let response = self
+            .send_checked::<Error>(self.client.post(&url).body(request_json))
+            .await?;
+        let chunks = crate::completions::json_stream(
+            response,
+            self.stream_read_timeout,
+            self.stream_max_pending_bytes,
+            Error::ParseError,
+            Error::StreamTimeout,
+            Error::PendingBytesExceeded,
+        );
+        Ok(
+            crate::completions::prefetch(chunks, self.stream_prefetch).inspect(
+                crate::completions::record_chunk_tokens(self.usage.clone(), engine),
+            ),
+        )
+    }
+}
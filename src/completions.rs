@@ -1,19 +1,62 @@
 //! Provides completion api
 
+#[cfg(feature = "streaming")]
+pub mod callback;
+#[cfg(feature = "streaming")]
+pub mod checkpoint;
+pub mod choices;
+pub mod context;
+pub mod escalate;
+#[cfg(feature = "streaming")]
+pub mod events;
+pub mod finish;
+#[cfg(feature = "streaming")]
+pub mod iter;
+pub mod language;
+pub mod logit_bias;
 pub mod logprob;
+pub mod postprocess;
+pub mod score;
+pub mod select;
+pub mod session;
+#[cfg(feature = "streaming")]
+pub mod stats;
+#[cfg(feature = "streaming")]
+pub mod stop_filter;
 
-use std::{collections::HashMap, fmt, marker::PhantomData};
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+use std::io::BufRead;
+#[cfg(feature = "streaming")]
+use std::time::Duration;
+use std::{collections::HashMap, fmt, marker::PhantomData, str::FromStr, sync::Arc};
 
-use bytes::{Buf, BytesMut};
-use futures::{stream, Stream, StreamExt};
+#[cfg(feature = "streaming")]
+use bytes::{Buf, Bytes, BytesMut};
+#[cfg(feature = "streaming")]
+use futures::{
+    future::{self, Either},
+    stream::{self, AbortHandle, Abortable},
+    Stream, StreamExt,
+};
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+use serde::de::DeserializeOwned;
 use serde::{de, Deserialize, Deserializer, Serialize};
 use serde_with::skip_serializing_none;
 use thiserror::Error;
 
-use crate::{IsEngine, TextSynthClient};
+use crate::{
+    engine::{engine_info, EngineDescriptor, EngineInfo, ParseEngineError},
+    record_tokens,
+    validation::ValidationError,
+    IsEngine, TextSynthClient, WithMeta,
+};
 
 /// Enum for the different completion engines available for TextSynth
-#[derive(strum::Display)]
+///
+/// New engines are added over time, use [`Engine::Other`] for the ones this
+/// version of the crate doesn't know yet.
+#[derive(strum::IntoStaticStr, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Engine {
     /// GPT-J is a language model with 6 billion parameters trained on the Pile
     /// (825 GB of text data) published by EleutherAI. Its main language is
@@ -34,24 +77,109 @@ pub enum Engine {
     /// with 20 billion parameters. It was trained on the same corpus as GPT-J.
     #[strum(serialize = "gptneox_20B")]
     GPTNeoX20B,
+    /// An engine the crate doesn't know about, see the `engine` module.
+    Custom(&'static EngineDescriptor),
+    /// An engine the crate doesn't know about, by its api name.
+    Other(String),
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Engine::Custom(descriptor) => f.write_str(descriptor.name),
+            Engine::Other(id) => f.write_str(id),
+            engine => f.write_str(engine.into()),
+        }
+    }
+}
+
+impl Engine {
+    /// Maximum number of prompt and generated tokens together, unknown for
+    /// [`Engine::Other`]
+    pub fn context_length(&self) -> Option<u32> {
+        match self {
+            Engine::GPTJ6B => Some(2048),
+            Engine::Custom(descriptor) => Some(descriptor.context_length),
+            Engine::Other(_) => None,
+            _ => Some(1024),
+        }
+    }
+
+    /// Maximum number of tokens of a request, the same as
+    /// [`Engine::context_length`]
+    pub fn max_context_tokens(&self) -> Option<u32> {
+        self.context_length()
+    }
+
+    /// Metadata of the engine, unknown for [`Engine::Other`] engines the
+    /// crate doesn't know
+    pub fn info(&self) -> Option<EngineInfo> {
+        match self {
+            Engine::Custom(descriptor) => Some(descriptor.info()),
+            engine => engine_info(&engine.to_string()).cloned(),
+        }
+    }
+
+    /// Find the engine with the api name `id`
+    pub(crate) fn from_id(id: &str) -> Option<Engine> {
+        [
+            Engine::GPTJ6B,
+            Engine::Boris6B,
+            Engine::FairseqGPT13B,
+            Engine::GPTNeoX20B,
+        ]
+        .into_iter()
+        .find(|engine| engine.to_string() == id)
+    }
+}
+
+/// Parses the api name of a built-in engine
+impl FromStr for Engine {
+    type Err = ParseEngineError;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        Engine::from_id(id).ok_or_else(|| ParseEngineError::new("completion", id))
+    }
+}
+
+impl TryFrom<&str> for Engine {
+    type Error = ParseEngineError;
+
+    fn try_from(id: &str) -> Result<Self, Self::Error> {
+        id.parse()
+    }
 }
 
 impl IsEngine for Engine {
     fn is_completion(&self) -> bool {
-        true
+        match self {
+            Engine::Custom(descriptor) => descriptor.is_completion(),
+            _ => true,
+        }
+    }
+
+    fn is_translation(&self) -> bool {
+        match self {
+            Engine::Custom(descriptor) => descriptor.is_translation(),
+            _ => false,
+        }
     }
 }
 
 /// Struct for a completion request
 #[skip_serializing_none]
-#[derive(Serialize, Builder)]
+#[derive(Serialize, Builder, Debug, Clone)]
 #[builder(setter(into))]
-#[builder(build_fn(validate = "Self::validate"))]
+#[builder(derive(Debug))]
+#[builder(build_fn(validate = "Self::validate", error = "ValidationError"))]
 pub struct Request {
     /// The input text to complete.
     ///
     /// NOTE: The prompt is not included in the output.
-    prompt: String,
+    ///
+    /// Shared rather than copied when the request or builder is cloned, so
+    /// large prompts are only allocated once.
+    prompt: Arc<str>,
     /// Maximum number of tokens to generate. A token represents about 4
     /// characters for English texts. The total number of tokens (prompt +
     /// generated text) cannot exceed the model's maximum context length. It
@@ -69,7 +197,7 @@ pub struct Request {
     /// text does not contain the string. The length of the array is at most 5.
     #[builder(setter(strip_option))]
     #[builder(default)]
-    stop: Option<Vec<String>>,
+    stop: Option<Vec<Arc<str>>>,
     /// Generate n completions from a single prompt.
     #[builder(setter(strip_option))]
     #[builder(default)]
@@ -130,53 +258,347 @@ pub struct Request {
     #[builder(setter(strip_option))]
     #[builder(default)]
     typical_p: Option<f64>,
+    /// If true, the log probability of every generated token is returned in
+    /// [`ResponseChunk::logprobs`].
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    logprobs: Option<bool>,
+    /// Number of most likely alternatives returned with the log probability
+    /// of every generated token, between 0 and 20. Requires `logprobs`.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    top_logprobs: Option<u32>,
+    /// Constrain the generated text to match a BNF grammar. Cannot be
+    /// combined with `schema`.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    grammar: Option<String>,
+    /// Constrain the generated text to be JSON matching a JSON schema.
+    /// Cannot be combined with `grammar`.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    schema: Option<serde_json::Value>,
+}
+
+/// Error of building a [`Request`]
+pub type RequestBuilderError = ValidationError;
+
+impl Request {
+    /// Builder with the required `prompt` set, so that building fails only on
+    /// invalid parameters
+    pub fn builder(prompt: impl Into<Arc<str>>) -> RequestBuilder {
+        let mut builder = RequestBuilder::default();
+        builder.prompt(prompt);
+        builder
+    }
+}
+
+/// Constraint on the generated text, see [`RequestBuilder::constraint`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constraint {
+    /// The text must match a BNF grammar.
+    Grammar(String),
+    /// The text must be JSON matching a JSON schema.
+    JsonSchema(serde_json::Value),
+}
+
+fn check_n(n: u32) -> Result<(), ValidationError> {
+    if !(1..=16).contains(&n) {
+        return Err(ValidationError::invalid("n", "between 1 and 16", n));
+    }
+    Ok(())
+}
+
+pub(crate) fn check_top_k(top_k: u32) -> Result<(), ValidationError> {
+    if !(1..=1000).contains(&top_k) {
+        return Err(ValidationError::invalid(
+            "top_k",
+            "between 1 and 1000",
+            top_k,
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn check_top_p(top_p: f64) -> Result<(), ValidationError> {
+    if !(0.0..=1.0).contains(&top_p) {
+        return Err(ValidationError::invalid(
+            "top_p",
+            "between 0.0 and 1.0",
+            top_p,
+        ));
+    }
+    Ok(())
+}
+
+fn check_presence_penalty(presence_penalty: f64) -> Result<(), ValidationError> {
+    if !(-2.0..=2.0).contains(&presence_penalty) {
+        return Err(ValidationError::invalid(
+            "presence_penalty",
+            "between -2.0 and 2.0",
+            presence_penalty,
+        ));
+    }
+    Ok(())
+}
+
+fn check_frequency_penalty(frequency_penalty: f64) -> Result<(), ValidationError> {
+    if !(-2.0..=2.0).contains(&frequency_penalty) {
+        return Err(ValidationError::invalid(
+            "frequency_penalty",
+            "between -2.0 and 2.0",
+            frequency_penalty,
+        ));
+    }
+    Ok(())
+}
+
+fn check_typical_p(typical_p: f64) -> Result<(), ValidationError> {
+    // typical_p: must be > 0 and <= 1
+    if !(typical_p > 0.0 && typical_p <= 1.0) {
+        return Err(ValidationError::invalid(
+            "typical_p",
+            "between 0.0 and 1.0",
+            typical_p,
+        ));
+    }
+    Ok(())
+}
+
+fn check_top_logprobs(top_logprobs: u32) -> Result<(), ValidationError> {
+    if top_logprobs > 20 {
+        return Err(ValidationError::invalid(
+            "top_logprobs",
+            "between 0 and 20",
+            top_logprobs,
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn check_max_tokens(max_tokens: u32) -> Result<(), ValidationError> {
+    if max_tokens == 0 {
+        return Err(ValidationError::invalid(
+            "max_tokens",
+            "positive",
+            max_tokens,
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn check_stop(stop: &[Arc<str>]) -> Result<(), ValidationError> {
+    if stop.len() > 5 {
+        return Err(ValidationError::invalid(
+            "stop",
+            "at most 5 entries",
+            format!("{} entries", stop.len()),
+        ));
+    }
+    Ok(())
+}
+
+fn check_logit_bias(logit_bias: &HashMap<String, f64>) -> Result<(), ValidationError> {
+    match logit_bias
+        .values()
+        .find(|bias| !(-100.0..=100.0).contains(*bias))
+    {
+        Some(bias) => Err(ValidationError::invalid(
+            "logit_bias",
+            "between -100.0 and 100.0",
+            bias,
+        )),
+        None => Ok(()),
+    }
+}
+
+fn check_repetition_penalty(repetition_penalty: f64) -> Result<(), ValidationError> {
+    // repetition_penalty: must be > 0
+    if repetition_penalty.is_nan() || repetition_penalty <= 0.0 {
+        return Err(ValidationError::invalid(
+            "repetition_penalty",
+            "positive",
+            repetition_penalty,
+        ));
+    }
+    Ok(())
 }
 
 impl RequestBuilder {
-    fn validate(&self) -> Result<(), String> {
-        // n must be between 1 and 16
-        match self.n {
-            Some(Some(n)) if !(1..=16).contains(&n) => {
-                return Err("n must be between 1 and 16".to_string());
-            }
-            _ => {}
-        };
-        // top_k must be between 1 and 1000
-        match self.top_k {
-            Some(Some(top_k)) if !(1..=1000).contains(&top_k) => {
-                return Err("top_k must be between 1 and 1000".to_string());
-            }
-            _ => {}
-        };
-        // top_p must be between 0.0 and 1.0
-        match self.top_p {
-            Some(Some(top_p)) if !(0.0..=1.0).contains(&top_p) => {
-                return Err("top_p must be between 0.0 and 1.0".to_string());
-            }
-            _ => {}
-        };
-        // presence_penalty must be between -2.0 and 2.0
-        match self.presence_penalty {
-            Some(Some(presence_penalty)) if !(-2.0..=2.0).contains(&presence_penalty) => {
-                return Err("presence_penalty must be between -2.0 and 2.0".to_string());
+    fn validate(&self) -> Result<(), ValidationError> {
+        if let Some(Some(max_tokens)) = self.max_tokens {
+            check_max_tokens(max_tokens)?;
+        }
+        if let Some(Some(stop)) = &self.stop {
+            check_stop(stop)?;
+        }
+        if let Some(Some(n)) = self.n {
+            check_n(n)?;
+        }
+        if let Some(Some(top_k)) = self.top_k {
+            check_top_k(top_k)?;
+        }
+        if let Some(Some(top_p)) = self.top_p {
+            check_top_p(top_p)?;
+        }
+        if let Some(Some(logit_bias)) = &self.logit_bias {
+            check_logit_bias(logit_bias)?;
+        }
+        if let Some(Some(presence_penalty)) = self.presence_penalty {
+            check_presence_penalty(presence_penalty)?;
+        }
+        if let Some(Some(frequency_penalty)) = self.frequency_penalty {
+            check_frequency_penalty(frequency_penalty)?;
+        }
+        if let Some(Some(repetition_penalty)) = self.repetition_penalty {
+            check_repetition_penalty(repetition_penalty)?;
+        }
+        if let Some(Some(typical_p)) = self.typical_p {
+            check_typical_p(typical_p)?;
+        }
+        if let Some(Some(top_logprobs)) = self.top_logprobs {
+            check_top_logprobs(top_logprobs)?;
+            if !matches!(self.logprobs, Some(Some(true))) {
+                return Err(ValidationError::invalid(
+                    "logprobs",
+                    "true when top_logprobs is set",
+                    self.logprobs.flatten().unwrap_or_default(),
+                ));
             }
-            _ => {}
-        };
-        // frequency_penalty must be between -2.0 and 2.0
-        match self.frequency_penalty {
-            Some(Some(frequency_penalty)) if !(-2.0..=2.0).contains(&frequency_penalty) => {
-                return Err("frequency_penalty must be between -2.0 and 2.0".to_string());
+        }
+        if let (Some(Some(_)), Some(Some(schema))) = (&self.grammar, &self.schema) {
+            return Err(ValidationError::invalid(
+                "schema",
+                "unset when grammar is set",
+                schema,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Set `max_tokens`, failing immediately if it is zero
+    pub fn try_max_tokens<VALUE: Into<u32>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let max_tokens = value.into();
+        check_max_tokens(max_tokens)?;
+        Ok(self.max_tokens(max_tokens))
+    }
+
+    /// Set `stop`, failing immediately if there are more than 5 entries
+    pub fn try_stop<VALUE: Into<Vec<Arc<str>>>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let stop = value.into();
+        check_stop(&stop)?;
+        Ok(self.stop(stop))
+    }
+
+    /// Set `n`, failing immediately if it is out of range
+    pub fn try_n<VALUE: Into<u32>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let n = value.into();
+        check_n(n)?;
+        Ok(self.n(n))
+    }
+
+    /// Set `top_k`, failing immediately if it is out of range
+    pub fn try_top_k<VALUE: Into<u32>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let top_k = value.into();
+        check_top_k(top_k)?;
+        Ok(self.top_k(top_k))
+    }
+
+    /// Set `top_p`, failing immediately if it is out of range
+    pub fn try_top_p<VALUE: Into<f64>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let top_p = value.into();
+        check_top_p(top_p)?;
+        Ok(self.top_p(top_p))
+    }
+
+    /// Set `presence_penalty`, failing immediately if it is out of range
+    pub fn try_presence_penalty<VALUE: Into<f64>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let presence_penalty = value.into();
+        check_presence_penalty(presence_penalty)?;
+        Ok(self.presence_penalty(presence_penalty))
+    }
+
+    /// Set `frequency_penalty`, failing immediately if it is out of range
+    pub fn try_frequency_penalty<VALUE: Into<f64>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let frequency_penalty = value.into();
+        check_frequency_penalty(frequency_penalty)?;
+        Ok(self.frequency_penalty(frequency_penalty))
+    }
+
+    /// Set `logit_bias`, failing immediately if a bias is out of range
+    pub fn try_logit_bias<VALUE: Into<HashMap<String, f64>>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let logit_bias = value.into();
+        check_logit_bias(&logit_bias)?;
+        Ok(self.logit_bias(logit_bias))
+    }
+
+    /// Set `repetition_penalty`, failing immediately if it is not positive
+    pub fn try_repetition_penalty<VALUE: Into<f64>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let repetition_penalty = value.into();
+        check_repetition_penalty(repetition_penalty)?;
+        Ok(self.repetition_penalty(repetition_penalty))
+    }
+
+    /// Set `typical_p`, failing immediately if it is out of range
+    pub fn try_typical_p<VALUE: Into<f64>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let typical_p = value.into();
+        check_typical_p(typical_p)?;
+        Ok(self.typical_p(typical_p))
+    }
+
+    /// Set `top_logprobs`, failing immediately if it is out of range
+    pub fn try_top_logprobs<VALUE: Into<u32>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let top_logprobs = value.into();
+        check_top_logprobs(top_logprobs)?;
+        Ok(self.top_logprobs(top_logprobs))
+    }
+
+    /// Constrain the generated text, replacing any grammar or schema set
+    /// before
+    pub fn constraint(&mut self, constraint: Constraint) -> &mut Self {
+        match constraint {
+            Constraint::Grammar(grammar) => {
+                self.schema = None;
+                self.grammar(grammar)
             }
-            _ => {}
-        };
-        // typical_p: must be > 0 and <= 1\
-        match self.typical_p {
-            Some(Some(typical_p)) if !(typical_p > 0.0 && typical_p <= 1.0) => {
-                return Err("typical_p must be between 0.0 and 1.0".to_string());
+            Constraint::JsonSchema(schema) => {
+                self.grammar = None;
+                self.schema(schema)
             }
-            _ => {}
-        };
-        Ok(())
+        }
     }
 }
 
@@ -212,7 +634,8 @@ where
 }
 
 /// Struct for a completion answer
-#[derive(Deserialize, Debug)]
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ResponseChunk {
     /// The completed text.
     #[serde(deserialize_with = "string_or_seq_string")]
@@ -225,6 +648,35 @@ pub struct ResponseChunk {
     pub input_tokens: Option<u32>,
     /// Indicate the total number of generated tokens.
     pub output_tokens: Option<u32>,
+    /// Log probabilities of the tokens generated in this chunk, present if
+    /// the request set `logprobs`.
+    pub logprobs: Option<Vec<TokenLogprob>>,
+    /// Why the generation stopped, if the api reported it with the last
+    /// answer. See [`ResponseChunk::infer_finish_reason`] otherwise.
+    #[serde(default, deserialize_with = "finish::known_reason")]
+    pub finish_reason: Option<finish::FinishReason>,
+}
+
+/// Log probability of a generated token
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TokenLogprob {
+    /// The generated token.
+    pub token: String,
+    /// Log probability of the token.
+    pub logprob: f64,
+    /// Most likely alternatives for this position, as many as the request's
+    /// `top_logprobs`.
+    #[serde(default)]
+    pub top_logprobs: Vec<TopLogprob>,
+}
+
+/// Alternative token considered for a generated position
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TopLogprob {
+    /// The alternative token.
+    pub token: String,
+    /// Log probability of the alternative token.
+    pub logprob: f64,
 }
 
 #[derive(Error, Debug)]
@@ -236,13 +688,157 @@ pub enum Error {
     /// Error from Reqwest
     #[error("Reqwest error: {0}")]
     RequestError(#[from] reqwest::Error),
+    /// Error answer of the api
+    #[error("API error: {0}")]
+    ApiError(#[from] crate::ApiError),
+    /// The api rate limited the request
+    #[error("Rate limited: {0}")]
+    RateLimited(#[from] crate::RateLimited),
+    /// The api rejected the api key
+    #[error("Unauthorized: {0}")]
+    Unauthorized(#[from] crate::Unauthorized),
+    /// The api key isn't allowed to make the request
+    #[error("Forbidden: {0}")]
+    Forbidden(#[from] crate::Forbidden),
+    /// Successful answer that couldn't be parsed
+    #[error("HTTP error: {0}")]
+    Http(#[from] crate::HttpError),
+    /// The request couldn't be built
+    #[error("Invalid request: {0}")]
+    BuilderError(#[from] RequestBuilderError),
     /// Couldn't parse the response to completion
+    #[cfg(feature = "streaming")]
     #[error("Couldn't parse the response to completion")]
     ParseError(bytes::Bytes),
+    /// No data of the streamed completion arrived within the read timeout
+    #[cfg(feature = "streaming")]
+    #[error("No data received within {0:?}")]
+    StreamTimeout(std::time::Duration),
+    /// More bytes of the streamed completion arrived than allowed without
+    /// completing a chunk
+    #[cfg(feature = "streaming")]
+    #[error("More than {0} bytes received without a complete chunk")]
+    PendingBytesExceeded(usize),
+    /// Error from the logprob api
+    #[error("Logprob error: {0}")]
+    LogprobError(#[from] logprob::Error),
+    /// Error from the translate api
+    #[error("Translate error: {0}")]
+    TranslateError(#[from] crate::translate::Error),
+    /// Error from the tokenize api
+    #[error("Tokenize error: {0}")]
+    TokenizeError(#[from] crate::tokenize::Error),
+    /// Couldn't save a checkpoint of the completion
+    #[cfg(feature = "streaming")]
+    #[error("Couldn't save checkpoint: {0}")]
+    CheckpointError(std::io::Error),
+    /// The prompt and the generated tokens don't fit the context length of
+    /// the engine, see [`Request::check_context`]
+    #[error(
+        "Prompt of {prompt_tokens} tokens and up to {max_tokens} generated tokens exceed the context length of {context_length} tokens"
+    )]
+    ContextLengthExceeded {
+        /// Number of tokens of the prompt
+        prompt_tokens: u32,
+        /// Maximum number of generated tokens
+        max_tokens: u32,
+        /// Context length of the engine
+        context_length: u32,
+    },
 }
 
 impl TextSynthClient {
+    /// Perform a completion request and wait for the complete answer. The
+    /// `stream` option of the request is ignored.
+    pub async fn complete(
+        &self,
+        engine: &Engine,
+        request: &Request,
+    ) -> Result<ResponseChunk, Error> {
+        Ok(self.complete_with_meta(engine, request).await?.response)
+    }
+
+    /// Complete `prompt` generating up to `max_tokens` tokens, returning the
+    /// generated text
+    pub async fn complete_str(
+        &self,
+        engine: &Engine,
+        prompt: &str,
+        max_tokens: u32,
+    ) -> Result<String, Error> {
+        let request = RequestBuilder::default()
+            .prompt(prompt)
+            .max_tokens(max_tokens)
+            .build()?;
+        Ok(self.complete(engine, &request).await?.text.concat())
+    }
+
+    /// Perform a completion request and wait for the complete answer, keeping
+    /// the metadata of the response. The `stream` option of the request is
+    /// ignored.
+    pub async fn complete_with_meta(
+        &self,
+        engine: &Engine,
+        request: &Request,
+    ) -> Result<WithMeta<ResponseChunk>, Error> {
+        let mut request_json = serde_json::to_value(request)?;
+        if let Some(request_json) = request_json.as_object_mut() {
+            request_json.remove("stream");
+        }
+        let request_json = serde_json::to_string(&request_json)?;
+        let url = format!("{}/engines/{}/completions", self.base_url, engine);
+        let response = self
+            .post_with_meta::<ResponseChunk, Error>(&url, request_json)
+            .await?;
+        record_tokens(
+            self.usage.as_ref(),
+            engine,
+            response.response.input_tokens,
+            response.response.output_tokens,
+        );
+        Ok(response)
+    }
+
+    /// Perform a completion request given as raw json and wait for the
+    /// complete raw json answer. The `stream` option of the request is
+    /// ignored.
+    pub async fn completions_raw(
+        &self,
+        engine: &Engine,
+        request: &serde_json::Value,
+    ) -> Result<serde_json::Value, Error> {
+        let mut request = request.clone();
+        if let Some(request) = request.as_object_mut() {
+            request.remove("stream");
+        }
+        let request_json = serde_json::to_string(&request)?;
+        let url = format!("{}/engines/{}/completions", self.base_url, engine);
+        let response: serde_json::Value = self
+            .post_with_meta::<_, Error>(&url, request_json)
+            .await?
+            .response;
+        let tokens = |name: &str| {
+            response
+                .get(name)
+                .and_then(serde_json::Value::as_u64)
+                .and_then(|tokens| u32::try_from(tokens).ok())
+        };
+        record_tokens(
+            self.usage.as_ref(),
+            engine,
+            tokens("input_tokens"),
+            tokens("output_tokens"),
+        );
+        Ok(response)
+    }
+
     /// Perform a completion request
+    ///
+    /// Dropping the returned stream aborts the request: the HTTP connection
+    /// is closed right away, even while waiting for the next chunk, and the
+    /// generation stops being billed. The connection is also closed as soon
+    /// as the stream times out.
+    #[cfg(feature = "streaming")]
     pub async fn completions(
         &self,
         engine: &Engine,
@@ -250,63 +846,412 @@ impl TextSynthClient {
     ) -> Result<impl Stream<Item = Result<ResponseChunk, Error>>, Error> {
         let request_json = serde_json::to_string(&request)?;
         let url = format!("{}/engines/{}/completions", self.base_url, engine);
-        let response = self.client.post(&url).body(request_json).send().await?;
+        let response = self
+            .send_checked::<Error>(self.client.post(&url).body(request_json))
+            .await?;
+        let chunks = json_stream(
+            response,
+            self.stream_read_timeout,
+            self.stream_max_pending_bytes,
+            Error::ParseError,
+            Error::StreamTimeout,
+            Error::PendingBytesExceeded,
+        );
+        Ok(prefetch(chunks, self.stream_prefetch)
+            .inspect(record_chunk_tokens(self.usage.clone(), engine)))
+    }
+
+    /// Perform a streaming completion request like
+    /// [`TextSynthClient::completions`] that can be cancelled with the
+    /// returned handle
+    ///
+    /// Aborting ends the stream and drops the HTTP connection right away,
+    /// even while waiting for the next chunk, so the generation stops being
+    /// billed as early as possible.
+    #[cfg(feature = "streaming")]
+    pub async fn completions_abortable(
+        &self,
+        engine: &Engine,
+        request: &Request,
+    ) -> Result<
+        (
+            impl Stream<Item = Result<ResponseChunk, Error>>,
+            AbortHandle,
+        ),
+        Error,
+    > {
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        let inner = Abortable::new(self.completions(engine, request).await?, abort_registration);
+        // the connection is dropped together with the state once the stream ends
+        let response_stream = stream::unfold(inner, |mut inner| async move {
+            let chunk = inner.next().await?;
+            Some((chunk, inner))
+        });
+        Ok((Box::pin(response_stream), abort_handle))
+    }
+}
+
+/// Record the tokens reported by the last chunk of a stream of `engine`
+#[cfg(feature = "streaming")]
+pub(crate) fn record_chunk_tokens<E>(
+    usage: Option<crate::usage::UsageRecorder>,
+    engine: &dyn fmt::Display,
+) -> impl FnMut(&Result<ResponseChunk, E>) {
+    let engine = engine.to_string();
+    move |chunk| {
+        if let Ok(chunk) = chunk {
+            if chunk.reached_end {
+                record_tokens(
+                    usage.as_ref(),
+                    &engine,
+                    chunk.input_tokens,
+                    chunk.output_tokens,
+                );
+            }
+        }
+    }
+}
+
+/// Text and token counts of a streamed completion, see [`collect_text`]
+#[cfg(feature = "streaming")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CollectedText {
+    /// The generated text of all chunks, concatenated.
+    pub text: String,
+    /// If true, the stream ended with the last answer of the generation.
+    pub reached_end: bool,
+    /// If true, the prompt was truncated because it was too large.
+    pub truncated_prompt: bool,
+    /// The number of input tokens, if the api reported it.
+    pub input_tokens: Option<u32>,
+    /// The total number of generated tokens, if the api reported it.
+    pub output_tokens: Option<u32>,
+}
+
+/// Drive a completion stream to its end, concatenating the text of its chunks
+///
+/// Fails with the first error of the stream. The completions of requests
+/// with `n` set are collected separately by [`choices::collect_choices`].
+#[cfg(feature = "streaming")]
+pub async fn collect_text<S>(stream: S) -> Result<CollectedText, Error>
+where
+    S: Stream<Item = Result<ResponseChunk, Error>>,
+{
+    futures::pin_mut!(stream);
+    let mut collected = CollectedText::default();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        for text in &chunk.text {
+            collected.text.push_str(text);
+        }
+        collected.reached_end |= chunk.reached_end;
+        collected.truncated_prompt |= chunk.truncated_prompt.unwrap_or(false);
+        collected.input_tokens = chunk.input_tokens.or(collected.input_tokens);
+        collected.output_tokens = chunk.output_tokens.or(collected.output_tokens);
+    }
+    Ok(collected)
+}
+
+/// How the values of a stream are separated
+#[cfg(any(feature = "streaming", feature = "blocking"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Framing {
+    /// Concatenated json values
+    Json,
+    /// Server-Sent Events with the json values in their `data:` fields
+    Sse,
+}
+
+/// Fields starting the lines of Server-Sent Events
+#[cfg(any(feature = "streaming", feature = "blocking"))]
+const SSE_FIELDS: [&[u8]; 5] = [b"data:", b"event:", b"id:", b"retry:", b":"];
+
+/// Framing of a stream starting with `buffer`, or None until enough of it
+/// arrived to tell
+#[cfg(any(feature = "streaming", feature = "blocking"))]
+pub(crate) fn detect_framing(buffer: &[u8]) -> Option<Framing> {
+    let start = buffer.iter().position(|byte| !byte.is_ascii_whitespace())?;
+    let buffer = &buffer[start..];
+    if SSE_FIELDS.iter().any(|field| buffer.starts_with(field)) {
+        Some(Framing::Sse)
+    } else if SSE_FIELDS.iter().any(|field| field.starts_with(buffer)) {
+        None
+    } else {
+        Some(Framing::Json)
+    }
+}
+
+/// Parse the json values read from `reader`, of Server-Sent Events if
+/// `event_stream` is set or the first bytes read look like them
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+pub(crate) fn read_values<T: DeserializeOwned + Send + 'static>(
+    mut reader: impl BufRead + Send + 'static,
+    event_stream: bool,
+) -> serde_json::Result<Box<dyn Iterator<Item = serde_json::Result<T>> + Send>> {
+    let framing = if event_stream {
+        Some(Framing::Sse)
+    } else {
+        detect_framing(reader.fill_buf().map_err(serde_json::Error::io)?)
+    };
+    Ok(match framing {
+        Some(Framing::Sse) => Box::new(sse_values(reader)),
+        _ => Box::new(serde_json::Deserializer::from_reader(reader).into_iter()),
+    })
+}
 
-        struct StreamState<S> {
-            inner: S,
-            chunks: BytesMut,
+/// Parse the json values in the `data:` fields of the Server-Sent Events read
+/// from `reader`, ending at a `[DONE]` event
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+fn sse_values<T: DeserializeOwned>(
+    reader: impl BufRead,
+) -> impl Iterator<Item = serde_json::Result<T>> {
+    let mut lines = reader.split(b'\n');
+    let mut data: Option<Vec<u8>> = None;
+    let mut done = false;
+    std::iter::from_fn(move || loop {
+        if done {
+            return None;
         }
-        let state = StreamState {
-            inner: response.bytes_stream(),
-            chunks: BytesMut::new(),
+        let line = match lines.next() {
+            Some(Ok(line)) => Some(line),
+            Some(Err(err)) => {
+                done = true;
+                return Some(Err(serde_json::Error::io(err)));
+            }
+            None => {
+                done = true;
+                None
+            }
         };
-        let response_stream = stream::unfold(state, |mut state| async move {
-            loop {
-                if let Some(chunk) = state.inner.next().await {
-                    let chunk = match chunk {
-                        Ok(chunk) => chunk,
-                        Err(err) => break Some((Err(err.into()), state)),
-                    };
-                    state.chunks.extend_from_slice(&chunk);
-                    // stream parse
-                    let mut stream = serde_json::Deserializer::from_slice(&state.chunks)
-                        .into_iter::<ResponseChunk>();
-                    // get next chunk
-                    let next = Iterator::next(&mut stream);
-                    // println!("Next: {:?}", next);
-                    if let Some(Ok(chunk)) = next {
+        let line = line
+            .as_deref()
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line));
+        match line {
+            Some(line) if !line.is_empty() => {
+                if let Some(value) = line.strip_prefix(b"data:") {
+                    let value = value.strip_prefix(b" ").unwrap_or(value);
+                    match &mut data {
+                        Some(data) => {
+                            data.push(b'\n');
+                            data.extend_from_slice(value);
+                        }
+                        None => data = Some(value.to_vec()),
+                    }
+                }
+            }
+            // a blank line or the end of the stream dispatches the event
+            _ => match data.take() {
+                Some(data) if data == b"[DONE]" => {
+                    done = true;
+                    return None;
+                }
+                Some(data) => return Some(serde_json::from_slice(&data)),
+                None => {}
+            },
+        }
+    })
+}
+
+/// Split the first complete event off `buffer`, returning its data joined
+/// by newlines, or None as data of events without `data:` fields
+#[cfg(feature = "streaming")]
+fn split_event(buffer: &mut BytesMut) -> Option<Option<Vec<u8>>> {
+    let mut data: Option<Vec<u8>> = None;
+    let mut start = 0;
+    while let Some(length) = buffer[start..].iter().position(|&byte| byte == b'\n') {
+        let line = &buffer[start..start + length];
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        start += length + 1;
+        if line.is_empty() {
+            buffer.advance(start);
+            return Some(data);
+        }
+        if let Some(value) = line.strip_prefix(b"data:") {
+            let value = value.strip_prefix(b" ").unwrap_or(value);
+            match &mut data {
+                Some(data) => {
+                    data.push(b'\n');
+                    data.extend_from_slice(value);
+                }
+                None => data = Some(value.to_vec()),
+            }
+        }
+    }
+    None
+}
+
+/// Read `stream` ahead of the consumer on a task, buffering up to `chunks`
+/// items, or only when polled if `chunks` is 0
+///
+/// The task stops reading once the buffer is full, so a slow consumer slows
+/// down the server instead of growing the buffer. Dropping the returned
+/// stream stops the task and drops `stream` right away.
+#[cfg(feature = "streaming")]
+pub(crate) fn prefetch<S>(stream: S, chunks: usize) -> impl Stream<Item = S::Item>
+where
+    S: Stream + Send + 'static,
+    S::Item: Send + Sync + 'static,
+{
+    if chunks == 0 {
+        return stream.left_stream();
+    }
+    let (sender, receiver) = tokio::sync::mpsc::channel(chunks);
+    tokio::spawn(async move {
+        futures::pin_mut!(stream);
+        loop {
+            let closed = sender.closed();
+            futures::pin_mut!(closed);
+            let item = match future::select(closed, stream.next()).await {
+                // the consumer dropped the stream
+                Either::Left(_) => break,
+                Either::Right((item, _)) => item,
+            };
+            let Some(item) = item else { break };
+            if sender.send(item).await.is_err() {
+                break;
+            }
+        }
+    });
+    Box::pin(stream::unfold(receiver, |mut receiver| async move {
+        let item = receiver.recv().await?;
+        Some((item, receiver))
+    }))
+    .right_stream()
+}
+
+/// Parse the json values streamed in `response`, failing with `parse_error`
+/// on trailing data that isn't a complete value, ending with `timeout_error`
+/// if no data arrives within `read_timeout` and with `overflow_error` if more
+/// than `max_pending_bytes` arrive without completing a value
+///
+/// The values may also be framed as Server-Sent Events, detected from the
+/// content type or the start of the stream, which ends at a `[DONE]` event.
+#[cfg(feature = "streaming")]
+pub(crate) fn json_stream<T, E>(
+    response: reqwest::Response,
+    read_timeout: Option<Duration>,
+    max_pending_bytes: Option<usize>,
+    parse_error: fn(Bytes) -> E,
+    timeout_error: fn(Duration) -> E,
+    overflow_error: fn(usize) -> E,
+) -> impl Stream<Item = Result<T, E>>
+where
+    T: de::DeserializeOwned + 'static,
+    E: From<reqwest::Error> + 'static,
+{
+    // the response is owned by the state of the stream, so dropping the
+    // stream drops the connection at once and the api stops generating
+    struct StreamState<S> {
+        /// The body of the response, none once the connection was dropped
+        inner: Option<S>,
+        chunks: BytesMut,
+        framing: Option<Framing>,
+        ended: bool,
+        timed_out: bool,
+    }
+    let event_stream = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("text/event-stream"));
+    let state = StreamState {
+        inner: Some(response.bytes_stream()),
+        chunks: BytesMut::new(),
+        framing: event_stream.then_some(Framing::Sse),
+        ended: false,
+        timed_out: false,
+    };
+    let response_stream = stream::unfold(state, move |mut state| async move {
+        loop {
+            if state.timed_out {
+                break None;
+            }
+            if state.framing.is_none() {
+                state.framing = detect_framing(&state.chunks);
+            }
+            match state.framing {
+                Some(Framing::Sse) => match split_event(&mut state.chunks) {
+                    Some(Some(data)) if data == b"[DONE]" => break None,
+                    Some(Some(data)) => match serde_json::from_slice(&data) {
+                        Ok(chunk) => break Some((Ok(chunk), state)),
+                        Err(_) => break Some((Err(parse_error(data.into())), state)),
+                    },
+                    // events without data, e.g. keep-alive comments
+                    Some(None) => continue,
+                    None => {}
+                },
+                Some(Framing::Json) => {
+                    // a single read may hold several values, parse buffered ones first
+                    let mut stream =
+                        serde_json::Deserializer::from_slice(&state.chunks).into_iter::<T>();
+                    if let Some(Ok(chunk)) = Iterator::next(&mut stream) {
                         // remove parsed chunk from buffer
                         state.chunks.advance(stream.byte_offset());
                         // remove leading whitespace from buffer
-                        let mut i = 0;
-                        while i < state.chunks.len() {
-                            if state.chunks[i].is_ascii_whitespace() {
-                                i += 1;
-                            } else {
-                                break;
-                            }
-                        }
-                        state.chunks.advance(i);
+                        let whitespace = state
+                            .chunks
+                            .iter()
+                            .take_while(|byte| byte.is_ascii_whitespace())
+                            .count();
+                        state.chunks.advance(whitespace);
                         break Some((Ok(chunk), state));
                     }
-                } else {
-                    // end of stream
-                    // if there is some data in the buffer (that isn't whitespace), return error
-                    if state.chunks.is_empty() {
-                        break None;
-                    } else {
-                        // return error
-                        break Some((
-                            Err(Error::ParseError(state.chunks.freeze())),
-                            StreamState {
-                                chunks: BytesMut::new(),
-                                ..state
-                            },
-                        ));
+                }
+                None => {}
+            }
+            // every complete value was parsed, only the remainder is pending
+            if let Some(max) = max_pending_bytes.filter(|max| state.chunks.len() > *max) {
+                break Some((
+                    Err(overflow_error(max)),
+                    StreamState {
+                        inner: None,
+                        chunks: BytesMut::new(),
+                        ended: true,
+                        ..state
+                    },
+                ));
+            }
+            let next = match (&mut state.inner, read_timeout) {
+                _ if state.ended => None,
+                (None, _) => None,
+                (Some(inner), Some(read_timeout)) => {
+                    match tokio::time::timeout(read_timeout, inner.next()).await {
+                        Ok(next) => next,
+                        Err(_) => {
+                            // close the connection now rather than with the stream
+                            state.inner = None;
+                            state.timed_out = true;
+                            break Some((Err(timeout_error(read_timeout)), state));
+                        }
                     }
                 }
+                (Some(inner), None) => inner.next().await,
+            };
+            if let Some(chunk) = next {
+                match chunk {
+                    Ok(chunk) => state.chunks.extend_from_slice(&chunk),
+                    Err(err) => break Some((Err(err.into()), state)),
+                }
+            } else if state.chunks.iter().all(u8::is_ascii_whitespace) {
+                // end of stream
+                break None;
+            } else if state.framing == Some(Framing::Sse) && !state.ended {
+                // dispatch the last event even without its terminating blank line
+                state.ended = true;
+                state.chunks.extend_from_slice(b"\n\n");
+            } else {
+                // return error on data in the buffer that isn't whitespace
+                break Some((
+                    Err(parse_error(state.chunks.freeze())),
+                    StreamState {
+                        inner: None,
+                        chunks: BytesMut::new(),
+                        ended: true,
+                        ..state
+                    },
+                ));
             }
-        });
-        Ok(Box::pin(response_stream))
-    }
+        }
+    });
+    Box::pin(response_stream)
 }
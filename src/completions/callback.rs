@@ -0,0 +1,41 @@
+//! Provides streamed completions passed to a callback
+//!
+//! [`TextSynthClient::completions_with`] drives the stream itself and calls
+//! back with every text delta, so callers don't deal with `Stream` and
+//! pinning, e.g. when bridging to synchronous code.
+
+use futures::StreamExt;
+
+use crate::TextSynthClient;
+
+use super::{choices::Completions, Engine, Error, Request};
+
+impl TextSynthClient {
+    /// Perform a streaming completion request, calling `on_delta` with the
+    /// text generated by every chunk, and return all completions once the
+    /// generation ended
+    ///
+    /// Empty deltas are skipped. With `n` set, the deltas of the completions
+    /// are passed in turn, use the choices of the result to tell them apart.
+    /// Fails with the first error of the stream.
+    pub async fn completions_with<F>(
+        &self,
+        engine: &Engine,
+        request: &Request,
+        mut on_delta: F,
+    ) -> Result<Completions, Error>
+    where
+        F: FnMut(&str),
+    {
+        let mut stream = self.completions(engine, request).await?;
+        let mut completions = Completions::default();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            for delta in chunk.text.iter().filter(|delta| !delta.is_empty()) {
+                on_delta(delta);
+            }
+            completions.push(chunk);
+        }
+        Ok(completions)
+    }
+}
@@ -0,0 +1,133 @@
+//! Provides checkpointing of streamed completions so long generations can be
+//! resumed after a restart
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::TextSynthClient;
+
+use super::{Engine, Error, Request, ResponseChunk};
+
+/// Progress of a streamed completion
+///
+/// Only the first completion is tracked when `n` is larger than 1.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Checkpoint {
+    /// Prompt of the checkpointed request.
+    pub prompt: String,
+    /// Text generated so far.
+    pub generated: String,
+    /// Number of chunks received so far.
+    pub chunks: u32,
+    /// Number of tokens generated so far, as reported by the api or else
+    /// counted as one per chunk.
+    pub output_tokens: u32,
+    /// If true, the generation is complete and there is nothing to resume.
+    pub reached_end: bool,
+}
+
+impl Checkpoint {
+    /// Request continuing the generation: `request` prompted with the
+    /// checkpointed prompt and generated text, with `max_tokens` reduced by
+    /// the tokens generated so far.
+    pub fn resume(&self, request: &Request) -> Request {
+        let mut request = request.clone();
+        request.prompt = format!("{}{}", self.prompt, self.generated).into();
+        request.max_tokens = request
+            .max_tokens
+            .map(|max_tokens| max_tokens.saturating_sub(self.output_tokens).max(1));
+        request
+    }
+}
+
+/// Storage for the latest checkpoint of a generation
+pub trait CheckpointStore {
+    /// Persist `checkpoint`, replacing the previous one.
+    fn save(&mut self, checkpoint: &Checkpoint) -> io::Result<()>;
+    /// Load the latest checkpoint, if any.
+    fn load(&self) -> io::Result<Option<Checkpoint>>;
+}
+
+impl<S: CheckpointStore + ?Sized> CheckpointStore for &mut S {
+    fn save(&mut self, checkpoint: &Checkpoint) -> io::Result<()> {
+        (**self).save(checkpoint)
+    }
+
+    fn load(&self) -> io::Result<Option<Checkpoint>> {
+        (**self).load()
+    }
+}
+
+/// Stores the checkpoint as a JSON file
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    path: PathBuf,
+}
+
+impl FileStore {
+    /// Store checkpoints at `path`
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        FileStore {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl CheckpointStore for FileStore {
+    fn save(&mut self, checkpoint: &Checkpoint) -> io::Result<()> {
+        // write next to the target and rename, so a crash never leaves a
+        // partially written checkpoint behind
+        let partial = self.path.with_extension("partial");
+        fs::write(&partial, serde_json::to_vec(checkpoint)?)?;
+        fs::rename(&partial, &self.path)
+    }
+
+    fn load(&self) -> io::Result<Option<Checkpoint>> {
+        match fs::read(&self.path) {
+            Ok(checkpoint) => Ok(Some(serde_json::from_slice(&checkpoint)?)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl TextSynthClient {
+    /// Perform a streaming completion request, saving a checkpoint to
+    /// `store` every `every` chunks and when the generation ends. Resume an
+    /// interrupted generation with [`Checkpoint::resume`].
+    pub async fn completions_with_checkpoints(
+        &self,
+        engine: &Engine,
+        request: &Request,
+        mut store: impl CheckpointStore,
+        every: u32,
+    ) -> Result<impl Stream<Item = Result<ResponseChunk, Error>>, Error> {
+        let every = every.max(1);
+        let mut checkpoint = Checkpoint {
+            prompt: request.prompt.to_string(),
+            ..Checkpoint::default()
+        };
+        let mut unsaved = 0;
+        let response = self.completions(engine, request).await?;
+        Ok(response.map(move |chunk| {
+            let chunk = chunk?;
+            if let Some(text) = chunk.text.first() {
+                checkpoint.generated.push_str(text);
+            }
+            checkpoint.chunks += 1;
+            checkpoint.output_tokens = chunk.output_tokens.unwrap_or(checkpoint.chunks);
+            checkpoint.reached_end = chunk.reached_end;
+            unsaved += 1;
+            if chunk.reached_end || unsaved == every {
+                store.save(&checkpoint).map_err(Error::CheckpointError)?;
+                unsaved = 0;
+            }
+            Ok(chunk)
+        }))
+    }
+}
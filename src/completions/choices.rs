@@ -0,0 +1,102 @@
+//! Provides the completions of requests generating several of them
+//!
+//! With `n` set, the api generates several completions of the same prompt,
+//! returned as one entry of [`ResponseChunk::text`] each. [`Completions`]
+//! holds them as separate [`Choice`]s instead.
+
+#[cfg(feature = "streaming")]
+use futures::{Stream, StreamExt};
+
+use crate::TextSynthClient;
+
+use super::{finish::FinishReason, Engine, Error, Request, ResponseChunk};
+
+/// One of the completions of a request
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Choice {
+    /// Index of the completion, below the request's `n`.
+    pub index: usize,
+    /// The generated text.
+    pub text: String,
+}
+
+/// All completions of a request, with the token counts of the answer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Completions {
+    /// One choice per completion, in the order of their index.
+    pub choices: Vec<Choice>,
+    /// If true, the generation ended.
+    pub reached_end: bool,
+    /// If true, the prompt was truncated because it was too large.
+    pub truncated_prompt: bool,
+    /// The number of input tokens, if the api reported it.
+    pub input_tokens: Option<u32>,
+    /// The total number of generated tokens, if the api reported it.
+    pub output_tokens: Option<u32>,
+    /// Why the generation stopped, if the api reported it.
+    pub finish_reason: Option<FinishReason>,
+}
+
+impl Completions {
+    /// Append the texts of the streamed `chunk` to the choices
+    pub fn push(&mut self, chunk: ResponseChunk) {
+        if self.choices.len() < chunk.text.len() {
+            let missing = self.choices.len()..chunk.text.len();
+            self.choices.extend(missing.map(|index| Choice {
+                index,
+                text: String::new(),
+            }));
+        }
+        for (choice, text) in self.choices.iter_mut().zip(chunk.text) {
+            choice.text.push_str(&text);
+        }
+        self.reached_end |= chunk.reached_end;
+        self.truncated_prompt |= chunk.truncated_prompt.unwrap_or(false);
+        self.input_tokens = chunk.input_tokens.or(self.input_tokens);
+        self.output_tokens = chunk.output_tokens.or(self.output_tokens);
+        self.finish_reason = chunk.finish_reason.or(self.finish_reason);
+    }
+
+    /// The generated texts, in the order of their index
+    pub fn texts(&self) -> impl Iterator<Item = &str> {
+        self.choices.iter().map(|choice| choice.text.as_str())
+    }
+}
+
+impl From<ResponseChunk> for Completions {
+    fn from(response: ResponseChunk) -> Self {
+        let mut completions = Completions::default();
+        completions.push(response);
+        completions
+    }
+}
+
+/// Drive a completion stream to its end, collecting the text of every
+/// completion separately
+///
+/// Fails with the first error of the stream.
+#[cfg(feature = "streaming")]
+pub async fn collect_choices<S>(stream: S) -> Result<Completions, Error>
+where
+    S: Stream<Item = Result<ResponseChunk, Error>>,
+{
+    futures::pin_mut!(stream);
+    let mut completions = Completions::default();
+    while let Some(chunk) = stream.next().await {
+        completions.push(chunk?);
+    }
+    Ok(completions)
+}
+
+impl TextSynthClient {
+    /// Perform a completion request like [`TextSynthClient::complete`],
+    /// returning every completion of a request with `n` set as its own
+    /// choice
+    pub async fn complete_choices(
+        &self,
+        engine: &Engine,
+        request: &Request,
+    ) -> Result<Completions, Error> {
+        Ok(self.complete(engine, request).await?.into())
+    }
+}
@@ -0,0 +1,78 @@
+//! Provides fitting prompts into the context length of an engine
+
+use crate::TextSynthClient;
+
+use super::{Engine, Error, Request};
+
+/// Number of tokens the api generates if the request doesn't set `max_tokens`
+const DEFAULT_MAX_TOKENS: u32 = 100;
+
+impl Request {
+    /// Check that the prompt and `max_tokens` generated tokens fit the
+    /// context length of `engine` before sending the request, failing with
+    /// [`Error::ContextLengthExceeded`] instead
+    ///
+    /// The prompt is tokenized with the tokenize api of `engine`. Nothing is
+    /// checked if the context length of the engine is unknown.
+    pub async fn check_context(
+        &self,
+        client: &TextSynthClient,
+        engine: &Engine,
+    ) -> Result<(), Error> {
+        let context_length = match engine.max_context_tokens() {
+            Some(context_length) => context_length,
+            None => return Ok(()),
+        };
+        let max_tokens = self.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+        let prompt_tokens = client.tokenize_str(engine, &self.prompt).await?.len() as u32;
+        if prompt_tokens.saturating_add(max_tokens) > context_length {
+            return Err(Error::ContextLengthExceeded {
+                prompt_tokens,
+                max_tokens,
+                context_length,
+            });
+        }
+        Ok(())
+    }
+
+    /// This request with the prompt trimmed from the front so that the prompt
+    /// and `max_tokens` generated tokens fit the context length of `engine`
+    ///
+    /// The prompt is tokenized with the tokenize api of `engine`. The request
+    /// is returned unchanged if it already fits or the context length of the
+    /// engine is unknown.
+    pub async fn fit_to_context(
+        &self,
+        client: &TextSynthClient,
+        engine: &Engine,
+    ) -> Result<Request, Error> {
+        let context_length = match engine.context_length() {
+            Some(context_length) => context_length,
+            None => return Ok(self.clone()),
+        };
+        let max_tokens = self.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+        let budget = context_length.saturating_sub(max_tokens) as usize;
+        let tokens = client.tokenize_with_content(engine, &self.prompt).await?;
+        if tokens.len() <= budget {
+            return Ok(self.clone());
+        }
+        let bytes: Vec<u8> = tokens[tokens.len() - budget..]
+            .iter()
+            .flat_map(|token| token.content.iter().copied())
+            .collect();
+        Ok(Request {
+            prompt: skip_partial_char(&bytes).into(),
+            ..self.clone()
+        })
+    }
+}
+
+/// `bytes` as text, without the continuation bytes of a character cut off at
+/// the start
+fn skip_partial_char(bytes: &[u8]) -> String {
+    let start = bytes
+        .iter()
+        .position(|&byte| byte & 0b1100_0000 != 0b1000_0000)
+        .unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[start..]).into_owned()
+}
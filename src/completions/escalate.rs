@@ -0,0 +1,120 @@
+//! Provides escalation of completions from cheap to larger engines
+
+use crate::TextSynthClient;
+
+use super::{logprob, Engine, Error, Request, ResponseChunk};
+
+/// How the confidence in a completion is judged
+#[derive(Debug, Clone, PartialEq)]
+pub enum Confidence {
+    /// Confident if at least this many tokens were generated.
+    MinOutputTokens(u32),
+    /// Confident if the average log probability per generated token, as
+    /// scored by the logprob api of the engine that generated it, is at least
+    /// this value.
+    MinAverageLogprob(f64),
+}
+
+/// Runs a completion on each tier in turn until the answer is confident
+#[derive(Debug, Clone)]
+pub struct EscalationPolicy {
+    tiers: Vec<Engine>,
+    confidence: Confidence,
+}
+
+impl EscalationPolicy {
+    /// Start with `engine`, judging answers by `confidence`
+    pub fn new(engine: Engine, confidence: Confidence) -> Self {
+        EscalationPolicy {
+            tiers: vec![engine],
+            confidence,
+        }
+    }
+
+    /// Escalate to `engine` when the previous tiers were not confident
+    pub fn then(mut self, engine: Engine) -> Self {
+        self.tiers.push(engine);
+        self
+    }
+
+    /// The engines in the order they are tried
+    pub fn tiers(&self) -> &[Engine] {
+        &self.tiers
+    }
+}
+
+/// Completion answered by an escalation
+#[derive(Debug)]
+pub struct Escalated {
+    /// The answer.
+    pub response: ResponseChunk,
+    /// Engine that answered.
+    pub engine: Engine,
+    /// Index of the tier that answered.
+    pub tier: usize,
+    /// `Some(true)` if the answer was judged confident, `None` if it is the
+    /// answer of the last tier, which is returned without being judged, so
+    /// it isn't scored with the logprob api.
+    pub confident: Option<bool>,
+}
+
+impl TextSynthClient {
+    /// Complete `request` on the first tier of `policy`, re-running it on the
+    /// next tier while the answer is not confident.
+    pub async fn complete_escalating(
+        &self,
+        policy: &EscalationPolicy,
+        request: &Request,
+    ) -> Result<Escalated, Error> {
+        let last = policy.tiers.len() - 1;
+        for (tier, engine) in policy.tiers.iter().enumerate() {
+            let response = self.complete(engine, request).await?;
+            // judging the last answer would change nothing, and might cost a
+            // logprob request
+            let confident = if tier == last {
+                None
+            } else {
+                Some(
+                    self.is_confident(&policy.confidence, engine, request, &response)
+                        .await?,
+                )
+            };
+            if confident != Some(false) {
+                return Ok(Escalated {
+                    response,
+                    engine: engine.clone(),
+                    tier,
+                    confident,
+                });
+            }
+        }
+        unreachable!("escalation policies have at least one tier")
+    }
+
+    async fn is_confident(
+        &self,
+        confidence: &Confidence,
+        engine: &Engine,
+        request: &Request,
+        response: &ResponseChunk,
+    ) -> Result<bool, Error> {
+        match confidence {
+            Confidence::MinOutputTokens(min_tokens) => {
+                Ok(response.output_tokens.unwrap_or(0) >= *min_tokens)
+            }
+            Confidence::MinAverageLogprob(min_logprob) => {
+                let continuation = match response.text.first() {
+                    Some(text) if !text.is_empty() => text,
+                    _ => return Ok(false),
+                };
+                let scored = logprob::RequestBuilder::default()
+                    .context(&*request.prompt)
+                    .continuation(continuation.as_str())
+                    .build()?;
+                let scored = self.logprob(engine, &scored).await?;
+                let average = scored.logprob / f64::from(scored.num_tokens.max(1));
+                Ok(average >= *min_logprob)
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! Provides streamed completions as events instead of chunks
+//!
+//! A [`ResponseChunk`] carries generated text, token counts and warnings in
+//! optional fields. [`events`] turns every chunk into the [`StreamEvent`]s it
+//! stands for, so consumers match on what happened instead.
+
+use futures::{stream, Stream, StreamExt};
+
+use crate::TextSynthClient;
+
+use super::{Engine, Error, Request, ResponseChunk, TokenLogprob};
+
+/// Something that happened during a streamed completion
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// Text generated since the previous delta.
+    Delta {
+        /// The new text of every completion, one per completion of requests
+        /// with `n` set.
+        text: Vec<String>,
+        /// Log probabilities of the generated tokens, present if the request
+        /// set `logprobs`.
+        logprobs: Option<Vec<TokenLogprob>>,
+    },
+    /// Something the application may want to tell the user about.
+    Warning(Warning),
+    /// The generation ended. A stream ending without it was cut off.
+    Final {
+        /// The number of input tokens, if the api reported it.
+        input_tokens: Option<u32>,
+        /// The total number of generated tokens, if the api reported it.
+        output_tokens: Option<u32>,
+    },
+}
+
+/// Warning about a streamed completion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// The prompt was truncated because it was too large.
+    TruncatedPrompt,
+}
+
+impl StreamEvent {
+    /// The events `chunk` stands for: a warning about a truncated prompt, the
+    /// text generated if any, then the end of the generation
+    pub fn from_chunk(chunk: ResponseChunk) -> Vec<StreamEvent> {
+        let mut events = Vec::new();
+        if chunk.truncated_prompt == Some(true) {
+            events.push(StreamEvent::Warning(Warning::TruncatedPrompt));
+        }
+        if chunk.text.iter().any(|text| !text.is_empty()) || chunk.logprobs.is_some() {
+            events.push(StreamEvent::Delta {
+                text: chunk.text,
+                logprobs: chunk.logprobs,
+            });
+        }
+        if chunk.reached_end {
+            events.push(StreamEvent::Final {
+                input_tokens: chunk.input_tokens,
+                output_tokens: chunk.output_tokens,
+            });
+        }
+        events
+    }
+}
+
+/// The events of the chunks of `stream`, each warning yielded once
+pub fn events<S>(stream: S) -> impl Stream<Item = Result<StreamEvent, Error>>
+where
+    S: Stream<Item = Result<ResponseChunk, Error>>,
+{
+    let mut warned = Vec::new();
+    stream.flat_map(move |chunk| {
+        let events: Vec<_> = match chunk {
+            Ok(chunk) => StreamEvent::from_chunk(chunk)
+                .into_iter()
+                .filter(|event| match event {
+                    StreamEvent::Warning(warning) if warned.contains(warning) => false,
+                    StreamEvent::Warning(warning) => {
+                        warned.push(*warning);
+                        true
+                    }
+                    _ => true,
+                })
+                .map(Ok)
+                .collect(),
+            Err(err) => vec![Err(err)],
+        };
+        stream::iter(events)
+    })
+}
+
+impl TextSynthClient {
+    /// Perform a streaming completion request like
+    /// [`TextSynthClient::completions`], yielding [`StreamEvent`]s
+    pub async fn completions_events(
+        &self,
+        engine: &Engine,
+        request: &Request,
+    ) -> Result<impl Stream<Item = Result<StreamEvent, Error>>, Error> {
+        Ok(events(self.completions(engine, request).await?))
+    }
+}
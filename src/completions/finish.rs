@@ -0,0 +1,95 @@
+//! Provides why the generation of a completion stopped
+//!
+//! The api may report it with the last answer. Otherwise
+//! [`ResponseChunk::infer_finish_reason`] infers it from the request and the
+//! number of generated tokens.
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::{choices::Completions, Request, ResponseChunk};
+
+/// Number of tokens generated for requests without `max_tokens`, as
+/// documented by the api
+pub const DEFAULT_MAX_TOKENS: u32 = 100;
+
+/// Why the generation of a completion stopped
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum FinishReason {
+    /// One of the request's `stop` strings was generated.
+    Stop,
+    /// `max_tokens` tokens were generated.
+    Length,
+    /// The model generated its end of text token.
+    EndOfText,
+}
+
+/// Deserialize a reported finish reason, ignoring ones the crate doesn't
+/// know
+pub(crate) fn known_reason<'de, D>(deserializer: D) -> Result<Option<FinishReason>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let reason = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(reason.and_then(|reason| serde_json::from_value(reason).ok()))
+}
+
+/// The finish reason `reported` by the api, or else the one inferred from
+/// `request` and the `output_tokens` of an answer that reached its end
+fn infer(
+    reached_end: bool,
+    reported: Option<FinishReason>,
+    output_tokens: Option<u32>,
+    request: &Request,
+) -> Option<FinishReason> {
+    if !reached_end {
+        return None;
+    }
+    if reported.is_some() {
+        return reported;
+    }
+    let max_tokens = request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+    let n = request.n.unwrap_or(1);
+    if output_tokens.is_some_and(|tokens| tokens >= max_tokens.saturating_mul(n)) {
+        return Some(FinishReason::Length);
+    }
+    // the api removes the stop string from the text, so a generation ending
+    // early is assumed to have matched it
+    if request.stop.as_ref().is_some_and(|stop| !stop.is_empty()) {
+        return Some(FinishReason::Stop);
+    }
+    Some(FinishReason::EndOfText)
+}
+
+impl ResponseChunk {
+    /// Why the generation of `request` stopped, as reported by the api or
+    /// else inferred, none before the last answer
+    ///
+    /// The generation is inferred to have reached `max_tokens` if as many
+    /// tokens were generated. A generation ending earlier is inferred to have
+    /// matched a stop string if the request has some, as the api removes
+    /// them from the text, and to have ended with the end of text token
+    /// otherwise.
+    pub fn infer_finish_reason(&self, request: &Request) -> Option<FinishReason> {
+        infer(
+            self.reached_end,
+            self.finish_reason,
+            self.output_tokens,
+            request,
+        )
+    }
+}
+
+impl Completions {
+    /// Why the generation of `request` stopped, see
+    /// [`ResponseChunk::infer_finish_reason`]
+    pub fn infer_finish_reason(&self, request: &Request) -> Option<FinishReason> {
+        infer(
+            self.reached_end,
+            self.finish_reason,
+            self.output_tokens,
+            request,
+        )
+    }
+}
@@ -0,0 +1,60 @@
+//! Provides streamed completions as blocking iterators
+//!
+//! The [`TextSynthBlockingClient`](crate::blocking::TextSynthBlockingClient)
+//! iterates over streamed completions itself. [`blocking_iter`] bridges the
+//! streams of the async client instead, so synchronous code, e.g. a command
+//! line tool printing tokens as they arrive, keeps the retries, middleware
+//! and timeouts of a [`TextSynthClient`] without any async code:
+//!
+//! ```no_run
+//! # use elikoga_textsynth::{completions::{Engine, Request}, TextSynthClient};
+//! let runtime = tokio::runtime::Builder::new_current_thread()
+//!     .enable_all()
+//!     .build()
+//!     .expect("runtime should build");
+//! let client = TextSynthClient::new("api key");
+//! let request = Request::builder("The capital of France is")
+//!     .build()
+//!     .expect("completion request should build");
+//! for chunk in client.completions_iter(&runtime, &Engine::GPTJ6B, &request)? {
+//!     print!("{}", chunk?.text.concat());
+//! }
+//! # Ok::<_, elikoga_textsynth::completions::Error>(())
+//! ```
+
+use futures::{Stream, StreamExt};
+use tokio::runtime::Runtime;
+
+use crate::TextSynthClient;
+
+use super::{Engine, Error, Request, ResponseChunk};
+
+/// The items of `stream`, each waited for on `runtime`
+///
+/// The runtime must have its io and time drivers enabled to drive the
+/// streams of the client. Iterating from within an async context panics, as
+/// [`Runtime::block_on`] does.
+pub fn blocking_iter<'a, S>(runtime: &'a Runtime, stream: S) -> impl Iterator<Item = S::Item> + 'a
+where
+    S: Stream + 'a,
+{
+    let mut stream = Box::pin(stream);
+    std::iter::from_fn(move || runtime.block_on(stream.next()))
+}
+
+impl TextSynthClient {
+    /// Perform a streaming completion request like
+    /// [`TextSynthClient::completions`] from synchronous code, iterating over
+    /// the chunks as they arrive, see [`blocking_iter`]
+    ///
+    /// Dropping the iterator aborts the request.
+    pub fn completions_iter<'a>(
+        &'a self,
+        runtime: &'a Runtime,
+        engine: &'a Engine,
+        request: &'a Request,
+    ) -> Result<impl Iterator<Item = Result<ResponseChunk, Error>> + 'a, Error> {
+        let stream = runtime.block_on(self.completions(engine, request))?;
+        Ok(blocking_iter(runtime, stream))
+    }
+}
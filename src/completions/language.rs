@@ -0,0 +1,85 @@
+//! Provides verification of the language of generated text
+
+use crate::{translate, TextSynthClient};
+
+use super::{Engine, Error, Request, ResponseChunk};
+
+/// Expected language of the generated text
+#[derive(Debug, Clone)]
+pub struct LanguageCheck {
+    /// ISO code of the expected language.
+    pub expected: String,
+    /// Translation engine detecting the language of the generated text.
+    pub engine: translate::Engine,
+    /// Number of times the completion is run again when it is in the wrong
+    /// language.
+    pub retries: u32,
+}
+
+impl LanguageCheck {
+    /// Expect `expected`, detected with `engine`, without retrying
+    pub fn new(expected: &str, engine: translate::Engine) -> Self {
+        LanguageCheck {
+            expected: expected.to_string(),
+            engine,
+            retries: 0,
+        }
+    }
+
+    /// Run the completion again up to `retries` times while it is in the
+    /// wrong language
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+}
+
+/// Completion whose language was checked
+#[derive(Debug)]
+pub struct Verified {
+    /// The answer of the last attempt.
+    pub response: ResponseChunk,
+    /// Language detected in the answer, none if nothing was generated.
+    pub detected: Option<String>,
+    /// Number of completions run.
+    pub attempts: u32,
+}
+
+impl Verified {
+    /// Returns true if the answer is in the `expected` language
+    pub fn matches(&self, expected: &str) -> bool {
+        self.detected.as_deref() == Some(expected)
+    }
+}
+
+impl TextSynthClient {
+    /// Complete `request`, running it again while the generated text is not
+    /// in the language expected by `check`. Only the first completion is
+    /// checked when `n` is larger than 1.
+    pub async fn complete_in_language(
+        &self,
+        engine: &Engine,
+        request: &Request,
+        check: &LanguageCheck,
+    ) -> Result<Verified, Error> {
+        let mut attempts = 0;
+        loop {
+            let response = self.complete(engine, request).await?;
+            attempts += 1;
+            let detected = match response.text.first() {
+                Some(text) if !text.trim().is_empty() => {
+                    Some(self.detect_language(&check.engine, text).await?)
+                }
+                _ => None,
+            };
+            let verified = Verified {
+                response,
+                detected,
+                attempts,
+            };
+            if verified.matches(&check.expected) || attempts > check.retries {
+                return Ok(verified);
+            }
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Provides building `logit_bias` maps from words instead of token indexes
+
+use std::collections::HashMap;
+
+use crate::{tokenize, TextSynthClient};
+
+use super::Engine;
+
+/// Bias banning a token
+pub const BAN: f64 = -100.0;
+
+/// Words and the bias of their tokens, turned into the `logit_bias` of a
+/// request for an engine by [`LogitBias::build`]
+///
+/// Every word is tokenized as is and with a leading space, as it appears
+/// inside a sentence. Every token of words made of several tokens is biased,
+/// which also affects other words sharing these tokens. A token of several
+/// words gets the bias of the word added last.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogitBias {
+    words: Vec<(String, f64)>,
+}
+
+impl LogitBias {
+    /// No biased words yet
+    pub fn new() -> Self {
+        LogitBias::default()
+    }
+
+    /// Bias the tokens of `words` by `bias`, between -100 and 100
+    pub fn bias<W: Into<String>>(mut self, words: impl IntoIterator<Item = W>, bias: f64) -> Self {
+        self.words
+            .extend(words.into_iter().map(|word| (word.into(), bias)));
+        self
+    }
+
+    /// Ban the tokens of `words`
+    pub fn ban<W: Into<String>>(self, words: impl IntoIterator<Item = W>) -> Self {
+        self.bias(words, BAN)
+    }
+
+    /// Tokenize the words for `engine`, returning the biases by token index
+    pub async fn build(
+        &self,
+        client: &TextSynthClient,
+        engine: &Engine,
+    ) -> Result<HashMap<String, f64>, tokenize::Error> {
+        let mut logit_bias = HashMap::new();
+        for (word, bias) in &self.words {
+            let word = word.trim_start();
+            for variant in [word.to_string(), format!(" {}", word)] {
+                for token in client.tokenize_str(engine, &variant).await? {
+                    logit_bias.insert(token.to_string(), *bias);
+                }
+            }
+        }
+        Ok(logit_bias)
+    }
+}
+
+impl TextSynthClient {
+    /// `logit_bias` banning `words` on `engine`, see [`LogitBias`]
+    pub async fn ban_words<W: Into<String>>(
+        &self,
+        engine: &Engine,
+        words: impl IntoIterator<Item = W>,
+    ) -> Result<HashMap<String, f64>, tokenize::Error> {
+        LogitBias::new().ban(words).build(self, engine).await
+    }
+}
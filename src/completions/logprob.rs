@@ -4,15 +4,16 @@ use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use thiserror::Error;
 
-use crate::TextSynthClient;
+use crate::{record_tokens, validation::ValidationError, TextSynthClient, WithMeta};
 
 use super::Engine;
 
 /// Struct for a logprob request
 #[skip_serializing_none]
-#[derive(Serialize, Builder)]
+#[derive(Serialize, Builder, Debug, Clone)]
 #[builder(setter(into))]
-#[builder(build_fn(validate = "Self::validate"))]
+#[builder(derive(Debug))]
+#[builder(build_fn(validate = "Self::validate", error = "ValidationError"))]
 pub struct Request {
     /// If empty string, the context is set to the End-Of-Text token.
     context: String,
@@ -20,21 +21,51 @@ pub struct Request {
     continuation: String,
 }
 
+/// Error of building a [`Request`]
+pub type RequestBuilderError = ValidationError;
+
+impl Request {
+    /// Builder with the required `context` and `continuation` set, so that
+    /// building fails only on invalid parameters
+    pub fn builder(context: impl Into<String>, continuation: impl Into<String>) -> RequestBuilder {
+        let mut builder = RequestBuilder::default();
+        builder.context(context).continuation(continuation);
+        builder
+    }
+}
+
+fn check_continuation(continuation: &str) -> Result<(), ValidationError> {
+    if continuation.is_empty() {
+        return Err(ValidationError::invalid(
+            "continuation",
+            "not empty",
+            "an empty string",
+        ));
+    }
+    Ok(())
+}
+
 impl RequestBuilder {
-    fn validate(&self) -> Result<(), String> {
-        // n must be between 1 and 16
-        match &self.continuation {
-            Some(continuation) if continuation.is_empty() => {
-                return Err("Continuation must not be empty".to_string());
-            }
-            _ => {}
+    fn validate(&self) -> Result<(), ValidationError> {
+        if let Some(continuation) = &self.continuation {
+            check_continuation(continuation)?;
         }
         Ok(())
     }
+
+    /// Set `continuation`, failing immediately if it is empty
+    pub fn try_continuation<VALUE: Into<String>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let continuation = value.into();
+        check_continuation(&continuation)?;
+        Ok(self.continuation(continuation))
+    }
 }
 
 /// Struct for a logprob answer
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub struct Response {
     /// Logarithm of the probability of generation of continuation preceeded by
     /// context. It corresponds to the sum of the logarithms of the
@@ -59,15 +90,61 @@ pub enum Error {
     /// Error from Reqwest
     #[error("Reqwest error: {0}")]
     RequestError(#[from] reqwest::Error),
+    /// Error answer of the api
+    #[error("API error: {0}")]
+    ApiError(#[from] crate::ApiError),
+    /// The api rate limited the request
+    #[error("Rate limited: {0}")]
+    RateLimited(#[from] crate::RateLimited),
+    /// The api rejected the api key
+    #[error("Unauthorized: {0}")]
+    Unauthorized(#[from] crate::Unauthorized),
+    /// The api key isn't allowed to make the request
+    #[error("Forbidden: {0}")]
+    Forbidden(#[from] crate::Forbidden),
+    /// Successful answer that couldn't be parsed
+    #[error("HTTP error: {0}")]
+    Http(#[from] crate::HttpError),
 }
 
 impl TextSynthClient {
     /// Perform a completion request
     pub async fn logprob(&self, engine: &Engine, request: &Request) -> Result<Response, Error> {
+        Ok(self.logprob_with_meta(engine, request).await?.response)
+    }
+
+    /// Perform a logprob request, keeping the metadata of the response
+    pub async fn logprob_with_meta(
+        &self,
+        engine: &Engine,
+        request: &Request,
+    ) -> Result<WithMeta<Response>, Error> {
         let request_json = serde_json::to_string(&request)?;
         let url = format!("{}/engines/{}/logprob", self.base_url, engine);
-        let response = self.client.post(&url).body(request_json).send().await?;
-        // println!("got response {:?}", response.text().await);
-        response.json().await.map_err(|e| e.into())
+        let response = self
+            .post_with_meta::<Response, Error>(&url, request_json)
+            .await?;
+        record_tokens(
+            self.usage.as_ref(),
+            engine,
+            Some(response.response.input_tokens),
+            None,
+        );
+        Ok(response)
+    }
+
+    /// Perform a logprob request given as raw json, returning the raw json
+    /// answer
+    pub async fn logprob_raw(
+        &self,
+        engine: &Engine,
+        request: &serde_json::Value,
+    ) -> Result<serde_json::Value, Error> {
+        let request_json = serde_json::to_string(request)?;
+        let url = format!("{}/engines/{}/logprob", self.base_url, engine);
+        Ok(self
+            .post_with_meta::<_, Error>(&url, request_json)
+            .await?
+            .response)
     }
 }
@@ -0,0 +1,207 @@
+//! Provides post-processing of the generated text of completions
+//!
+//! [`TextSynthClient::complete_post_processed`] post-processes a whole
+//! completion. A [`PostProcessor`] does the same for streamed text, holding
+//! back what may still change until the end of the stream.
+
+use std::sync::Arc;
+
+#[cfg(feature = "streaming")]
+use futures::{Stream, StreamExt};
+
+use crate::TextSynthClient;
+
+use super::{finish::FinishReason, Engine, Error, Request, ResponseChunk};
+
+/// Clean-ups applied to the generated text, all disabled by default
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PostProcess {
+    /// Remove leading and trailing whitespace.
+    pub trim: bool,
+    /// Remove the start of a stop string the generation ended with, left
+    /// when it ran out of tokens before completing the stop string. Only
+    /// applied to generations that stopped at `max_tokens`.
+    pub strip_partial_stop: bool,
+    /// Collapse consecutive blank lines into a single one.
+    pub collapse_blank_lines: bool,
+}
+
+impl PostProcess {
+    /// Apply the enabled clean-ups to `text`, generated by a request with the
+    /// stop strings `stop` that stopped for `finish_reason`
+    pub fn apply(
+        &self,
+        text: &str,
+        stop: &[impl AsRef<str>],
+        finish_reason: Option<FinishReason>,
+    ) -> String {
+        let mut text = text;
+        if self.strip_partial_stop && finish_reason == Some(FinishReason::Length) {
+            text = strip_partial_stop(text, stop);
+        }
+        let mut text = if self.collapse_blank_lines {
+            collapse_blank_lines(text)
+        } else {
+            text.to_string()
+        };
+        if self.trim {
+            text = text.trim().to_string();
+        }
+        text
+    }
+}
+
+/// `text` without the longest proper prefix of a stop string it ends with
+pub(super) fn strip_partial_stop<'a>(text: &'a str, stop: &[impl AsRef<str>]) -> &'a str {
+    let partial = stop
+        .iter()
+        .map(AsRef::as_ref)
+        .flat_map(|stop| {
+            stop.char_indices()
+                .skip(1)
+                .map(move |(end, _)| &stop[..end])
+        })
+        .filter(|prefix| text.ends_with(prefix))
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    &text[..text.len() - partial]
+}
+
+fn collapse_blank_lines(text: &str) -> String {
+    let mut lines = Vec::new();
+    let mut previous_blank = false;
+    for line in text.split('\n') {
+        let blank = line.trim().is_empty();
+        if !(blank && previous_blank) {
+            lines.push(line);
+        }
+        previous_blank = blank;
+    }
+    lines.join("\n")
+}
+
+/// Post-processing of the text of a single streamed completion
+///
+/// The yielded text is always the start of the post-processed completion:
+/// trailing whitespace and the start of a stop string are held back until
+/// more text shows they stay, or until the end of the completion.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PostProcessor {
+    post_process: PostProcess,
+    stop: Vec<Arc<str>>,
+    text: String,
+    yielded: usize,
+}
+
+impl PostProcessor {
+    /// Apply `post_process` to the text of a completion with the stop
+    /// strings `stop`
+    pub fn new(
+        post_process: PostProcess,
+        stop: impl IntoIterator<Item = impl Into<Arc<str>>>,
+    ) -> Self {
+        PostProcessor {
+            post_process,
+            stop: stop.into_iter().map(Into::into).collect(),
+            ..PostProcessor::default()
+        }
+    }
+
+    /// Add the next `text` of the completion, returning the text that can be
+    /// yielded
+    pub fn push(&mut self, text: &str) -> String {
+        self.text.push_str(text);
+        let mut settled = self.text.as_str();
+        if self.post_process.strip_partial_stop {
+            settled = strip_partial_stop(settled, &self.stop);
+        }
+        if self.post_process.trim || self.post_process.collapse_blank_lines {
+            settled = settled.trim_end();
+        }
+        let processed = self.post_process.apply(settled, &self.stop, None);
+        self.take_new(processed)
+    }
+
+    /// End the completion, which stopped for `finish_reason`, returning the
+    /// rest of the post-processed text
+    pub fn finish(&mut self, finish_reason: Option<FinishReason>) -> String {
+        let processed = self
+            .post_process
+            .apply(&self.text, &self.stop, finish_reason);
+        self.take_new(processed)
+    }
+
+    /// The part of `processed` that wasn't yielded yet
+    fn take_new(&mut self, processed: String) -> String {
+        let new = processed.get(self.yielded..).unwrap_or_default();
+        self.yielded = self.yielded.max(processed.len());
+        new.to_string()
+    }
+}
+
+/// Post-process the text of every completion of `stream`, the answer to
+/// `request`
+#[cfg(feature = "streaming")]
+pub fn post_process_stream<S>(
+    stream: S,
+    request: &Request,
+    post_process: &PostProcess,
+) -> impl Stream<Item = Result<ResponseChunk, Error>>
+where
+    S: Stream<Item = Result<ResponseChunk, Error>>,
+{
+    let request = request.clone();
+    let template = PostProcessor::new(
+        post_process.clone(),
+        request.stop.clone().unwrap_or_default(),
+    );
+    let mut processors: Vec<PostProcessor> = Vec::new();
+    stream.map(move |chunk| {
+        let mut chunk = chunk?;
+        if processors.len() < chunk.text.len() {
+            processors.resize(chunk.text.len(), template.clone());
+        }
+        let finish_reason = chunk.infer_finish_reason(&request);
+        for (text, processor) in chunk.text.iter_mut().zip(&mut processors) {
+            *text = processor.push(text);
+            if chunk.reached_end {
+                text.push_str(&processor.finish(finish_reason));
+            }
+        }
+        Ok(chunk)
+    })
+}
+
+impl TextSynthClient {
+    /// Perform a completion request like [`TextSynthClient::complete`] and
+    /// post-process the generated text
+    pub async fn complete_post_processed(
+        &self,
+        engine: &Engine,
+        request: &Request,
+        post_process: &PostProcess,
+    ) -> Result<ResponseChunk, Error> {
+        let mut response = self.complete(engine, request).await?;
+        let stop = request.stop.as_deref().unwrap_or_default();
+        let finish_reason = response.infer_finish_reason(request);
+        for text in &mut response.text {
+            *text = post_process.apply(text, stop, finish_reason);
+        }
+        Ok(response)
+    }
+
+    /// Perform a streaming completion request like
+    /// [`TextSynthClient::completions`] and post-process the generated text,
+    /// see [`PostProcessor`]
+    #[cfg(feature = "streaming")]
+    pub async fn completions_post_processed(
+        &self,
+        engine: &Engine,
+        request: &Request,
+        post_process: &PostProcess,
+    ) -> Result<impl Stream<Item = Result<ResponseChunk, Error>>, Error> {
+        let stream = self.completions(engine, request).await?;
+        Ok(post_process_stream(stream, request, post_process))
+    }
+}
@@ -0,0 +1,120 @@
+//! Provides scoring texts with the logprob api
+
+use std::ops::Add;
+
+use crate::TextSynthClient;
+
+use super::{
+    logprob::{self, Error, RequestBuilder},
+    Engine,
+};
+
+/// Maximum number of characters scored by a single logprob request of
+/// [`TextSynthClient::perplexity`], about 250 tokens of English text
+const CHUNK_CHARS: usize = 1000;
+
+/// Log probability of a text
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Score {
+    /// Sum of the log probabilities of the tokens of the text.
+    pub logprob: f64,
+    /// Number of tokens of the text.
+    pub num_tokens: u32,
+}
+
+impl Score {
+    /// Average log probability per token, 0 for an empty text
+    pub fn average_logprob(&self) -> f64 {
+        if self.num_tokens == 0 {
+            0.0
+        } else {
+            self.logprob / f64::from(self.num_tokens)
+        }
+    }
+
+    /// Perplexity of the text, the exponential of the negative average log
+    /// probability per token
+    pub fn perplexity(&self) -> f64 {
+        (-self.average_logprob()).exp()
+    }
+}
+
+impl From<&logprob::Response> for Score {
+    fn from(response: &logprob::Response) -> Self {
+        Score {
+            logprob: response.logprob,
+            num_tokens: response.num_tokens,
+        }
+    }
+}
+
+/// Scores of consecutive parts of a text add up to the score of the text
+impl Add for Score {
+    type Output = Score;
+
+    fn add(self, other: Score) -> Score {
+        Score {
+            logprob: self.logprob + other.logprob,
+            num_tokens: self.num_tokens + other.num_tokens,
+        }
+    }
+}
+
+/// Split `text` at whitespace into chunks of at most `max_chars` characters,
+/// unless a single word is longer
+fn chunks(text: &str, max_chars: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut end = 0;
+    let mut chars = 0;
+    for (index, char) in text.char_indices() {
+        if char.is_whitespace() && index > start {
+            end = index;
+        }
+        chars += 1;
+        if chars > max_chars && end > start {
+            chunks.push(&text[start..end]);
+            chars = text[end..=index].chars().count();
+            start = end;
+        }
+    }
+    if start < text.len() {
+        chunks.push(&text[start..]);
+    }
+    chunks
+}
+
+impl TextSynthClient {
+    /// Score `continuation` following `context` with the logprob api
+    pub async fn score(
+        &self,
+        engine: &Engine,
+        context: &str,
+        continuation: &str,
+    ) -> Result<Score, Error> {
+        if continuation.is_empty() {
+            return Ok(Score::default());
+        }
+        let request = RequestBuilder::default()
+            .context(context)
+            .continuation(continuation)
+            .build()
+            .expect("continuation is not empty");
+        Ok(Score::from(&self.logprob(engine, &request).await?))
+    }
+
+    /// Score `text` from its start, see [`Score::perplexity`]
+    ///
+    /// Long texts are split into chunks at whitespace, each scored with the
+    /// chunk before it as context so that every request fits the context of
+    /// the engine.
+    pub async fn perplexity(&self, engine: &Engine, text: &str) -> Result<Score, Error> {
+        let mut score = Score::default();
+        let mut context = "";
+        for chunk in chunks(text, CHUNK_CHARS) {
+            score = score + self.score(engine, context, chunk).await?;
+            context = chunk;
+        }
+        Ok(score)
+    }
+}
@@ -0,0 +1,101 @@
+//! Provides selection of the cheapest completion engine fitting a request
+
+use crate::engine::EngineDescriptor;
+
+use super::Engine;
+
+/// A completion engine the selector may pick
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    /// The engine.
+    pub engine: Engine,
+    /// Maximum number of prompt and generated tokens together.
+    pub context_length: u32,
+    /// Relative cost of using the engine, only compared between candidates.
+    pub cost: f64,
+    /// ISO codes of the languages the engine should be used for. Empty if
+    /// it may be used for any language.
+    pub languages: Vec<String>,
+}
+
+impl Candidate {
+    /// The candidate for a custom engine, as declared by its descriptor
+    pub fn custom(descriptor: &'static EngineDescriptor) -> Self {
+        Candidate {
+            engine: Engine::Custom(descriptor),
+            context_length: descriptor.context_length,
+            cost: descriptor.cost,
+            languages: descriptor
+                .languages
+                .iter()
+                .map(|language| language.to_string())
+                .collect(),
+        }
+    }
+
+    /// Returns true if a request of `prompt_tokens` generating up to
+    /// `max_tokens` in `language` fits this candidate.
+    pub fn fits(&self, prompt_tokens: u32, max_tokens: u32, language: &str) -> bool {
+        let fits_context = prompt_tokens.saturating_add(max_tokens) <= self.context_length;
+        let fits_language = self.languages.is_empty()
+            || self.languages.iter().any(|supported| supported == language);
+        fits_context && fits_language
+    }
+}
+
+/// Picks the first candidate, in preference order, that fits a request
+#[derive(Debug, Clone)]
+pub struct EngineSelector {
+    candidates: Vec<Candidate>,
+}
+
+impl EngineSelector {
+    /// Create a selector preferring `candidates` in the given order
+    pub fn new(candidates: Vec<Candidate>) -> Self {
+        EngineSelector { candidates }
+    }
+
+    /// Order the candidates by cost, keeping the given order between
+    /// candidates of the same cost
+    pub fn by_cost(mut self) -> Self {
+        self.candidates.sort_by(|a, b| a.cost.total_cmp(&b.cost));
+        self
+    }
+
+    /// The candidates in preference order
+    pub fn candidates(&self) -> &[Candidate] {
+        &self.candidates
+    }
+
+    /// The preferred engine fitting a prompt of `prompt_tokens` tokens
+    /// generating up to `max_tokens` tokens in `language`, an ISO language
+    /// code. Count the prompt tokens with the tokenize api.
+    pub fn select(&self, prompt_tokens: u32, max_tokens: u32, language: &str) -> Option<&Engine> {
+        self.candidates
+            .iter()
+            .find(|candidate| candidate.fits(prompt_tokens, max_tokens, language))
+            .map(|candidate| &candidate.engine)
+    }
+}
+
+impl Default for EngineSelector {
+    /// The built-in engines ordered by size, which the cost is taken from
+    fn default() -> Self {
+        let candidate = |engine: Engine, cost, languages: &[&str]| Candidate {
+            context_length: engine.context_length().unwrap_or_default(),
+            engine,
+            cost,
+            languages: languages
+                .iter()
+                .map(|language| language.to_string())
+                .collect(),
+        };
+        EngineSelector::new(vec![
+            candidate(Engine::Boris6B, 6.0, &["fr"]),
+            candidate(Engine::GPTJ6B, 6.0, &[]),
+            candidate(Engine::FairseqGPT13B, 13.0, &["en"]),
+            candidate(Engine::GPTNeoX20B, 20.0, &["en"]),
+        ])
+        .by_cost()
+    }
+}
@@ -0,0 +1,169 @@
+//! Provides conversations on top of completions
+//!
+//! A [`Session`] keeps the transcript of a conversation and replays it in the
+//! prompt of every turn, formatted as
+//!
+//! ```text
+//! <prefix>
+//! User: <input>
+//! Assistant: <reply>
+//! User: <input>
+//! Assistant:
+//! ```
+
+use std::sync::Arc;
+
+use crate::{validation::ValidationError, TextSynthClient};
+
+use super::{check_max_tokens, check_stop, Engine, Error, RequestBuilder};
+
+/// A turn of a conversation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Turn {
+    /// What the user said.
+    pub input: String,
+    /// What the model replied.
+    pub reply: String,
+}
+
+/// Conversation with a completion engine
+pub struct Session<'a> {
+    client: &'a TextSynthClient,
+    engine: Engine,
+    prefix: String,
+    user_name: String,
+    bot_name: String,
+    max_tokens: u32,
+    stop: Vec<Arc<str>>,
+    transcript: Vec<Turn>,
+}
+
+impl<'a> Session<'a> {
+    /// Start an empty conversation on `engine`
+    pub fn new(client: &'a TextSynthClient, engine: Engine) -> Self {
+        Session {
+            client,
+            engine,
+            prefix: String::new(),
+            user_name: "User".to_string(),
+            bot_name: "Assistant".to_string(),
+            max_tokens: 200,
+            stop: Vec::new(),
+            transcript: Vec::new(),
+        }
+    }
+
+    /// Text put before the transcript, e.g. describing the assistant
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Names the user and the model speak under, `User` and `Assistant` by
+    /// default
+    pub fn names(mut self, user_name: impl Into<String>, bot_name: impl Into<String>) -> Self {
+        self.user_name = user_name.into();
+        self.bot_name = bot_name.into();
+        self
+    }
+
+    /// Maximum number of tokens of a reply, 200 by default
+    ///
+    /// Turns fail with a [`ValidationError`] if it is 0, see
+    /// [`Session::try_max_tokens`].
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Maximum number of tokens of a reply, failing if it is 0
+    pub fn try_max_tokens(self, max_tokens: u32) -> Result<Self, ValidationError> {
+        check_max_tokens(max_tokens)?;
+        Ok(self.max_tokens(max_tokens))
+    }
+
+    /// Stop strings ending a reply besides the start of the next user line
+    ///
+    /// With the start of the next user line, the api takes at most 5 stop
+    /// strings, so turns fail with a [`ValidationError`] if more than 4 are
+    /// given, see [`Session::try_stop`].
+    pub fn stop(mut self, stop: impl IntoIterator<Item = impl Into<Arc<str>>>) -> Self {
+        self.stop = stop.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Stop strings ending a reply besides the start of the next user line,
+    /// failing if more than 4 are given
+    pub fn try_stop(
+        mut self,
+        stop: impl IntoIterator<Item = impl Into<Arc<str>>>,
+    ) -> Result<Self, ValidationError> {
+        self.stop = stop.into_iter().map(Into::into).collect();
+        check_stop(&self.request_stop())?;
+        Ok(self)
+    }
+
+    /// The turns of the conversation so far
+    pub fn transcript(&self) -> &[Turn] {
+        &self.transcript
+    }
+
+    /// Forget the turns of the conversation, keeping the prefix
+    pub fn clear(&mut self) {
+        self.transcript.clear();
+    }
+
+    /// The prompt sent when the user says `input`
+    pub fn prompt(&self, input: &str) -> String {
+        let mut prompt = self.prefix.clone();
+        if !prompt.is_empty() && !prompt.ends_with('\n') {
+            prompt.push('\n');
+        }
+        for turn in &self.transcript {
+            prompt.push_str(&format!(
+                "{}: {}\n{}: {}\n",
+                self.user_name, turn.input, self.bot_name, turn.reply
+            ));
+        }
+        prompt.push_str(&format!(
+            "{}: {}\n{}:",
+            self.user_name, input, self.bot_name
+        ));
+        prompt
+    }
+
+    /// The stop strings of the requests: the start of the next user line,
+    /// then the stop strings given
+    fn request_stop(&self) -> Vec<Arc<str>> {
+        let mut stop = vec![Arc::from(format!("\n{}:", self.user_name))];
+        stop.extend(self.stop.iter().cloned());
+        stop
+    }
+
+    /// Say `input` to the model, returning its reply and adding the turn to
+    /// the transcript
+    ///
+    /// The transcript is left unchanged if the request fails, including if
+    /// the maximum number of tokens or the stop strings are invalid.
+    pub async fn say(&mut self, input: &str) -> Result<String, Error> {
+        let request = RequestBuilder::default()
+            .prompt(self.prompt(input))
+            .max_tokens(self.max_tokens)
+            .stop(self.request_stop())
+            .build()?;
+        let response = self.client.complete(&self.engine, &request).await?;
+        let reply = response.text.concat().trim().to_string();
+        self.transcript.push(Turn {
+            input: input.to_string(),
+            reply: reply.clone(),
+        });
+        Ok(reply)
+    }
+}
+
+impl TextSynthClient {
+    /// Start a conversation on `engine`, see [`Session`]
+    pub fn session(&self, engine: Engine) -> Session<'_> {
+        Session::new(self, engine)
+    }
+}
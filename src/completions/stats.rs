@@ -0,0 +1,113 @@
+//! Provides statistics of streamed completions, updated as chunks arrive
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures::{Stream, StreamExt};
+
+use crate::TextSynthClient;
+
+use super::{Engine, Error, Request, ResponseChunk};
+
+/// Statistics of a streamed completion at one point in time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    /// Number of chunks received so far.
+    pub chunks: u32,
+    /// Number of tokens generated so far, as reported by the api or else
+    /// counted as one per chunk.
+    pub output_tokens: u32,
+    /// Time since the request was sent, until the end of the generation once
+    /// it ended.
+    pub elapsed: Duration,
+    /// If true, the generation ended.
+    pub reached_end: bool,
+}
+
+impl Stats {
+    /// Average number of tokens generated per second so far
+    pub fn tokens_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            f64::from(self.output_tokens) / seconds
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    started: Instant,
+    ended: Option<Instant>,
+    chunks: u32,
+    output_tokens: u32,
+}
+
+/// Handle to the statistics of a streamed completion, shared with its stream
+#[derive(Debug, Clone)]
+pub struct StreamStats {
+    state: Arc<Mutex<State>>,
+}
+
+impl StreamStats {
+    fn new() -> Self {
+        StreamStats {
+            state: Arc::new(Mutex::new(State {
+                started: Instant::now(),
+                ended: None,
+                chunks: 0,
+                output_tokens: 0,
+            })),
+        }
+    }
+
+    fn record(&self, chunk: &ResponseChunk) {
+        let mut state = self.state.lock().expect("stats lock poisoned");
+        state.chunks += 1;
+        state.output_tokens = chunk.output_tokens.unwrap_or(state.chunks);
+        if chunk.reached_end {
+            state.ended = Some(Instant::now());
+        }
+    }
+
+    /// The current statistics
+    pub fn snapshot(&self) -> Stats {
+        let state = self.state.lock().expect("stats lock poisoned");
+        let until = state.ended.unwrap_or_else(Instant::now);
+        Stats {
+            chunks: state.chunks,
+            output_tokens: state.output_tokens,
+            elapsed: until - state.started,
+            reached_end: state.ended.is_some(),
+        }
+    }
+}
+
+impl TextSynthClient {
+    /// Perform a streaming completion request, together with a handle to its
+    /// statistics that is updated as the chunks are consumed
+    pub async fn completions_with_stats(
+        &self,
+        engine: &Engine,
+        request: &Request,
+    ) -> Result<
+        (
+            impl Stream<Item = Result<ResponseChunk, Error>>,
+            StreamStats,
+        ),
+        Error,
+    > {
+        let stats = StreamStats::new();
+        let response = self.completions(engine, request).await?;
+        let recorder = stats.clone();
+        let response = response.map(move |chunk| {
+            let chunk = chunk?;
+            recorder.record(&chunk);
+            Ok(chunk)
+        });
+        Ok((response, stats))
+    }
+}
@@ -0,0 +1,107 @@
+//! Provides keeping stop strings out of streamed completions
+//!
+//! The api checks for stop strings on the text generated so far, so a chunk
+//! may already hold the start of a stop string when the generation stops.
+//! A [`StopFilter`] holds text back until it can't be the start of a stop
+//! string anymore.
+
+use std::sync::Arc;
+
+use futures::{Stream, StreamExt};
+
+use crate::TextSynthClient;
+
+use super::{postprocess::strip_partial_stop, Engine, Error, Request, ResponseChunk};
+
+/// Filter of the text of a single completion, yielding its text without the
+/// stop strings and anything following them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StopFilter {
+    stop: Vec<Arc<str>>,
+    held: String,
+    stopped: bool,
+}
+
+impl StopFilter {
+    /// Filter out `stop`
+    pub fn new(stop: impl IntoIterator<Item = impl Into<Arc<str>>>) -> Self {
+        StopFilter {
+            stop: stop.into_iter().map(Into::into).collect(),
+            ..StopFilter::default()
+        }
+    }
+
+    /// Add the next `text` of the completion, returning the text that can be
+    /// yielded
+    pub fn push(&mut self, text: &str) -> String {
+        if self.stopped {
+            return String::new();
+        }
+        self.held.push_str(text);
+        let first_stop = self
+            .stop
+            .iter()
+            .filter(|stop| !stop.is_empty())
+            .filter_map(|stop| self.held.find(&**stop))
+            .min();
+        if let Some(index) = first_stop {
+            self.stopped = true;
+            self.held.truncate(index);
+            return std::mem::take(&mut self.held);
+        }
+        let ready = strip_partial_stop(&self.held, &self.stop).len();
+        self.held.drain(..ready).collect()
+    }
+
+    /// Returns true once a stop string was found, after which all text is
+    /// dropped
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Drop the text held back at the end of the completion, the start of a
+    /// stop string
+    pub fn finish(&mut self) {
+        self.held.clear();
+    }
+}
+
+/// Filter the stop strings `stop` out of the text of every completion of
+/// `stream`
+pub fn filter_stops<S>(
+    stream: S,
+    stop: impl IntoIterator<Item = impl Into<Arc<str>>>,
+) -> impl Stream<Item = Result<ResponseChunk, Error>>
+where
+    S: Stream<Item = Result<ResponseChunk, Error>>,
+{
+    let template = StopFilter::new(stop);
+    let mut filters: Vec<StopFilter> = Vec::new();
+    stream.map(move |chunk| {
+        let mut chunk = chunk?;
+        if filters.len() < chunk.text.len() {
+            filters.resize(chunk.text.len(), template.clone());
+        }
+        for (text, filter) in chunk.text.iter_mut().zip(&mut filters) {
+            *text = filter.push(text);
+            if chunk.reached_end {
+                filter.finish();
+            }
+        }
+        Ok(chunk)
+    })
+}
+
+impl TextSynthClient {
+    /// Perform a streaming completion request like
+    /// [`TextSynthClient::completions`], holding text back so the stop
+    /// strings of the request never appear in the yielded text
+    pub async fn completions_stop_filtered(
+        &self,
+        engine: &Engine,
+        request: &Request,
+    ) -> Result<impl Stream<Item = Result<ResponseChunk, Error>>, Error> {
+        let stop = request.stop.clone().unwrap_or_default();
+        Ok(filter_stops(self.completions(engine, request).await?, stop))
+    }
+}
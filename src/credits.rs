@@ -0,0 +1,57 @@
+//! Provides credits api
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{TextSynthClient, WithMeta};
+
+/// Struct for a credits answer
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Response {
+    /// Remaining credits of the account, in units of 1e-9 dollars.
+    pub credits: i64,
+}
+
+impl Response {
+    /// Remaining credits of the account in dollars
+    pub fn dollars(&self) -> f64 {
+        self.credits as f64 / 1e9
+    }
+}
+
+#[derive(Error, Debug)]
+/// Error for a credits answer
+pub enum Error {
+    /// Error from Reqwest
+    #[error("Reqwest error: {0}")]
+    RequestError(#[from] reqwest::Error),
+    /// Error answer of the api
+    #[error("API error: {0}")]
+    ApiError(#[from] crate::ApiError),
+    /// The api rate limited the request
+    #[error("Rate limited: {0}")]
+    RateLimited(#[from] crate::RateLimited),
+    /// The api rejected the api key
+    #[error("Unauthorized: {0}")]
+    Unauthorized(#[from] crate::Unauthorized),
+    /// The api key isn't allowed to make the request
+    #[error("Forbidden: {0}")]
+    Forbidden(#[from] crate::Forbidden),
+    /// Successful answer that couldn't be parsed
+    #[error("HTTP error: {0}")]
+    Http(#[from] crate::HttpError),
+}
+
+impl TextSynthClient {
+    /// Get the remaining credits of the account
+    pub async fn credits(&self) -> Result<Response, Error> {
+        Ok(self.credits_with_meta().await?.response)
+    }
+
+    /// Get the remaining credits of the account, keeping the metadata of the
+    /// response
+    pub async fn credits_with_meta(&self) -> Result<WithMeta<Response>, Error> {
+        let url = format!("{}/credits", self.base_url);
+        self.send_with_meta(self.client.get(&url)).await
+    }
+}
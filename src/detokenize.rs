@@ -0,0 +1,112 @@
+//! Provides turning token indexes back into text
+//!
+//! The api has no endpoint decoding token indexes, but it can return the
+//! content of every token of a tokenized text, see
+//! [`TokenContentType`](crate::tokenize::TokenContentType). A [`Vocabulary`] collects these
+//! contents and decodes any token index it has seen before.
+
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::{
+    tokenize::{Error, RequestBuilder, TokenContentType},
+    IsEngine, TextSynthClient,
+};
+
+/// A token index together with the bytes it stands for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    /// Index of the token, as used by `logit_bias`.
+    pub id: u32,
+    /// Bytes of text represented by the token. A single token may hold only
+    /// part of a multi-byte character.
+    pub content: Vec<u8>,
+}
+
+impl Token {
+    /// The content of the token as text, with invalid UTF-8 replaced
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.content).into_owned()
+    }
+}
+
+/// Token contents collected from tokenized texts
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Vocabulary {
+    contents: HashMap<u32, Vec<u8>>,
+}
+
+/// A token index that isn't in the [`Vocabulary`]
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("unknown token {0}")]
+pub struct UnknownToken(pub u32);
+
+impl Vocabulary {
+    /// Remember the contents of `tokens`
+    pub fn learn<'a>(&mut self, tokens: impl IntoIterator<Item = &'a Token>) {
+        for token in tokens {
+            self.contents.insert(token.id, token.content.clone());
+        }
+    }
+
+    /// Bytes represented by the token `id`, if it was learned
+    pub fn get(&self, id: u32) -> Option<&[u8]> {
+        self.contents.get(&id).map(Vec::as_slice)
+    }
+
+    /// Number of known tokens
+    pub fn len(&self) -> usize {
+        self.contents.len()
+    }
+
+    /// Returns true if no token was learned yet
+    pub fn is_empty(&self) -> bool {
+        self.contents.is_empty()
+    }
+
+    /// Text of the token sequence `ids`, with invalid UTF-8 replaced
+    pub fn decode(&self, ids: &[u32]) -> Result<String, UnknownToken> {
+        let mut bytes = Vec::new();
+        for &id in ids {
+            bytes.extend_from_slice(self.get(id).ok_or(UnknownToken(id))?);
+        }
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+impl TextSynthClient {
+    /// Tokenize `text`, returning every token together with its content
+    pub async fn tokenize_with_content(
+        &self,
+        engine: &impl IsEngine,
+        text: &str,
+    ) -> Result<Vec<Token>, Error> {
+        let request = RequestBuilder::default()
+            .text(text)
+            .token_content_type(TokenContentType::Base64)
+            .build()
+            .expect("tokenize requests have no constraints");
+        let response = self.tokenize(engine, &request).await?;
+        let contents = response.token_bytes().ok_or(Error::MissingTokenContent)??;
+        Ok(response
+            .tokens
+            .into_iter()
+            .zip(contents)
+            .map(|(id, content)| Token { id, content })
+            .collect())
+    }
+
+    /// Tokenize `text` and add the contents of its tokens to `vocabulary`,
+    /// returning the token indexes
+    pub async fn learn_tokens(
+        &self,
+        engine: &impl IsEngine,
+        text: &str,
+        vocabulary: &mut Vocabulary,
+    ) -> Result<Vec<u32>, Error> {
+        let tokens = self.tokenize_with_content(engine, text).await?;
+        vocabulary.learn(&tokens);
+        Ok(tokens.into_iter().map(|token| token.id).collect())
+    }
+}
@@ -0,0 +1,295 @@
+//! Provides descriptions of custom engines, e.g. of a self-hosted server,
+//! and the metadata of all engines in [`ENGINES`]
+//!
+//! Costs are computed from the prices of custom engine descriptors only. The
+//! prices of the built-in engines change over time and aren't tracked by the
+//! crate, so their [`EngineInfo`] has no price until one is set with
+//! [`EngineInfo::with_price`].
+
+use thiserror::Error;
+
+use crate::IsEngine;
+
+/// Description of an engine the crate doesn't know about. Declare one with
+/// the [`define_engine!`](crate::define_engine!) macro and use it through the
+/// `Custom` variant of the completion or translation engines.
+#[derive(Debug)]
+pub struct EngineDescriptor {
+    /// Name of the engine in the api.
+    pub name: &'static str,
+    /// Maximum number of prompt and generated tokens together.
+    pub context_length: u32,
+    /// If true, the engine supports the completion and logprob apis.
+    pub completion: bool,
+    /// If true, the engine supports the translation api.
+    pub translation: bool,
+    /// Relative cost of using the engine, only compared between engines.
+    pub cost: f64,
+    /// ISO codes of the languages the engine should be used for. Empty if it
+    /// may be used for any language.
+    pub languages: &'static [&'static str],
+    /// Price of a thousand tokens, if known.
+    pub price_per_kilotoken: Option<f64>,
+}
+
+impl EngineDescriptor {
+    /// Descriptor the fields not given to
+    /// [`define_engine!`](crate::define_engine!) are taken from
+    pub const DEFAULT: EngineDescriptor = EngineDescriptor {
+        name: "",
+        context_length: 1024,
+        completion: false,
+        translation: false,
+        cost: 0.0,
+        languages: &[],
+        price_per_kilotoken: None,
+    };
+
+    /// Metadata of the engine, as far as the descriptor declares it
+    pub fn info(&self) -> EngineInfo {
+        let endpoints = match (self.completion, self.translation) {
+            (true, true) => ALL_ENDPOINTS,
+            (true, false) => COMPLETION_ENDPOINTS,
+            (false, true) => TRANSLATION_ENDPOINTS,
+            (false, false) => &[],
+        };
+        EngineInfo {
+            id: self.name,
+            family: None,
+            parameters: None,
+            context_length: Some(self.context_length),
+            endpoints,
+            price_per_kilotoken: self.price_per_kilotoken,
+        }
+    }
+}
+
+/// Engines are identified by their name in the api
+impl PartialEq for EngineDescriptor {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for EngineDescriptor {}
+
+impl IsEngine for EngineDescriptor {
+    fn is_completion(&self) -> bool {
+        self.completion
+    }
+
+    fn is_translation(&self) -> bool {
+        self.translation
+    }
+}
+
+impl std::fmt::Display for EngineDescriptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+/// Api endpoint an engine can be used with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Endpoint {
+    /// The completions api.
+    Completions,
+    /// The logprob api.
+    Logprob,
+    /// The tokenize and detokenize apis.
+    Tokenize,
+    /// The translate api.
+    Translate,
+    /// The chat api.
+    Chat,
+    /// The text to image api.
+    TextToImage,
+}
+
+const COMPLETION_ENDPOINTS: &[Endpoint] =
+    &[Endpoint::Completions, Endpoint::Logprob, Endpoint::Tokenize];
+const TRANSLATION_ENDPOINTS: &[Endpoint] = &[Endpoint::Translate];
+const ALL_ENDPOINTS: &[Endpoint] = &[
+    Endpoint::Completions,
+    Endpoint::Logprob,
+    Endpoint::Tokenize,
+    Endpoint::Translate,
+];
+
+/// Metadata of an engine, e.g. to display a model picker or estimate the
+/// cost of requests
+#[derive(Debug, Clone, PartialEq)]
+pub struct EngineInfo {
+    /// Name of the engine in the api.
+    pub id: &'static str,
+    /// Model family the engine belongs to, e.g. `GPT-J`.
+    pub family: Option<&'static str>,
+    /// Number of parameters of the model.
+    pub parameters: Option<u64>,
+    /// Maximum number of prompt and generated tokens together.
+    pub context_length: Option<u32>,
+    /// The apis the engine can be used with.
+    pub endpoints: &'static [Endpoint],
+    /// Price of a thousand tokens. Only known for custom engines declaring
+    /// one, see [`EngineInfo::with_price`] for the built-in engines.
+    pub price_per_kilotoken: Option<f64>,
+}
+
+impl EngineInfo {
+    /// Returns true if the engine can be used with `endpoint`
+    pub fn supports(&self, endpoint: Endpoint) -> bool {
+        self.endpoints.contains(&endpoint)
+    }
+
+    /// This metadata with the price of a thousand tokens, e.g. the price of
+    /// a built-in engine from the pricing of the account
+    pub fn with_price(self, price_per_kilotoken: f64) -> Self {
+        EngineInfo {
+            price_per_kilotoken: Some(price_per_kilotoken),
+            ..self
+        }
+    }
+
+    /// Cost of `tokens` tokens, if the price is known
+    pub fn cost(&self, tokens: u32) -> Option<f64> {
+        self.price_per_kilotoken
+            .map(|price| price * f64::from(tokens) / 1000.0)
+    }
+}
+
+const fn builtin(
+    id: &'static str,
+    family: &'static str,
+    parameters: Option<u64>,
+    context_length: Option<u32>,
+    endpoints: &'static [Endpoint],
+) -> EngineInfo {
+    EngineInfo {
+        id,
+        family: Some(family),
+        parameters,
+        context_length,
+        endpoints,
+        price_per_kilotoken: None,
+    }
+}
+
+/// Metadata of the engines built into the crate, without prices and with
+/// the number of parameters only if it is published
+pub static ENGINES: &[EngineInfo] = &[
+    builtin(
+        "gptj_6B",
+        "GPT-J",
+        Some(6_000_000_000),
+        Some(2048),
+        COMPLETION_ENDPOINTS,
+    ),
+    builtin(
+        "boris_6B",
+        "GPT-J",
+        Some(6_000_000_000),
+        Some(1024),
+        COMPLETION_ENDPOINTS,
+    ),
+    builtin(
+        "fairseq_gpt_13B",
+        "Fairseq GPT",
+        Some(13_000_000_000),
+        Some(1024),
+        COMPLETION_ENDPOINTS,
+    ),
+    builtin(
+        "gptneox_20B",
+        "GPT-NeoX",
+        Some(20_000_000_000),
+        Some(1024),
+        COMPLETION_ENDPOINTS,
+    ),
+    builtin(
+        "m2m100_1_2B",
+        "M2M100",
+        Some(1_200_000_000),
+        None,
+        TRANSLATION_ENDPOINTS,
+    ),
+    builtin(
+        "falcon_40B-chat",
+        "Falcon",
+        Some(40_000_000_000),
+        None,
+        &[Endpoint::Chat],
+    ),
+    builtin(
+        "llama2_70B_chat",
+        "Llama 2",
+        Some(70_000_000_000),
+        None,
+        &[Endpoint::Chat],
+    ),
+    builtin(
+        "mistral_7B_instruct",
+        "Mistral",
+        Some(7_000_000_000),
+        None,
+        &[Endpoint::Chat],
+    ),
+    builtin(
+        "stable_diffusion",
+        "Stable Diffusion",
+        None,
+        None,
+        &[Endpoint::TextToImage],
+    ),
+];
+
+/// Metadata of the built-in engine with the api name `id`
+pub fn engine_info(id: &str) -> Option<&'static EngineInfo> {
+    ENGINES.iter().find(|info| info.id == id)
+}
+
+/// Error parsing the api name of an engine
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("unknown {kind} engine {id}")]
+pub struct ParseEngineError {
+    /// Kind of engine that was parsed, e.g. `completion`.
+    pub kind: &'static str,
+    /// The unknown api name.
+    pub id: String,
+}
+
+impl ParseEngineError {
+    pub(crate) fn new(kind: &'static str, id: &str) -> Self {
+        ParseEngineError {
+            kind,
+            id: id.to_string(),
+        }
+    }
+}
+
+/// Declare a static [`EngineDescriptor`] named `$ident` for the engine
+/// `$name`. Fields not given are taken from [`EngineDescriptor::DEFAULT`].
+///
+/// ```
+/// use elikoga_textsynth::{completions::Engine, define_engine};
+///
+/// define_engine!(pub LLAMA_7B = "llama_7B" {
+///     context_length: 2048,
+///     completion: true,
+///     cost: 7.0,
+/// });
+///
+/// let engine = Engine::Custom(&LLAMA_7B);
+/// assert_eq!(engine.to_string(), "llama_7B");
+/// ```
+#[macro_export]
+macro_rules! define_engine {
+    ($(#[$meta:meta])* $vis:vis $ident:ident = $name:literal { $($field:ident: $value:expr),* $(,)? }) => {
+        $(#[$meta])*
+        $vis static $ident: $crate::engine::EngineDescriptor = $crate::engine::EngineDescriptor {
+            name: $name,
+            $($field: $value,)*
+            ..$crate::engine::EngineDescriptor::DEFAULT
+        };
+    };
+}
@@ -0,0 +1,332 @@
+//! Provides a C api over the client
+//!
+//! Enabled by the `ffi` feature. `cargo build --release --features ffi`
+//! builds the shared library `libelikoga_textsynth`, declared by
+//! `include/textsynth.h`. All strings are nul terminated UTF-8. Strings
+//! returned by this api must be released with [`textsynth_string_free`].
+//! Calls block the calling thread until the request is done, and fail when
+//! made from a thread running a tokio runtime. A panic fails the call
+//! instead of unwinding into C.
+
+use std::{
+    cell::RefCell,
+    ffi::{CStr, CString},
+    future::Future,
+    os::raw::{c_char, c_int, c_void},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use futures::StreamExt;
+use tokio::runtime::Runtime;
+
+use crate::{completions, engine::ParseEngineError, translate, TextSynthClient};
+
+/// Client handle owned by C code
+pub struct TextSynthHandle {
+    client: TextSynthClient,
+    runtime: Runtime,
+}
+
+/// Callback receiving each piece of streamed text
+pub type TextSynthCallback = extern "C" fn(text: *const c_char, user_data: *mut c_void);
+
+/// Returned when the call succeeded
+pub const TEXTSYNTH_OK: c_int = 0;
+/// Returned when an argument is invalid
+pub const TEXTSYNTH_INVALID_ARGUMENT: c_int = -1;
+/// Returned when the request failed
+pub const TEXTSYNTH_REQUEST_FAILED: c_int = -2;
+/// Returned when the call panicked
+pub const TEXTSYNTH_PANICKED: c_int = -3;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl ToString) {
+    let message = CString::new(message.to_string().replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+/// Run `f`, returning `on_panic` with the panic as the last error instead of
+/// unwinding into C
+fn catch_panic<T>(on_panic: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown cause");
+        set_last_error(format!("panicked: {}", message));
+        on_panic
+    })
+}
+
+/// Run `future` to completion on the runtime of `handle`, which tokio only
+/// allows outside of a runtime
+fn block_on<F: Future>(handle: &TextSynthHandle, future: F) -> Result<F::Output, String> {
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return Err("called from within a tokio runtime".to_string());
+    }
+    Ok(handle.runtime.block_on(future))
+}
+
+unsafe fn str_arg<'a>(arg: *const c_char, name: &str) -> Result<&'a str, String> {
+    if arg.is_null() {
+        return Err(format!("{} is null", name));
+    }
+    CStr::from_ptr(arg)
+        .to_str()
+        .map_err(|_| format!("{} is not valid UTF-8", name))
+}
+
+fn to_c_string(text: String) -> CString {
+    CString::new(text.replace('\0', "")).unwrap_or_default()
+}
+
+fn completion_engine(id: &str) -> Result<completions::Engine, String> {
+    id.parse().map_err(|err: ParseEngineError| err.to_string())
+}
+
+fn translation_engine(id: &str) -> Result<translate::Engine, String> {
+    id.parse().map_err(|err: ParseEngineError| err.to_string())
+}
+
+/// Message of the last error on the calling thread, or null. The pointer is
+/// valid until the next call into this api on the same thread.
+#[no_mangle]
+pub extern "C" fn textsynth_last_error() -> *const c_char {
+    catch_panic(ptr::null(), || {
+        LAST_ERROR.with(|last_error| {
+            last_error
+                .borrow()
+                .as_ref()
+                .map_or(ptr::null(), |message| message.as_ptr())
+        })
+    })
+}
+
+/// Create a client for the TextSynth API, or null on error.
+///
+/// # Safety
+///
+/// `api_key` must be a valid nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn textsynth_client_new(api_key: *const c_char) -> *mut TextSynthHandle {
+    textsynth_client_new_with_endpoint(api_key, ptr::null())
+}
+
+/// Create a client for a custom endpoint, or null on error. A null
+/// `endpoint` selects the public TextSynth API.
+///
+/// # Safety
+///
+/// `api_key` and, unless null, `endpoint` must be valid nul terminated
+/// strings.
+#[no_mangle]
+pub unsafe extern "C" fn textsynth_client_new_with_endpoint(
+    api_key: *const c_char,
+    endpoint: *const c_char,
+) -> *mut TextSynthHandle {
+    catch_panic(ptr::null_mut(), || {
+        let api_key = match str_arg(api_key, "api_key") {
+            Ok(api_key) => api_key,
+            Err(err) => {
+                set_last_error(err);
+                return ptr::null_mut();
+            }
+        };
+        let endpoint = if endpoint.is_null() {
+            Ok("https://api.textsynth.com/v1")
+        } else {
+            str_arg(endpoint, "endpoint")
+        };
+        let client = match endpoint {
+            Ok(endpoint) => TextSynthClient::try_new_with_endpoint(api_key, endpoint),
+            Err(err) => {
+                set_last_error(err);
+                return ptr::null_mut();
+            }
+        };
+        let client = match client {
+            Ok(client) => client,
+            Err(err) => {
+                set_last_error(err);
+                return ptr::null_mut();
+            }
+        };
+        let runtime = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(runtime) => runtime,
+            Err(err) => {
+                set_last_error(err);
+                return ptr::null_mut();
+            }
+        };
+        Box::into_raw(Box::new(TextSynthHandle { client, runtime }))
+    })
+}
+
+/// Release a client.
+///
+/// # Safety
+///
+/// `handle` must be null or a client returned by this api that was not freed
+/// yet.
+#[no_mangle]
+pub unsafe extern "C" fn textsynth_client_free(handle: *mut TextSynthHandle) {
+    catch_panic((), || {
+        if !handle.is_null() {
+            drop(Box::from_raw(handle));
+        }
+    })
+}
+
+/// Release a string returned by this api.
+///
+/// # Safety
+///
+/// `text` must be null or a string returned by this api that was not freed
+/// yet.
+#[no_mangle]
+pub unsafe extern "C" fn textsynth_string_free(text: *mut c_char) {
+    catch_panic((), || {
+        if !text.is_null() {
+            drop(CString::from_raw(text));
+        }
+    })
+}
+
+/// Complete `prompt` with `engine`, passing each piece of generated text to
+/// `callback` as it is streamed. A `max_tokens` of 0 or less uses the
+/// server default. Returns `TEXTSYNTH_OK` or an error code.
+///
+/// # Safety
+///
+/// `handle` must be a live client, `engine` and `prompt` valid nul terminated
+/// strings. The text passed to `callback` is only valid during the call.
+#[no_mangle]
+pub unsafe extern "C" fn textsynth_complete(
+    handle: *const TextSynthHandle,
+    engine: *const c_char,
+    prompt: *const c_char,
+    max_tokens: c_int,
+    callback: Option<TextSynthCallback>,
+    user_data: *mut c_void,
+) -> c_int {
+    catch_panic(TEXTSYNTH_PANICKED, || {
+        let handle = match handle.as_ref() {
+            Some(handle) => handle,
+            None => {
+                set_last_error("handle is null");
+                return TEXTSYNTH_INVALID_ARGUMENT;
+            }
+        };
+        let callback = match callback {
+            Some(callback) => callback,
+            None => {
+                set_last_error("callback is null");
+                return TEXTSYNTH_INVALID_ARGUMENT;
+            }
+        };
+        let arguments = str_arg(engine, "engine")
+            .and_then(completion_engine)
+            .and_then(|engine| {
+                let mut request = completions::RequestBuilder::default();
+                request.prompt(str_arg(prompt, "prompt")?).stream(true);
+                if max_tokens > 0 {
+                    request.max_tokens(max_tokens as u32);
+                }
+                let request = request.build().map_err(|err| err.to_string())?;
+                Ok((engine, request))
+            });
+        let (engine, request) = match arguments {
+            Ok(arguments) => arguments,
+            Err(err) => {
+                set_last_error(err);
+                return TEXTSYNTH_INVALID_ARGUMENT;
+            }
+        };
+        let result = block_on(handle, async {
+            let mut response = handle.client.completions(&engine, &request).await?;
+            while let Some(chunk) = response.next().await {
+                for text in chunk?.text {
+                    let text = to_c_string(text);
+                    callback(text.as_ptr(), user_data);
+                }
+            }
+            Ok::<_, completions::Error>(())
+        })
+        .and_then(|result| result.map_err(|err| err.to_string()));
+        match result {
+            Ok(()) => TEXTSYNTH_OK,
+            Err(err) => {
+                set_last_error(err);
+                TEXTSYNTH_REQUEST_FAILED
+            }
+        }
+    })
+}
+
+/// Translate `text` from `source_lang` to `target_lang` with `engine`.
+/// Returns the translation, to be released with [`textsynth_string_free`], or
+/// null on error.
+///
+/// # Safety
+///
+/// `handle` must be a live client, the other arguments valid nul terminated
+/// strings.
+#[no_mangle]
+pub unsafe extern "C" fn textsynth_translate(
+    handle: *const TextSynthHandle,
+    engine: *const c_char,
+    text: *const c_char,
+    source_lang: *const c_char,
+    target_lang: *const c_char,
+) -> *mut c_char {
+    catch_panic(ptr::null_mut(), || {
+        let handle = match handle.as_ref() {
+            Some(handle) => handle,
+            None => {
+                set_last_error("handle is null");
+                return ptr::null_mut();
+            }
+        };
+        let arguments = str_arg(engine, "engine")
+            .and_then(translation_engine)
+            .and_then(|engine| {
+                let request = translate::RequestBuilder::default()
+                    .text([str_arg(text, "text")?.into()])
+                    .source_lang(str_arg(source_lang, "source_lang")?)
+                    .target_lang(str_arg(target_lang, "target_lang")?)
+                    .build()
+                    .map_err(|err| err.to_string())?;
+                Ok((engine, request))
+            });
+        let (engine, request) = match arguments {
+            Ok(arguments) => arguments,
+            Err(err) => {
+                set_last_error(err);
+                return ptr::null_mut();
+            }
+        };
+        let result = block_on(handle, handle.client.translate(&engine, &request))
+            .and_then(|result| result.map_err(|err| err.to_string()));
+        match result {
+            Ok(response) => match response.translations.into_iter().next() {
+                Some(translation) => to_c_string(translation.text).into_raw(),
+                None => {
+                    set_last_error("no translation returned");
+                    ptr::null_mut()
+                }
+            },
+            Err(err) => {
+                set_last_error(err);
+                ptr::null_mut()
+            }
+        }
+    })
+}
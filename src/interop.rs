@@ -0,0 +1,4 @@
+//! Provides conversions from and to the formats of other apis, so
+//! applications written against them can switch to TextSynth
+
+pub mod openai;
@@ -0,0 +1,388 @@
+//! Provides conversions between the OpenAI completion and chat formats and
+//! the TextSynth types
+//!
+//! Requests deserialized from OpenAI's json convert into TextSynth requests,
+//! with the engine taken from their `model`. TextSynth answers convert back
+//! into OpenAI's responses. Options TextSynth doesn't know, e.g. `user`, are
+//! ignored.
+
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::{BuildHasher, Hasher},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    chat,
+    completions::{self, finish::FinishReason, ResponseChunk},
+    engine::ParseEngineError,
+};
+
+/// `stop` option, a single string or a list of them
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Stop {
+    /// A single stop string.
+    One(String),
+    /// Several stop strings.
+    Many(Vec<String>),
+}
+
+impl From<Stop> for Vec<Arc<str>> {
+    fn from(stop: Stop) -> Self {
+        match stop {
+            Stop::One(stop) => vec![stop.into()],
+            Stop::Many(stops) => stops.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Request of OpenAI's completions api
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct CompletionRequest {
+    /// Name of the engine.
+    pub model: String,
+    /// The text to complete.
+    pub prompt: String,
+    /// Maximum number of tokens to generate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Sampling temperature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    /// Nucleus sampling probability.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    /// Number of completions to generate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n: Option<u32>,
+    /// If true, the answer is streamed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<bool>,
+    /// Stop the generation at these strings.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Stop>,
+    /// Penalty of tokens already generated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f64>,
+    /// Penalty of tokens proportional to how often they were generated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f64>,
+    /// Bias of the logits of token indexes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logit_bias: Option<HashMap<String, f64>>,
+    /// Number of most likely alternatives returned with the log probability
+    /// of every generated token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<u32>,
+}
+
+impl CompletionRequest {
+    /// The engine named by `model`, `Other` if the crate doesn't know it
+    pub fn engine(&self) -> completions::Engine {
+        self.model
+            .parse()
+            .unwrap_or_else(|_| completions::Engine::Other(self.model.clone()))
+    }
+}
+
+impl TryFrom<CompletionRequest> for completions::Request {
+    type Error = ConversionError;
+
+    fn try_from(request: CompletionRequest) -> Result<Self, Self::Error> {
+        let mut builder = completions::RequestBuilder::default();
+        builder.prompt(request.prompt);
+        if let Some(max_tokens) = request.max_tokens {
+            builder.max_tokens(max_tokens);
+        }
+        if let Some(temperature) = request.temperature {
+            builder.temperature(temperature);
+        }
+        if let Some(top_p) = request.top_p {
+            builder.top_p(top_p);
+        }
+        if let Some(n) = request.n {
+            builder.n(n);
+        }
+        if let Some(stream) = request.stream {
+            builder.stream(stream);
+        }
+        if let Some(stop) = request.stop {
+            builder.stop(Vec::from(stop));
+        }
+        if let Some(presence_penalty) = request.presence_penalty {
+            builder.presence_penalty(presence_penalty);
+        }
+        if let Some(frequency_penalty) = request.frequency_penalty {
+            builder.frequency_penalty(frequency_penalty);
+        }
+        if let Some(logit_bias) = request.logit_bias {
+            builder.logit_bias(logit_bias);
+        }
+        if let Some(top_logprobs) = request.logprobs {
+            builder.logprobs(true).top_logprobs(top_logprobs);
+        }
+        builder
+            .build()
+            .map_err(|err| ConversionError::InvalidRequest(err.to_string()))
+    }
+}
+
+/// Message of OpenAI's chat api
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChatMessage {
+    /// Author of the message, `system`, `user` or `assistant`.
+    pub role: String,
+    /// Text of the message.
+    pub content: String,
+}
+
+impl TryFrom<ChatMessage> for chat::Message {
+    type Error = ConversionError;
+
+    fn try_from(message: ChatMessage) -> Result<Self, Self::Error> {
+        match message.role.as_str() {
+            "system" => Ok(chat::Message::system(message.content)),
+            "user" => Ok(chat::Message::user(message.content)),
+            "assistant" => Ok(chat::Message::assistant(message.content)),
+            _ => Err(ConversionError::UnknownRole(message.role)),
+        }
+    }
+}
+
+/// Request of OpenAI's chat completions api
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ChatCompletionRequest {
+    /// Name of the engine.
+    pub model: String,
+    /// The conversation so far.
+    pub messages: Vec<ChatMessage>,
+    /// Maximum number of tokens to generate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Sampling temperature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    /// Nucleus sampling probability.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    /// If true, the answer is streamed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<bool>,
+    /// Stop the generation at these strings.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Stop>,
+}
+
+impl ChatCompletionRequest {
+    /// The engine named by `model`
+    pub fn engine(&self) -> Result<chat::Engine, ConversionError> {
+        Ok(self.model.parse()?)
+    }
+}
+
+impl TryFrom<ChatCompletionRequest> for chat::Request {
+    type Error = ConversionError;
+
+    fn try_from(request: ChatCompletionRequest) -> Result<Self, Self::Error> {
+        let messages = request
+            .messages
+            .into_iter()
+            .map(chat::Message::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut builder = chat::RequestBuilder::default();
+        builder.messages(messages);
+        if let Some(max_tokens) = request.max_tokens {
+            builder.max_tokens(max_tokens);
+        }
+        if let Some(temperature) = request.temperature {
+            builder.temperature(temperature);
+        }
+        if let Some(top_p) = request.top_p {
+            builder.top_p(top_p);
+        }
+        if let Some(stream) = request.stream {
+            builder.stream(stream);
+        }
+        if let Some(stop) = request.stop {
+            builder.stop(Vec::from(stop));
+        }
+        builder
+            .build()
+            .map_err(|err| ConversionError::InvalidRequest(err.to_string()))
+    }
+}
+
+/// Token counts of an answer
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// Number of tokens of the prompt.
+    pub prompt_tokens: u32,
+    /// Number of generated tokens.
+    pub completion_tokens: u32,
+    /// Number of tokens of the prompt and the generated text together.
+    pub total_tokens: u32,
+}
+
+impl Usage {
+    /// Token counts of `chunk`, None unless the api reported both
+    fn of(chunk: &ResponseChunk) -> Option<Self> {
+        let (prompt_tokens, completion_tokens) = (chunk.input_tokens?, chunk.output_tokens?);
+        Some(Usage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        })
+    }
+}
+
+/// Generated text of a [`CompletionResponse`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CompletionChoice {
+    /// The generated text.
+    pub text: String,
+    /// Index of the completion, below the request's `n`.
+    pub index: u32,
+    /// `length` if the api reported that the generation stopped at
+    /// `max_tokens`, else `stop` once it ended, None while it is streamed.
+    pub finish_reason: Option<String>,
+}
+
+/// Response of OpenAI's completions api
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CompletionResponse {
+    /// Identifier of the answer.
+    pub id: String,
+    /// `text_completion`.
+    pub object: String,
+    /// Unix timestamp of the answer in seconds.
+    pub created: u64,
+    /// Name of the engine.
+    pub model: String,
+    /// One generated text per completion.
+    pub choices: Vec<CompletionChoice>,
+    /// Token counts, present once the generation ended.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+impl CompletionResponse {
+    /// Convert the completion answer or streamed chunk `chunk` of the engine
+    /// `model`
+    pub fn from_chunk(model: &str, chunk: &ResponseChunk) -> Self {
+        CompletionResponse {
+            id: answer_id("cmpl"),
+            object: "text_completion".to_string(),
+            created: now(),
+            model: model.to_string(),
+            choices: chunk
+                .text
+                .iter()
+                .zip(0..)
+                .map(|(text, index)| CompletionChoice {
+                    text: text.clone(),
+                    index,
+                    finish_reason: finish_reason(chunk),
+                })
+                .collect(),
+            usage: Usage::of(chunk),
+        }
+    }
+}
+
+/// Generated message of a [`ChatCompletionResponse`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChatChoice {
+    /// Index of the answer.
+    pub index: u32,
+    /// The generated message, from the assistant.
+    pub message: ChatMessage,
+    /// `length` if the api reported that the generation stopped at
+    /// `max_tokens`, else `stop` once it ended, None while it is streamed.
+    pub finish_reason: Option<String>,
+}
+
+/// Response of OpenAI's chat completions api
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChatCompletionResponse {
+    /// Identifier of the answer.
+    pub id: String,
+    /// `chat.completion`.
+    pub object: String,
+    /// Unix timestamp of the answer in seconds.
+    pub created: u64,
+    /// Name of the engine.
+    pub model: String,
+    /// The generated message.
+    pub choices: Vec<ChatChoice>,
+    /// Token counts, present once the generation ended.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+impl ChatCompletionResponse {
+    /// Convert the chat answer `chunk` of the engine `model`
+    pub fn from_chunk(model: &str, chunk: &ResponseChunk) -> Self {
+        ChatCompletionResponse {
+            id: answer_id("chatcmpl"),
+            object: "chat.completion".to_string(),
+            created: now(),
+            model: model.to_string(),
+            choices: chunk
+                .text
+                .iter()
+                .zip(0..)
+                .map(|(text, index)| ChatChoice {
+                    index,
+                    message: ChatMessage {
+                        role: "assistant".to_string(),
+                        content: text.clone(),
+                    },
+                    finish_reason: finish_reason(chunk),
+                })
+                .collect(),
+            usage: Usage::of(chunk),
+        }
+    }
+}
+
+/// Error converting an OpenAI request
+#[derive(Error, Debug)]
+pub enum ConversionError {
+    /// The request isn't a valid TextSynth request
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    /// A message has a role other than `system`, `user` or `assistant`
+    #[error("Unknown role: {0}")]
+    UnknownRole(String),
+    /// The model isn't a known engine
+    #[error(transparent)]
+    UnknownEngine(#[from] ParseEngineError),
+}
+
+fn finish_reason(chunk: &ResponseChunk) -> Option<String> {
+    let reason = match chunk.finish_reason {
+        Some(FinishReason::Length) => "length",
+        _ => "stop",
+    };
+    chunk.reached_end.then(|| reason.to_string())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs())
+}
+
+/// Random identifier of an answer starting with `prefix`
+fn answer_id(prefix: &str) -> String {
+    format!(
+        "{}-{:016x}",
+        prefix,
+        RandomState::new().build_hasher().finish()
+    )
+}
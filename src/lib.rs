@@ -1,16 +1,78 @@
 #![warn(missing_docs)]
 //! TextSynth API Crate
+//!
+//! The `streaming` feature, enabled by default, provides streaming
+//! completions. Without it only the one-shot apis are available and the
+//! `futures` and `bytes` dependencies are dropped. The `batch` feature, also
+//! enabled by default, provides the concurrent helpers. The `retry` feature,
+//! enabled by default too, provides retrying of transient failures, and the
+//! `rate-limit` feature, also enabled by default, client-side rate limiting.
+//! The `blocking` feature provides a client for programs without an async
+//! runtime, and the `tracing` feature emits a span for every request and an
+//! event with the token counts of every answer. The `local-tokenizer`
+//! feature counts tokens without the api. The `surf` feature sends requests
+//! with surf on async-std, for programs without a tokio runtime, and the
+//! `ureq` feature provides a synchronous client on ureq.
+//!
+//! TLS is provided by rustls through the default `rustls` feature. Disable
+//! the default features and enable `native-tls` to use the platform's TLS
+//! library instead. The default `compression` feature negotiates gzip and
+//! brotli compressed responses, see [`TextSynthClientBuilder::compression`].
 
+#[cfg(feature = "batch")]
+pub mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod chat;
 pub mod completions;
+pub mod credits;
+pub mod detokenize;
+pub mod engine;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod interop;
+#[cfg(feature = "local-tokenizer")]
+pub mod local_tokenizer;
+pub mod middleware;
+pub mod prepare;
+pub mod prompt;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "rate-limit")]
+pub mod rate_limit;
+pub mod redact;
+#[cfg(feature = "retry")]
+pub mod retry;
+#[cfg(feature = "surf")]
+pub mod surf;
+pub mod text_to_image;
 pub mod tokenize;
+pub mod transcript;
 pub mod translate;
+pub mod transport;
+#[cfg(feature = "ureq")]
+pub mod ureq;
+pub mod usage;
+pub mod validation;
+#[cfg(feature = "vcr")]
+pub mod vcr;
+
+#[cfg(feature = "proptest")]
+mod arbitrary;
 
 #[macro_use]
 extern crate derive_builder;
 
-use std::fmt::Display;
+use std::{
+    fmt::{self, Display},
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
 
-use reqwest::Client;
+use reqwest::{header::HeaderMap, Client, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize};
+use thiserror::Error;
 
 /// Engine trait,
 pub trait IsEngine: Display {
@@ -24,31 +86,1227 @@ pub trait IsEngine: Display {
     }
 }
 
+/// Response together with the metadata of the HTTP response it was parsed from
+#[derive(Debug)]
+pub struct WithMeta<T> {
+    /// The parsed response.
+    pub response: T,
+    /// HTTP status of the response.
+    pub status: StatusCode,
+    /// HTTP headers of the response, including any rate limit headers.
+    pub headers: HeaderMap,
+    /// Time from sending the request until the response was parsed.
+    pub latency: Duration,
+}
+
+impl<T> WithMeta<T> {
+    /// Value of the response header `name`, if it is present and valid text
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)?.to_str().ok()
+    }
+
+    /// Id of the request, see [`REQUEST_ID_HEADER`]
+    pub fn request_id(&self) -> Option<&str> {
+        self.header(REQUEST_ID_HEADER)
+    }
+}
+
+/// Header identifying a request, to correlate failures with the logs of the
+/// application and TextSynth support
+///
+/// Every request is sent with a new random id unless it already has one.
+/// The id the api answers with, or else the one sent, is added to the
+/// headers of the response and kept in the errors of error answers.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// New random id of a request
+pub(crate) fn new_request_id() -> reqwest::header::HeaderValue {
+    use std::{
+        collections::hash_map::RandomState,
+        hash::{BuildHasher, Hasher},
+        sync::atomic::{AtomicU64, Ordering},
+    };
+
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    let id = format!("{:016x}", hasher.finish());
+    reqwest::header::HeaderValue::from_str(&id).expect("hex digits are a valid header")
+}
+
+/// Id of the request a response with `headers` answers
+fn request_id(headers: &HeaderMap) -> Option<String> {
+    Some(headers.get(REQUEST_ID_HEADER)?.to_str().ok()?.to_string())
+}
+
+/// Error answer of the TextSynth API
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{status}: {message}")]
+pub struct ApiError {
+    /// HTTP status of the answer.
+    pub status: StatusCode,
+    /// The `error` field of the answer, or the whole answer if it isn't an
+    /// error object.
+    pub message: String,
+    /// Id of the request, see [`REQUEST_ID_HEADER`].
+    pub request_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: String,
+}
+
+impl ApiError {
+    /// Error for an answer with `status`, `headers` and `body`
+    fn from_body(status: StatusCode, headers: &HeaderMap, body: String) -> Self {
+        let message = match serde_json::from_str::<ErrorBody>(&body) {
+            Ok(error) => error.error,
+            Err(_) => body,
+        };
+        ApiError {
+            status,
+            message,
+            request_id: request_id(headers),
+        }
+    }
+}
+
+/// The api answered with 429 Too Many Requests
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("rate limited: {message}")]
+pub struct RateLimited {
+    /// How long to wait before sending the request again, from the
+    /// `Retry-After` header of the answer.
+    pub retry_after: Option<Duration>,
+    /// The `error` field of the answer, or the whole answer if it isn't an
+    /// error object.
+    pub message: String,
+    /// Id of the request, see [`REQUEST_ID_HEADER`].
+    pub request_id: Option<String>,
+}
+
+/// The api answered with 401 Unauthorized, e.g. for a missing or mistyped
+/// api key
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("unauthorized: {message}")]
+pub struct Unauthorized {
+    /// The `error` field of the answer, or the whole answer if it isn't an
+    /// error object.
+    pub message: String,
+    /// Id of the request, see [`REQUEST_ID_HEADER`].
+    pub request_id: Option<String>,
+}
+
+/// The api answered with 403 Forbidden, e.g. for a revoked api key or an
+/// engine the account can't use
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("forbidden: {message}")]
+pub struct Forbidden {
+    /// The `error` field of the answer, or the whole answer if it isn't an
+    /// error object.
+    pub message: String,
+    /// Id of the request, see [`REQUEST_ID_HEADER`].
+    pub request_id: Option<String>,
+}
+
+/// Successful answer of the api that couldn't be parsed, e.g. the HTML page
+/// of a proxy in front of the api
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("couldn't parse answer with status {status} ({message}): {body}")]
+pub struct HttpError {
+    /// HTTP status of the answer.
+    pub status: StatusCode,
+    /// The whole answer.
+    pub body: String,
+    /// Why the answer couldn't be parsed.
+    pub message: String,
+    /// Id of the request, see [`REQUEST_ID_HEADER`].
+    pub request_id: Option<String>,
+}
+
+/// Error of a module that error answers of the api convert into
+pub(crate) trait ResponseError:
+    From<reqwest::Error>
+    + From<ApiError>
+    + From<RateLimited>
+    + From<Unauthorized>
+    + From<Forbidden>
+    + From<HttpError>
+{
+}
+
+impl<E> ResponseError for E where
+    E: From<reqwest::Error>
+        + From<ApiError>
+        + From<RateLimited>
+        + From<Unauthorized>
+        + From<Forbidden>
+        + From<HttpError>
+{
+}
+
+/// Parse the json `body` of a successful answer with `status` and `headers`,
+/// keeping the answer in the error if it can't be parsed
+pub(crate) fn parse_body<T: DeserializeOwned>(
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<T, HttpError> {
+    serde_json::from_slice(body).map_err(|err| HttpError {
+        status,
+        body: String::from_utf8_lossy(body).into_owned(),
+        message: err.to_string(),
+        request_id: request_id(headers),
+    })
+}
+
+/// Time to wait given by the `Retry-After` header in `headers`, either in
+/// seconds or as a date
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    match value.trim().parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => {
+            let date = httpdate::parse_http_date(value).ok()?;
+            Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+        }
+    }
+}
+
+/// Error for an answer with `status`, `headers` and `body` that isn't
+/// successful
+pub(crate) fn error_answer<E: ResponseError>(
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: String,
+) -> E {
+    let error = ApiError::from_body(status, headers, body);
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => RateLimited {
+            retry_after: retry_after(headers),
+            message: error.message,
+            request_id: error.request_id,
+        }
+        .into(),
+        StatusCode::UNAUTHORIZED => Unauthorized {
+            message: error.message,
+            request_id: error.request_id,
+        }
+        .into(),
+        StatusCode::FORBIDDEN => Forbidden {
+            message: error.message,
+            request_id: error.request_id,
+        }
+        .into(),
+        _ => error.into(),
+    }
+}
+
+/// Fail with the error answer of the api if `response` isn't successful
+async fn check_status<E: ResponseError>(
+    response: reqwest::Response,
+) -> Result<reqwest::Response, E> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let headers = response.headers().clone();
+    let body = response.text().await?;
+    Err(error_answer(status, &headers, body))
+}
+
 /// TextSynth API Client
+///
+/// Clones are cheap and share the connection pool, usage recorder and rate
+/// limits, so a client can be stored in the state of a web application. Its
+/// `Debug` output shows the endpoint but never the api key.
+#[derive(Clone)]
 pub struct TextSynthClient {
     /// endpoint of TextSynth API
     base_url: String,
     /// Client for making requests to the TextSynth API
     client: Client,
+    /// Sends the requests built with `client`
+    transport: Arc<dyn transport::Transport>,
+    /// Hooks around every attempt of a request, outermost first
+    middleware: Vec<Arc<dyn middleware::Middleware>>,
+    /// Authorization header sent with every request, none for a local server
+    authorization: Option<reqwest::header::HeaderValue>,
+    /// User-Agent header sent with every request, if not left to `client`
+    user_agent: Option<reqwest::header::HeaderValue>,
+    /// Records the usage of the client, if any
+    usage: Option<usage::UsageRecorder>,
+    /// Timeout of requests answered at once
+    request_timeout: Option<Duration>,
+    /// Id sent with every request instead of a new one, see
+    /// [`RequestOptions::request_id`]
+    request_id: Option<reqwest::header::HeaderValue>,
+    /// Timeout between the chunks of streamed answers
+    #[cfg_attr(not(feature = "streaming"), allow(dead_code))]
+    stream_read_timeout: Option<Duration>,
+    /// Number of chunks of streamed answers read ahead of the consumer
+    #[cfg_attr(not(feature = "streaming"), allow(dead_code))]
+    stream_prefetch: usize,
+    /// Maximum number of bytes of streamed answers received without
+    /// completing a chunk
+    #[cfg_attr(not(feature = "streaming"), allow(dead_code))]
+    stream_max_pending_bytes: Option<usize>,
+    /// How failed requests are retried
+    #[cfg(feature = "retry")]
+    retry: retry::RetryPolicy,
+    /// Limits on the requests sent, if any
+    #[cfg(feature = "rate-limit")]
+    rate_limiter: Option<Arc<rate_limit::RateLimiter>>,
+}
+
+impl fmt::Debug for TextSynthClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TextSynthClient")
+            .field("base_url", &self.base_url)
+            .field(
+                "authorization",
+                &self.authorization.as_ref().map(|_| REDACTED),
+            )
+            .field("user_agent", &self.user_agent)
+            .field("request_timeout", &self.request_timeout)
+            .field("stream_read_timeout", &self.stream_read_timeout)
+            .field("stream_prefetch", &self.stream_prefetch)
+            .field("stream_max_pending_bytes", &self.stream_max_pending_bytes)
+            .field("request_id", &self.request_id)
+            .finish_non_exhaustive()
+    }
 }
 
 impl TextSynthClient {
     /// Create a new TextSynth API Client with a custom endpoint
+    ///
+    /// # Panics
+    ///
+    /// Panics if the client can't be built, see
+    /// [`TextSynthClient::try_new_with_endpoint`].
     pub fn new_with_endpoint(api_key: &str, endpoint: &str) -> Self {
-        let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert(
-            reqwest::header::AUTHORIZATION,
-            reqwest::header::HeaderValue::from_str(&format!("Bearer {}", api_key)).unwrap(),
-        );
-        let reqwest_client = Client::builder().default_headers(headers);
-        TextSynthClient {
-            base_url: endpoint.to_string(),
-            client: reqwest_client.build().unwrap(),
-        }
+        Self::builder(api_key).endpoint(endpoint).build()
+    }
+
+    /// Create a new TextSynth API Client with a custom endpoint, failing if
+    /// the endpoint isn't an http or https url
+    pub fn try_new_with_endpoint(api_key: &str, endpoint: &str) -> Result<Self, BuildError> {
+        Self::builder(api_key).endpoint(endpoint).try_build()
     }
 
     /// Create a new TextSynth API Client
+    ///
+    /// # Panics
+    ///
+    /// Panics if the api key isn't valid in a header or the http client
+    /// can't be built, see [`TextSynthClient::try_new`].
     pub fn new(api_key: &str) -> Self {
-        Self::new_with_endpoint(api_key, "https://api.textsynth.com/v1")
+        Self::builder(api_key).build()
+    }
+
+    /// Create a new TextSynth API Client, failing if the api key isn't valid
+    /// in a header or the http client can't be built
+    pub fn try_new(api_key: &str) -> Result<Self, BuildError> {
+        Self::builder(api_key).try_build()
+    }
+
+    /// Create a new client of a self-hosted `ts_server` at `addr`, see
+    /// [`TextSynthClient::builder_local`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the client can't be built, see
+    /// [`TextSynthClientBuilder::try_build`].
+    pub fn new_local(addr: &str) -> Self {
+        Self::builder_local(addr).build()
+    }
+
+    /// Start building a client of a self-hosted `ts_server` at `addr`, which
+    /// needs no api key
+    ///
+    /// `addr` is a host with an optional port, e.g. `localhost` or
+    /// `192.168.1.2:9000`, or a full endpoint like `http://host:8080/v1`.
+    /// Without a scheme, plain http and the default port 8080 of `ts_server`
+    /// are used, without a path `/v1`. The models served locally are used
+    /// through `Engine::Other` or engines declared with
+    /// [`define_engine!`](crate::define_engine!).
+    pub fn builder_local(addr: &str) -> TextSynthClientBuilder {
+        TextSynthClientBuilder {
+            api_key: None,
+            ..Self::builder("").endpoint(&local_endpoint(addr))
+        }
+    }
+
+    /// Create a new TextSynth API Client from the environment, see
+    /// [`TextSynthClient::builder_from_env`]
+    pub fn from_env() -> Result<Self, BuildError> {
+        Self::builder_from_env()?.try_build()
+    }
+
+    /// Start building a TextSynth API Client with the api key from
+    /// `TEXTSYNTH_API_KEY`, or `TEXT_SYNTH_API_KEY` if it isn't set, and the
+    /// endpoint from `TEXTSYNTH_API_URL` if it is set
+    pub fn builder_from_env() -> Result<TextSynthClientBuilder, BuildError> {
+        let api_key = std::env::var(API_KEY_VAR)
+            .or_else(|_| std::env::var("TEXT_SYNTH_API_KEY"))
+            .map_err(|_| BuildError::MissingApiKey)?;
+        let builder = Self::builder(&api_key);
+        Ok(match std::env::var(API_URL_VAR) {
+            Ok(endpoint) => builder.endpoint(&endpoint),
+            Err(_) => builder,
+        })
+    }
+
+    /// This client with the settings of `options` overriding its own, e.g.
+    /// a longer timeout for a translation of many texts
+    ///
+    /// The returned client shares the connection pool, usage recorder and
+    /// rate limits with this one.
+    pub fn with_options(&self, options: &RequestOptions) -> TextSynthClient {
+        let mut client = self.clone();
+        if let Some(timeout) = options.timeout {
+            client.request_timeout = Some(timeout);
+        }
+        if let Some(timeout) = options.stream_read_timeout {
+            client.stream_read_timeout = Some(timeout);
+        }
+        if let Some(request_id) = &options.request_id {
+            client.request_id = Some(request_id.clone());
+        }
+        client
+    }
+
+    /// The requests and tokens recorded so far, including the tokens
+    /// reported at the end of streams, if the client was built with a
+    /// [`usage_recorder`](TextSynthClientBuilder::usage_recorder)
+    pub fn usage(&self) -> Option<usage::Usage> {
+        self.usage.as_ref().map(usage::UsageRecorder::snapshot)
+    }
+
+    /// Start building a TextSynth API Client
+    pub fn builder(api_key: &str) -> TextSynthClientBuilder {
+        TextSynthClientBuilder {
+            api_key: Some(api_key.to_string()),
+            endpoint: "https://api.textsynth.com/v1".to_string(),
+            user_agent: None,
+            client: None,
+            transport: None,
+            middleware: Vec::new(),
+            usage: None,
+            connect_timeout: None,
+            request_timeout: None,
+            stream_read_timeout: None,
+            stream_prefetch: 0,
+            stream_max_pending_bytes: None,
+            proxy: None,
+            resolve: Vec::new(),
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            tcp_keepalive: None,
+            http_version: HttpVersion::default(),
+            #[cfg(feature = "compression")]
+            compression: true,
+            #[cfg(feature = "retry")]
+            retry: retry::RetryPolicy::default(),
+            #[cfg(feature = "rate-limit")]
+            rate_limit: None,
+        }
+    }
+
+    /// Post `body` to `url` and parse the json response, keeping its metadata
+    async fn post_with_meta<T, E>(&self, url: &str, body: String) -> Result<WithMeta<T>, E>
+    where
+        T: DeserializeOwned,
+        E: ResponseError,
+    {
+        self.send_with_meta(self.client.post(url).body(body)).await
+    }
+
+    /// Send `request`, retrying transient failures, and fail with the error
+    /// answer of the api if the response isn't successful
+    pub(crate) async fn send_checked<E>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, E>
+    where
+        E: ResponseError,
+    {
+        let mut request = request.build()?;
+        self.insert_headers(request.headers_mut());
+        let request_id = request
+            .headers_mut()
+            .entry(REQUEST_ID_HEADER)
+            .or_insert_with(|| self.request_id.clone().unwrap_or_else(new_request_id))
+            .clone();
+        let engine = engine_of(request.url()).map(str::to_string);
+        let start = Instant::now();
+        #[cfg(feature = "tracing")]
+        let response = {
+            use tracing::Instrument;
+
+            let span = tracing::debug_span!(
+                "textsynth_request",
+                method = %request.method(),
+                endpoint = %request.url().path(),
+                engine = engine.as_deref(),
+                request_id = request_id.to_str().unwrap_or_default(),
+                request_size = request.body().and_then(|body| body.as_bytes()).map(<[u8]>::len),
+                status = tracing::field::Empty,
+                duration_ms = tracing::field::Empty,
+            );
+            let response = self.send(request).instrument(span.clone()).await;
+            span.record("duration_ms", &(start.elapsed().as_millis() as u64));
+            if let Ok(response) = &response {
+                span.record("status", &response.status().as_u16());
+            }
+            response
+        };
+        #[cfg(not(feature = "tracing"))]
+        let response = self.send(request).await;
+        if let Some(usage) = &self.usage {
+            let success = matches!(&response, Ok(response) if response.status().is_success());
+            usage.record_request(engine.as_deref(), start.elapsed(), success);
+        }
+        let mut response = response?;
+        response
+            .headers_mut()
+            .entry(REQUEST_ID_HEADER)
+            .or_insert(request_id);
+        check_status(response).await
+    }
+
+    /// Insert the headers sent with every request into `headers`: the
+    /// authorization, the User-Agent and the fixed request id, if any
+    fn insert_headers(&self, headers: &mut HeaderMap) {
+        if let Some(authorization) = &self.authorization {
+            headers.insert(reqwest::header::AUTHORIZATION, authorization.clone());
+        }
+        if let Some(user_agent) = &self.user_agent {
+            headers.insert(reqwest::header::USER_AGENT, user_agent.clone());
+        }
+        if let Some(request_id) = &self.request_id {
+            headers
+                .entry(REQUEST_ID_HEADER)
+                .or_insert_with(|| request_id.clone());
+        }
+    }
+
+    /// Send `request` through the middleware and transport, respecting the
+    /// rate limit and retrying transient failures
+    async fn send(&self, request: reqwest::Request) -> Result<reqwest::Response, reqwest::Error> {
+        #[cfg(feature = "rate-limit")]
+        let _permit = match &self.rate_limiter {
+            Some(rate_limiter) => Some(rate_limiter.acquire().await),
+            None => None,
+        };
+        let next = middleware::Next::new(&self.middleware, &*self.transport);
+        #[cfg(feature = "retry")]
+        return self.retry.send(&next, request).await;
+        #[cfg(not(feature = "retry"))]
+        next.run(request).await
+    }
+
+    /// Send `request` and parse the json response, keeping its metadata
+    async fn send_with_meta<T, E>(&self, request: reqwest::RequestBuilder) -> Result<WithMeta<T>, E>
+    where
+        T: DeserializeOwned,
+        E: ResponseError,
+    {
+        let request = match self.request_timeout {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        };
+        let start = Instant::now();
+        let response = self.send_checked::<E>(request).await?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+        let response = parse_body(status, &headers, &body)?;
+        Ok(WithMeta {
+            response,
+            status,
+            headers,
+            latency: start.elapsed(),
+        })
+    }
+}
+
+/// Api name of the engine a request to `url` is made to
+fn engine_of(url: &reqwest::Url) -> Option<&str> {
+    let mut segments = url.path_segments()?;
+    segments.find(|segment| *segment == "engines")?;
+    segments.next()
+}
+
+/// Report the token counts of an answer of `engine` to `usage` and, with the
+/// `tracing` feature, in an event
+pub(crate) fn record_tokens(
+    usage: Option<&usage::UsageRecorder>,
+    engine: &dyn Display,
+    input_tokens: Option<u32>,
+    output_tokens: Option<u32>,
+) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(%engine, input_tokens, output_tokens, "textsynth tokens");
+    if let Some(usage) = usage {
+        usage.record_tokens(&engine.to_string(), input_tokens, output_tokens);
+    }
+}
+
+/// Error building a TextSynth API Client
+#[derive(Error, Debug)]
+pub enum BuildError {
+    /// The api key isn't set in the environment
+    #[error("Missing api key: set {}", API_KEY_VAR)]
+    MissingApiKey,
+    /// The endpoint isn't an http or https url
+    #[error("Invalid endpoint {0:?}: expected an http or https url")]
+    InvalidEndpoint(String),
+    /// The api key can't be sent in a header
+    #[error("Invalid api key: {0}")]
+    InvalidApiKey(#[from] reqwest::header::InvalidHeaderValue),
+    /// The user agent can't be sent in a header
+    #[error("Invalid user agent: {0}")]
+    InvalidUserAgent(reqwest::header::InvalidHeaderValue),
+    /// Error from Reqwest
+    #[error("Reqwest error: {0}")]
+    RequestError(#[from] reqwest::Error),
+}
+
+/// Error of any request of this crate
+///
+/// Every module error converts into it, so applications can handle all
+/// TextSynth failures with a single `?`.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Error of a completion request
+    #[error("Completion error: {0}")]
+    Completions(#[from] completions::Error),
+    /// Error of a log probability request
+    #[error("Logprob error: {0}")]
+    Logprob(#[from] completions::logprob::Error),
+    /// Error of a chat request
+    #[error("Chat error: {0}")]
+    Chat(#[from] chat::Error),
+    /// Error of a translation request
+    #[error("Translation error: {0}")]
+    Translate(#[from] translate::Error),
+    /// Error of a tokenization request
+    #[error("Tokenize error: {0}")]
+    Tokenize(#[from] tokenize::Error),
+    /// Error of a text to image request
+    #[error("Text to image error: {0}")]
+    TextToImage(#[from] text_to_image::Error),
+    /// Error of a transcription request
+    #[error("Transcript error: {0}")]
+    Transcript(#[from] transcript::Error),
+    /// Error of a credits request
+    #[error("Credits error: {0}")]
+    Credits(#[from] credits::Error),
+    /// Error building the client
+    #[error("Build error: {0}")]
+    Build(#[from] BuildError),
+    /// Unknown engine name
+    #[error("{0}")]
+    ParseEngine(#[from] engine::ParseEngineError),
+    /// Unknown language code
+    #[error("{0}")]
+    ParseLanguage(#[from] translate::language::ParseLanguageError),
+    /// Invalid request
+    #[error("Invalid request: {0}")]
+    Validation(#[from] validation::ValidationError),
+    /// Error converting an OpenAI request
+    #[error("OpenAI conversion error: {0}")]
+    Conversion(#[from] interop::openai::ConversionError),
+    /// Error of a local tokenizer
+    #[cfg(feature = "local-tokenizer")]
+    #[error("Local tokenizer error: {0}")]
+    LocalTokenizer(#[from] local_tokenizer::Error),
+}
+
+impl Error {
+    /// The rate limit answer of the api, if the request failed with one
+    pub fn rate_limited(&self) -> Option<&RateLimited> {
+        match self {
+            Error::Completions(completions::Error::RateLimited(err))
+            | Error::Logprob(completions::logprob::Error::RateLimited(err))
+            | Error::Chat(chat::Error::RateLimited(err))
+            | Error::Translate(translate::Error::RateLimited(err))
+            | Error::Tokenize(tokenize::Error::RateLimited(err))
+            | Error::TextToImage(text_to_image::Error::RateLimited(err))
+            | Error::Transcript(transcript::Error::RateLimited(err))
+            | Error::Credits(credits::Error::RateLimited(err)) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// The error answer of the api, if the request failed with one
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            Error::Completions(completions::Error::ApiError(err))
+            | Error::Logprob(completions::logprob::Error::ApiError(err))
+            | Error::Chat(chat::Error::ApiError(err))
+            | Error::Translate(translate::Error::ApiError(err))
+            | Error::Tokenize(tokenize::Error::ApiError(err))
+            | Error::TextToImage(text_to_image::Error::ApiError(err))
+            | Error::Transcript(transcript::Error::ApiError(err))
+            | Error::Credits(credits::Error::ApiError(err)) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// The 401 Unauthorized answer of the api, if the request failed with one
+    pub fn unauthorized(&self) -> Option<&Unauthorized> {
+        match self {
+            Error::Completions(completions::Error::Unauthorized(err))
+            | Error::Logprob(completions::logprob::Error::Unauthorized(err))
+            | Error::Chat(chat::Error::Unauthorized(err))
+            | Error::Translate(translate::Error::Unauthorized(err))
+            | Error::Tokenize(tokenize::Error::Unauthorized(err))
+            | Error::TextToImage(text_to_image::Error::Unauthorized(err))
+            | Error::Transcript(transcript::Error::Unauthorized(err))
+            | Error::Credits(credits::Error::Unauthorized(err)) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// The 403 Forbidden answer of the api, if the request failed with one
+    pub fn forbidden(&self) -> Option<&Forbidden> {
+        match self {
+            Error::Completions(completions::Error::Forbidden(err))
+            | Error::Logprob(completions::logprob::Error::Forbidden(err))
+            | Error::Chat(chat::Error::Forbidden(err))
+            | Error::Translate(translate::Error::Forbidden(err))
+            | Error::Tokenize(tokenize::Error::Forbidden(err))
+            | Error::TextToImage(text_to_image::Error::Forbidden(err))
+            | Error::Transcript(transcript::Error::Forbidden(err))
+            | Error::Credits(credits::Error::Forbidden(err)) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Id of the request, if it failed with an answer of the api, see
+    /// [`REQUEST_ID_HEADER`]
+    pub fn request_id(&self) -> Option<&str> {
+        let request_id = if let Some(err) = self.api_error() {
+            &err.request_id
+        } else if let Some(err) = self.rate_limited() {
+            &err.request_id
+        } else if let Some(err) = self.unauthorized() {
+            &err.request_id
+        } else if let Some(err) = self.forbidden() {
+            &err.request_id
+        } else if let Some(err) = self.http_error() {
+            &err.request_id
+        } else {
+            return None;
+        };
+        request_id.as_deref()
+    }
+
+    /// The successful answer of the api that couldn't be parsed, if the
+    /// request failed with one
+    pub fn http_error(&self) -> Option<&HttpError> {
+        match self {
+            Error::Completions(completions::Error::Http(err))
+            | Error::Logprob(completions::logprob::Error::Http(err))
+            | Error::Chat(chat::Error::Http(err))
+            | Error::Translate(translate::Error::Http(err))
+            | Error::Tokenize(tokenize::Error::Http(err))
+            | Error::TextToImage(text_to_image::Error::Http(err))
+            | Error::Transcript(transcript::Error::Http(err))
+            | Error::Credits(credits::Error::Http(err)) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// `endpoint` parsed as the base of the api urls, without trailing slashes
+pub(crate) fn normalize_endpoint(endpoint: &str) -> Result<String, BuildError> {
+    let invalid = || BuildError::InvalidEndpoint(endpoint.to_string());
+    let url = url::Url::parse(endpoint.trim()).map_err(|_| invalid())?;
+    if !matches!(url.scheme(), "http" | "https")
+        || !url.has_host()
+        || url.query().is_some()
+        || url.fragment().is_some()
+    {
+        return Err(invalid());
+    }
+    Ok(url.as_str().trim_end_matches('/').to_string())
+}
+
+/// Port `ts_server` listens on by default
+const LOCAL_PORT: u16 = 8080;
+
+/// Endpoint of a self-hosted `ts_server` at `addr`, see
+/// [`TextSynthClient::builder_local`]
+fn local_endpoint(addr: &str) -> String {
+    let addr = addr.trim();
+    let has_scheme = addr.contains("://");
+    let url = if has_scheme {
+        url::Url::parse(addr)
+    } else {
+        url::Url::parse(&format!("http://{}", addr))
+    };
+    match url {
+        Ok(mut url) => {
+            // the url drops ports that are the default of the scheme, like 80
+            if !has_scheme && !has_port(addr) {
+                let _ = url.set_port(Some(LOCAL_PORT));
+            }
+            if url.path() == "/" {
+                url.set_path("/v1");
+            }
+            url.to_string()
+        }
+        // left for the builder to reject
+        Err(_) => addr.to_string(),
+    }
+}
+
+/// Whether the host `addr`, given without a scheme, has a port
+fn has_port(addr: &str) -> bool {
+    let authority = addr.split(['/', '?', '#']).next().unwrap_or(addr);
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    // the colons of IPv6 addresses are within brackets
+    let host_end = host.rfind(']').map_or(0, |end| end + 1);
+    host[host_end..].contains(':')
+}
+
+/// Environment variable holding the api key
+const API_KEY_VAR: &str = "TEXTSYNTH_API_KEY";
+
+/// Environment variable holding the endpoint
+const API_URL_VAR: &str = "TEXTSYNTH_API_URL";
+
+/// User-Agent header sent by clients, naming the crate and its version
+pub const DEFAULT_USER_AGENT: &str = concat!("elikoga-textsynth-rs/", env!("CARGO_PKG_VERSION"));
+
+/// Shown instead of secrets in `Debug` output
+const REDACTED: &str = "[redacted]";
+
+/// HTTP version the client talks to the api with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HttpVersion {
+    /// HTTP/2 if the server offers it during the TLS handshake, HTTP/1.1
+    /// otherwise.
+    #[default]
+    Negotiate,
+    /// Always HTTP/1.1, e.g. for proxies mishandling HTTP/2.
+    Http1Only,
+    /// HTTP/2 without negotiating it, e.g. for self-hosted servers reached
+    /// without TLS.
+    Http2PriorKnowledge,
+}
+
+/// Settings overriding the ones of a client for some requests, see
+/// [`TextSynthClient::with_options`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestOptions {
+    timeout: Option<Duration>,
+    stream_read_timeout: Option<Duration>,
+    request_id: Option<reqwest::header::HeaderValue>,
+}
+
+impl RequestOptions {
+    /// Keep the settings of the client
+    pub fn new() -> Self {
+        RequestOptions::default()
+    }
+
+    /// Fail requests answered at once that don't finish within `timeout`,
+    /// see [`TextSynthClientBuilder::request_timeout`]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// End streamed answers with an error if no data arrives for `timeout`,
+    /// see [`TextSynthClientBuilder::stream_read_timeout`]
+    pub fn stream_read_timeout(mut self, timeout: Duration) -> Self {
+        self.stream_read_timeout = Some(timeout);
+        self
+    }
+
+    /// Send `id` as the [`REQUEST_ID_HEADER`] instead of a new random id,
+    /// e.g. the id of the request of the application being served
+    pub fn request_id(mut self, id: reqwest::header::HeaderValue) -> Self {
+        self.request_id = Some(id);
+        self
+    }
+}
+
+/// Proxy the client connects through
+///
+/// Its `Debug` output doesn't show the password.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ProxyConfig {
+    /// Url of the proxy, e.g. `http://proxy:3128`. `socks5://` urls need the
+    /// `socks` feature.
+    pub url: String,
+    /// Username and password to authenticate to the proxy with.
+    pub basic_auth: Option<(String, String)>,
+    /// Hosts connected to directly. An entry also matches its subdomains.
+    pub no_proxy: Vec<String>,
+}
+
+impl fmt::Debug for ProxyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyConfig")
+            .field("url", &self.url)
+            .field(
+                "basic_auth",
+                &self
+                    .basic_auth
+                    .as_ref()
+                    .map(|(username, _)| (username, REDACTED)),
+            )
+            .field("no_proxy", &self.no_proxy)
+            .finish()
+    }
+}
+
+impl ProxyConfig {
+    /// Connect to every host through the proxy at `url`
+    pub fn new(url: &str) -> Self {
+        ProxyConfig {
+            url: url.to_string(),
+            basic_auth: None,
+            no_proxy: Vec::new(),
+        }
+    }
+
+    /// Authenticate to the proxy with `username` and `password`
+    pub fn basic_auth(mut self, username: &str, password: &str) -> Self {
+        self.basic_auth = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    /// Connect to `host` and its subdomains directly
+    pub fn no_proxy(mut self, host: &str) -> Self {
+        self.no_proxy.push(host.to_string());
+        self
+    }
+
+    /// Returns true if `host` is connected to through the proxy
+    pub fn proxies(&self, host: &str) -> bool {
+        !self.no_proxy.iter().any(|no_proxy| {
+            let no_proxy = no_proxy.trim_start_matches('.');
+            host == no_proxy
+                || host
+                    .strip_suffix(no_proxy)
+                    .is_some_and(|subdomain| subdomain.ends_with('.'))
+        })
+    }
+
+    fn build(self) -> Result<reqwest::Proxy, reqwest::Error> {
+        // fail early on invalid urls rather than on every request
+        reqwest::Proxy::all(self.url.as_str())?;
+        let basic_auth = self.basic_auth.clone();
+        let proxy = reqwest::Proxy::custom(move |target| {
+            target
+                .host_str()
+                .filter(|host| self.proxies(host))
+                .map(|_| self.url.clone())
+        });
+        Ok(match basic_auth {
+            Some((username, password)) => proxy.basic_auth(&username, &password),
+            None => proxy,
+        })
+    }
+}
+
+/// Builder for a TextSynth API Client
+pub struct TextSynthClientBuilder {
+    api_key: Option<String>,
+    endpoint: String,
+    user_agent: Option<String>,
+    client: Option<reqwest::ClientBuilder>,
+    transport: Option<Arc<dyn transport::Transport>>,
+    middleware: Vec<Arc<dyn middleware::Middleware>>,
+    usage: Option<usage::UsageRecorder>,
+    connect_timeout: Option<Duration>,
+    request_timeout: Option<Duration>,
+    stream_read_timeout: Option<Duration>,
+    stream_prefetch: usize,
+    stream_max_pending_bytes: Option<usize>,
+    proxy: Option<ProxyConfig>,
+    resolve: Vec<(String, IpAddr)>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Option<Duration>>,
+    tcp_keepalive: Option<Duration>,
+    http_version: HttpVersion,
+    #[cfg(feature = "compression")]
+    compression: bool,
+    #[cfg(feature = "retry")]
+    retry: retry::RetryPolicy,
+    #[cfg(feature = "rate-limit")]
+    rate_limit: Option<rate_limit::RateLimit>,
+}
+
+impl fmt::Debug for TextSynthClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut builder = f.debug_struct("TextSynthClientBuilder");
+        builder
+            .field("api_key", &self.api_key.as_ref().map(|_| REDACTED))
+            .field("endpoint", &self.endpoint)
+            .field("user_agent", &self.user_agent)
+            .field("client", &self.client)
+            .field("transport", &self.transport)
+            .field("middleware", &self.middleware)
+            .field("usage", &self.usage)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("request_timeout", &self.request_timeout)
+            .field("stream_read_timeout", &self.stream_read_timeout)
+            .field("stream_prefetch", &self.stream_prefetch)
+            .field("stream_max_pending_bytes", &self.stream_max_pending_bytes)
+            .field("proxy", &self.proxy)
+            .field("resolve", &self.resolve)
+            .field("pool_max_idle_per_host", &self.pool_max_idle_per_host)
+            .field("pool_idle_timeout", &self.pool_idle_timeout)
+            .field("tcp_keepalive", &self.tcp_keepalive)
+            .field("http_version", &self.http_version);
+        #[cfg(feature = "compression")]
+        builder.field("compression", &self.compression);
+        #[cfg(feature = "retry")]
+        builder.field("retry", &self.retry);
+        #[cfg(feature = "rate-limit")]
+        builder.field("rate_limit", &self.rate_limit);
+        builder.finish()
+    }
+}
+
+impl TextSynthClientBuilder {
+    /// Use a custom endpoint instead of the public TextSynth API, e.g.
+    /// `http://localhost:8080/v1`. Trailing slashes are ignored.
+    pub fn endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = endpoint.to_string();
+        self
+    }
+
+    /// Send `user_agent` as the User-Agent header instead of
+    /// [`DEFAULT_USER_AGENT`], e.g. to name the application. Without it, the
+    /// user agent of a [`client_builder`](Self::client_builder) is kept.
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    /// Connect to `addr` for `domain` instead of resolving it with DNS. The
+    /// port is still taken from the endpoint.
+    pub fn resolve(mut self, domain: &str, addr: IpAddr) -> Self {
+        self.resolve.push((domain.to_string(), addr));
+        self
+    }
+
+    /// Retry requests failing with transient errors according to `retry`
+    #[cfg(feature = "retry")]
+    pub fn retry(mut self, retry: retry::RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Limit the requests sent by the client according to `rate_limit`
+    #[cfg(feature = "rate-limit")]
+    pub fn rate_limit(mut self, rate_limit: rate_limit::RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Fail connecting to the api after `timeout`
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Fail requests answered at once, i.e. everything but streamed
+    /// completions, that don't finish within `timeout`
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// End streamed answers with an error if no data arrives for `timeout`.
+    /// Long generations are not limited as long as they keep streaming.
+    pub fn stream_read_timeout(mut self, timeout: Duration) -> Self {
+        self.stream_read_timeout = Some(timeout);
+        self
+    }
+
+    /// Read up to `chunks` parsed chunks of streamed answers ahead of the
+    /// consumer, on a task of the runtime. Streams are only read when polled
+    /// by default. Reading stops while the buffer is full, so slow consumers
+    /// slow down the server rather than grow the buffer.
+    pub fn stream_prefetch(mut self, chunks: usize) -> Self {
+        self.stream_prefetch = chunks;
+        self
+    }
+
+    /// End streamed answers with an error if more than `bytes` bytes arrive
+    /// without completing a chunk, instead of buffering them without limit
+    pub fn stream_max_pending_bytes(mut self, bytes: usize) -> Self {
+        self.stream_max_pending_bytes = Some(bytes);
+        self
+    }
+
+    /// Keep at most `max` idle connections per host open for reuse
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Close connections that were idle for `timeout`, or never if None
+    pub fn pool_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Send TCP keep-alive probes on idle connections every `interval`, so
+    /// connections through NATs and load balancers stay open
+    pub fn tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Talk to the api with the HTTP version chosen by `version`
+    pub fn http_version(mut self, version: HttpVersion) -> Self {
+        self.http_version = version;
+        self
+    }
+
+    /// Ask for gzip or brotli compressed responses and decompress them, on by
+    /// default. Long completions and large translation batches compress
+    /// well, which saves time on slow links.
+    #[cfg(feature = "compression")]
+    pub fn compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Connect through the proxy configured by `proxy`
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Build the http client from `client`, to configure e.g. proxies, TLS or
+    /// connection pooling. The authorization header and the options of this
+    /// builder are added to it.
+    pub fn client_builder(mut self, client: reqwest::ClientBuilder) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Record the requests, errors and tokens of the client into `usage`
+    pub fn usage_recorder(mut self, usage: usage::UsageRecorder) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// Send requests through `transport` instead of the http client, e.g. a
+    /// [`MockTransport`](transport::MockTransport) answering with canned json
+    pub fn transport(mut self, transport: impl transport::Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Pass every attempt of every request through `middleware`, after the
+    /// middleware added before
+    pub fn middleware(mut self, middleware: impl middleware::Middleware + 'static) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Build the TextSynth API Client
+    ///
+    /// # Panics
+    ///
+    /// Panics if the client can't be built, see
+    /// [`TextSynthClientBuilder::try_build`].
+    pub fn build(self) -> TextSynthClient {
+        self.try_build().expect("failed to build TextSynth client")
+    }
+
+    /// Build the TextSynth API Client, failing if the endpoint isn't an http
+    /// or https url, the api key isn't valid in a header or the http client
+    /// can't be built
+    pub fn try_build(self) -> Result<TextSynthClient, BuildError> {
+        let base_url = normalize_endpoint(&self.endpoint)?;
+        let user_agent = match (&self.user_agent, &self.client) {
+            (Some(user_agent), _) => Some(
+                reqwest::header::HeaderValue::from_str(user_agent)
+                    .map_err(BuildError::InvalidUserAgent)?,
+            ),
+            (None, Some(_)) => None,
+            (None, None) => Some(reqwest::header::HeaderValue::from_static(
+                DEFAULT_USER_AGENT,
+            )),
+        };
+        let authorization = match &self.api_key {
+            Some(api_key) => {
+                let mut authorization =
+                    reqwest::header::HeaderValue::from_str(&format!("Bearer {}", api_key))?;
+                authorization.set_sensitive(true);
+                Some(authorization)
+            }
+            None => None,
+        };
+        let mut reqwest_client = self.client.unwrap_or_else(Client::builder);
+        if let Some(proxy) = self.proxy {
+            reqwest_client = reqwest_client.proxy(proxy.build()?);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            reqwest_client = reqwest_client.connect_timeout(timeout);
+        }
+        for (domain, addr) in &self.resolve {
+            reqwest_client = reqwest_client.resolve(domain, SocketAddr::new(*addr, 0));
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            reqwest_client = reqwest_client.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            reqwest_client = reqwest_client.pool_idle_timeout(timeout);
+        }
+        if let Some(interval) = self.tcp_keepalive {
+            reqwest_client = reqwest_client.tcp_keepalive(interval);
+        }
+        reqwest_client = match self.http_version {
+            HttpVersion::Negotiate => reqwest_client,
+            HttpVersion::Http1Only => reqwest_client.http1_only(),
+            HttpVersion::Http2PriorKnowledge => reqwest_client.http2_prior_knowledge(),
+        };
+        #[cfg(feature = "compression")]
+        {
+            reqwest_client = reqwest_client
+                .gzip(self.compression)
+                .brotli(self.compression);
+        }
+        let client = reqwest_client.build()?;
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => Arc::new(client.clone()),
+        };
+        Ok(TextSynthClient {
+            base_url,
+            client,
+            transport,
+            middleware: self.middleware,
+            authorization,
+            user_agent,
+            usage: self.usage,
+            request_timeout: self.request_timeout,
+            request_id: None,
+            stream_read_timeout: self.stream_read_timeout,
+            stream_prefetch: self.stream_prefetch,
+            stream_max_pending_bytes: self.stream_max_pending_bytes,
+            #[cfg(feature = "retry")]
+            retry: self.retry,
+            #[cfg(feature = "rate-limit")]
+            rate_limiter: self
+                .rate_limit
+                .map(|limit| Arc::new(rate_limit::RateLimiter::new(limit))),
+        })
     }
 }
@@ -0,0 +1,90 @@
+//! Provides counting, encoding and decoding tokens without the api
+//!
+//! A [`LocalTokenizer`] loads the tokenizer definition of an engine, e.g. the
+//! `tokenizer.json` of GPT-J or GPT-NeoX published on Hugging Face, and
+//! tokenizes locally instead of a round trip to the tokenize api.
+
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::completions::Engine;
+
+/// Url of the tokenizer definition of `engine`, if it is published
+pub fn tokenizer_url(engine: &Engine) -> Option<&'static str> {
+    match engine {
+        Engine::GPTJ6B | Engine::Boris6B => {
+            Some("https://huggingface.co/EleutherAI/gpt-j-6B/resolve/main/tokenizer.json")
+        }
+        Engine::GPTNeoX20B => {
+            Some("https://huggingface.co/EleutherAI/gpt-neox-20b/resolve/main/tokenizer.json")
+        }
+        _ => None,
+    }
+}
+
+/// Tokenizer of an engine running locally
+#[derive(Debug, Clone)]
+pub struct LocalTokenizer {
+    tokenizer: tokenizers::Tokenizer,
+}
+
+impl LocalTokenizer {
+    /// Load the tokenizer definition at `path`
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(LocalTokenizer {
+            tokenizer: tokenizers::Tokenizer::from_file(path).map_err(Error::TokenizerError)?,
+        })
+    }
+
+    /// Load the tokenizer definition `bytes`, e.g. bundled with
+    /// `include_bytes!`
+    pub fn from_bytes(bytes: impl AsRef<[u8]>) -> Result<Self, Error> {
+        Ok(LocalTokenizer {
+            tokenizer: tokenizers::Tokenizer::from_bytes(bytes).map_err(Error::TokenizerError)?,
+        })
+    }
+
+    /// Download the tokenizer definition of `engine`, see [`tokenizer_url`]
+    pub async fn download(engine: &Engine) -> Result<Self, Error> {
+        let url =
+            tokenizer_url(engine).ok_or_else(|| Error::UnknownTokenizer(engine.to_string()))?;
+        let bytes = reqwest::get(url).await?.error_for_status()?.bytes().await?;
+        Self::from_bytes(bytes)
+    }
+
+    /// Token indexes of `text`, like the tokenize api returns them
+    pub fn encode(&self, text: &str) -> Result<Vec<u32>, Error> {
+        let encoding = self
+            .tokenizer
+            .encode(text, false)
+            .map_err(Error::TokenizerError)?;
+        Ok(encoding.get_ids().to_vec())
+    }
+
+    /// Number of tokens of `text`
+    pub fn count_tokens(&self, text: &str) -> Result<usize, Error> {
+        Ok(self.encode(text)?.len())
+    }
+
+    /// Text of the token indexes `tokens`
+    pub fn decode(&self, tokens: &[u32]) -> Result<String, Error> {
+        self.tokenizer
+            .decode(tokens, false)
+            .map_err(Error::TokenizerError)
+    }
+}
+
+#[derive(Error, Debug)]
+/// Error of a local tokenizer
+pub enum Error {
+    /// Error from the tokenizers crate
+    #[error("Tokenizer error: {0}")]
+    TokenizerError(tokenizers::Error),
+    /// Error from Reqwest
+    #[error("Reqwest error: {0}")]
+    RequestError(#[from] reqwest::Error),
+    /// No tokenizer definition of the engine is known
+    #[error("No tokenizer known for engine {0}")]
+    UnknownTokenizer(String),
+}
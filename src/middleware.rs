@@ -0,0 +1,94 @@
+//! Provides hooks around the requests of a client
+//!
+//! Every [`Middleware`] added to a
+//! [`TextSynthClientBuilder`](crate::TextSynthClientBuilder) sees each attempt
+//! of every request before it is sent, and its response once it arrives, e.g.
+//! to log, add headers, keep an audit trail or inject failures in tests. The
+//! middleware added first runs outermost.
+
+use std::{fmt, sync::Arc};
+
+use reqwest::{Request, Response};
+
+use crate::transport::{Transport, TransportFuture};
+
+/// Hook around sending a request
+pub trait Middleware: fmt::Debug + Send + Sync {
+    /// Handle `request`, usually by passing it on to `next` and returning its
+    /// response. The request may be modified before, the response observed
+    /// after, or an answer returned without sending the request at all.
+    fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> TransportFuture<'a>;
+}
+
+/// The remaining middleware and the transport a request is passed on to
+#[derive(Debug, Clone, Copy)]
+pub struct Next<'a> {
+    middleware: &'a [Arc<dyn Middleware>],
+    transport: &'a dyn Transport,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(middleware: &'a [Arc<dyn Middleware>], transport: &'a dyn Transport) -> Self {
+        Next {
+            middleware,
+            transport,
+        }
+    }
+
+    /// Pass `request` to the next middleware, or send it if there is none
+    pub fn run(self, request: Request) -> TransportFuture<'a> {
+        match self.middleware.split_first() {
+            Some((middleware, rest)) => middleware.handle(request, Next::new(rest, self.transport)),
+            None => self.transport.execute(request),
+        }
+    }
+}
+
+impl Transport for Next<'_> {
+    fn execute(&self, request: Request) -> TransportFuture<'_> {
+        self.run(request)
+    }
+}
+
+/// Middleware calling `f` on every request before it is sent
+pub fn map_request(f: impl Fn(&mut Request) + Send + Sync + 'static) -> impl Middleware {
+    MapRequest(f)
+}
+
+/// Middleware calling `f` on every response received
+pub fn inspect_response(f: impl Fn(&Response) + Send + Sync + 'static) -> impl Middleware {
+    InspectResponse(f)
+}
+
+struct MapRequest<F>(F);
+
+impl<F> fmt::Debug for MapRequest<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MapRequest").finish_non_exhaustive()
+    }
+}
+
+impl<F: Fn(&mut Request) + Send + Sync> Middleware for MapRequest<F> {
+    fn handle<'a>(&'a self, mut request: Request, next: Next<'a>) -> TransportFuture<'a> {
+        (self.0)(&mut request);
+        next.run(request)
+    }
+}
+
+struct InspectResponse<F>(F);
+
+impl<F> fmt::Debug for InspectResponse<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InspectResponse").finish_non_exhaustive()
+    }
+}
+
+impl<F: Fn(&Response) + Send + Sync> Middleware for InspectResponse<F> {
+    fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> TransportFuture<'a> {
+        Box::pin(async move {
+            let response = next.run(request).await?;
+            (self.0)(&response);
+            Ok(response)
+        })
+    }
+}
@@ -0,0 +1,97 @@
+//! Provides requests prepared without sending them
+//!
+//! [`TextSynthClient::prepare`] builds the HTTP request a call would send, as
+//! a dry run for debugging payloads or sending them with other HTTP tooling.
+//! The api key is redacted from the prepared headers.
+
+use std::fmt;
+
+use reqwest::{
+    header::{HeaderMap, HeaderValue, AUTHORIZATION},
+    Method,
+};
+use serde::Serialize;
+
+use crate::{chat, completions, text_to_image, tokenize, translate, TextSynthClient, REDACTED};
+
+/// Request of an api endpoint taking a json body
+pub trait ApiRequest: Serialize {
+    /// Engine the request is sent to
+    type Engine: fmt::Display;
+
+    /// Path of the endpoint below the engine, e.g. "completions"
+    const ENDPOINT: &'static str;
+}
+
+/// Implement [`ApiRequest`] and an inherent `to_json` for a request
+macro_rules! api_request {
+    ($request:ty, $engine:ty, $endpoint:literal) => {
+        impl ApiRequest for $request {
+            type Engine = $engine;
+
+            const ENDPOINT: &'static str = $endpoint;
+        }
+
+        impl $request {
+            /// The json body of the request, as sent
+            pub fn to_json(&self) -> Result<String, serde_json::Error> {
+                serde_json::to_string(self)
+            }
+        }
+    };
+}
+
+api_request!(completions::Request, completions::Engine, "completions");
+api_request!(
+    completions::logprob::Request,
+    completions::Engine,
+    "logprob"
+);
+api_request!(tokenize::Request, completions::Engine, "tokenize");
+api_request!(chat::Request, chat::Engine, "chat");
+api_request!(translate::Request, translate::Engine, "translate");
+api_request!(
+    text_to_image::Request,
+    text_to_image::Engine,
+    "text_to_image"
+);
+
+/// HTTP request as it would be sent, with the api key redacted
+#[derive(Debug, Clone)]
+pub struct PreparedRequest {
+    /// The HTTP method.
+    pub method: Method,
+    /// The full url of the endpoint.
+    pub url: String,
+    /// The headers set by the client. The `Authorization` header holds
+    /// `Bearer [redacted]` instead of the api key, and headers added by the
+    /// HTTP client itself are missing.
+    pub headers: HeaderMap,
+    /// The json body.
+    pub body: String,
+}
+
+impl TextSynthClient {
+    /// Prepare `request` to `engine` without sending it
+    pub fn prepare<R: ApiRequest>(
+        &self,
+        engine: &R::Engine,
+        request: &R,
+    ) -> Result<PreparedRequest, serde_json::Error> {
+        let mut headers = HeaderMap::new();
+        self.insert_headers(&mut headers);
+        if headers.contains_key(AUTHORIZATION) {
+            let redacted = format!("Bearer {}", REDACTED);
+            headers.insert(
+                AUTHORIZATION,
+                HeaderValue::from_str(&redacted).expect("redacted header should be valid"),
+            );
+        }
+        Ok(PreparedRequest {
+            method: Method::POST,
+            url: format!("{}/engines/{}/{}", self.base_url, engine, R::ENDPOINT),
+            headers,
+            body: serde_json::to_string(request)?,
+        })
+    }
+}
@@ -0,0 +1,79 @@
+//! Provides prompts built from compile-time checked templates
+
+use std::{fmt, sync::Arc};
+
+/// A prompt built by the [`prompt!`](crate::prompt!) macro
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prompt(String);
+
+impl Prompt {
+    /// Wrap an already formatted prompt, use the
+    /// [`prompt!`](crate::prompt!) macro instead
+    #[doc(hidden)]
+    pub fn from_formatted(prompt: String) -> Self {
+        Prompt(prompt)
+    }
+
+    /// The prompt text
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Prompt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<Prompt> for String {
+    fn from(prompt: Prompt) -> Self {
+        prompt.0
+    }
+}
+
+impl From<Prompt> for Arc<str> {
+    fn from(prompt: Prompt) -> Self {
+        prompt.0.into()
+    }
+}
+
+/// Build a [`Prompt`] from a template literal and named bindings.
+///
+/// Every `{name}` placeholder of the template must have a binding and every
+/// binding must be used, otherwise the prompt doesn't compile. Unlike
+/// [`format!`], placeholders are never bound to variables in scope, and the
+/// bindings are formatted with [`Display`](std::fmt::Display). Literal braces
+/// are written as `{{` and `}}`.
+///
+/// ```
+/// use elikoga_textsynth::prompt;
+///
+/// let prompt = prompt!("Translate {text} to {lang}:", text = "Hallo", lang = "English");
+/// assert_eq!(prompt.as_str(), "Translate Hallo to English:");
+/// ```
+///
+/// ```compile_fail
+/// use elikoga_textsynth::prompt;
+///
+/// // `lang` has no binding
+/// let prompt = prompt!("Translate {text} to {lang}:", text = "Hallo");
+/// ```
+///
+/// ```compile_fail
+/// use elikoga_textsynth::prompt;
+///
+/// // the variable `lang` isn't a binding
+/// let lang = "English";
+/// let prompt = prompt!("Translate {text} to {lang}:", text = "Hallo");
+/// ```
+#[macro_export]
+macro_rules! prompt {
+    ($template:literal $(, $name:ident = $value:expr)* $(,)?) => {{
+        // a fn item can't capture the variables in scope
+        fn template($($name: &dyn ::std::fmt::Display),*) -> ::std::string::String {
+            ::std::format!($template $(, $name = $name)*)
+        }
+        $crate::prompt::Prompt::from_formatted(template($(&$value),*))
+    }};
+}
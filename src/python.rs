@@ -0,0 +1,552 @@
+//! Provides python bindings
+//!
+//! Enabled by the `python` feature. Build the extension module with
+//! `maturin build`, which turns on the `extension-module` feature through
+//! `pyproject.toml`, then `import elikoga_textsynth`. Request parameters are
+//! passed as keyword arguments mirroring the request builders, or as
+//! requests made by `CompletionRequestBuilder` and `TranslateRequestBuilder`.
+//! Engines are passed by their api name (e.g. `"gptj_6B"`).
+//!
+//! Streamed completions are python iterators, or async iterators for
+//! `async for` with `Client.astream`.
+
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use futures::StreamExt;
+use pyo3::{
+    exceptions::{PyRuntimeError, PyStopAsyncIteration, PyValueError},
+    prelude::*,
+};
+use tokio::{runtime::Runtime, sync::mpsc};
+
+use crate::{completions, tokenize, translate, TextSynthClient};
+
+fn runtime_error(err: impl ToString) -> PyErr {
+    PyRuntimeError::new_err(err.to_string())
+}
+
+fn value_error(err: impl ToString) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+fn completion_engine(id: &str) -> PyResult<completions::Engine> {
+    id.parse().map_err(value_error)
+}
+
+fn translation_engine(id: &str) -> PyResult<translate::Engine> {
+    id.parse().map_err(value_error)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn completion_request(
+    prompt: &str,
+    stream: bool,
+    max_tokens: Option<u32>,
+    temperature: Option<f64>,
+    top_k: Option<u32>,
+    top_p: Option<f64>,
+    stop: Option<Vec<String>>,
+) -> PyResult<completions::Request> {
+    let mut request = completions::RequestBuilder::default();
+    request.prompt(prompt).stream(stream);
+    if let Some(max_tokens) = max_tokens {
+        request.max_tokens(max_tokens);
+    }
+    if let Some(temperature) = temperature {
+        request.temperature(temperature);
+    }
+    if let Some(top_k) = top_k {
+        request.top_k(top_k);
+    }
+    if let Some(top_p) = top_p {
+        request.top_p(top_p);
+    }
+    if let Some(stop) = stop {
+        request.stop(stop.into_iter().map(Into::into).collect::<Vec<_>>());
+    }
+    request.build().map_err(value_error)
+}
+
+type TextReceiver = mpsc::UnboundedReceiver<Result<String, String>>;
+
+/// Stream the text generated for `request`, returning the future to spawn
+/// and the receiver of the text, which stops the generation when dropped
+fn stream_text(
+    client: Arc<TextSynthClient>,
+    engine: completions::Engine,
+    request: completions::Request,
+) -> (impl Future<Output = ()> + Send + 'static, TextReceiver) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let task = async move {
+        let result = async {
+            let mut response = client.completions(&engine, &request).await?;
+            while let Some(chunk) = response.next().await {
+                for text in chunk?.text {
+                    if sender.send(Ok(text)).is_err() {
+                        // the iterator was dropped, stop generating
+                        return Ok(());
+                    }
+                }
+            }
+            Ok::<_, completions::Error>(())
+        }
+        .await;
+        if let Err(err) = result {
+            let _ = sender.send(Err(err.to_string()));
+        }
+    };
+    (task, receiver)
+}
+
+/// Builder of completion requests, see [`completions::RequestBuilder`]
+#[pyclass(name = "CompletionRequestBuilder")]
+#[derive(Debug, Clone, Default)]
+pub struct PyCompletionRequestBuilder {
+    builder: completions::RequestBuilder,
+}
+
+#[pymethods]
+impl PyCompletionRequestBuilder {
+    /// Start a request without any parameter set.
+    #[new]
+    pub fn new() -> Self {
+        PyCompletionRequestBuilder::default()
+    }
+
+    /// Set the prompt to complete.
+    pub fn prompt<'py>(mut slf: PyRefMut<'py, Self>, prompt: &str) -> PyRefMut<'py, Self> {
+        slf.builder.prompt(prompt);
+        slf
+    }
+
+    /// Set whether the api streams the completion.
+    pub fn stream(mut slf: PyRefMut<'_, Self>, stream: bool) -> PyRefMut<'_, Self> {
+        slf.builder.stream(stream);
+        slf
+    }
+
+    /// Set the maximum number of tokens to generate.
+    pub fn max_tokens(mut slf: PyRefMut<'_, Self>, max_tokens: u32) -> PyRefMut<'_, Self> {
+        slf.builder.max_tokens(max_tokens);
+        slf
+    }
+
+    /// Set the strings stopping the generation.
+    pub fn stop(mut slf: PyRefMut<'_, Self>, stop: Vec<String>) -> PyRefMut<'_, Self> {
+        slf.builder
+            .stop(stop.into_iter().map(Into::into).collect::<Vec<_>>());
+        slf
+    }
+
+    /// Set the number of completions to generate.
+    pub fn n(mut slf: PyRefMut<'_, Self>, n: u32) -> PyRefMut<'_, Self> {
+        slf.builder.n(n);
+        slf
+    }
+
+    /// Set the sampling temperature.
+    pub fn temperature(mut slf: PyRefMut<'_, Self>, temperature: f64) -> PyRefMut<'_, Self> {
+        slf.builder.temperature(temperature);
+        slf
+    }
+
+    /// Keep only the `top_k` most likely tokens when sampling.
+    pub fn top_k(mut slf: PyRefMut<'_, Self>, top_k: u32) -> PyRefMut<'_, Self> {
+        slf.builder.top_k(top_k);
+        slf
+    }
+
+    /// Keep only the most likely tokens up to a cumulative probability of
+    /// `top_p` when sampling.
+    pub fn top_p(mut slf: PyRefMut<'_, Self>, top_p: f64) -> PyRefMut<'_, Self> {
+        slf.builder.top_p(top_p);
+        slf
+    }
+
+    /// Build the request, raising `ValueError` if it is invalid.
+    pub fn build(&self) -> PyResult<PyCompletionRequest> {
+        let request = self.builder.build().map_err(value_error)?;
+        Ok(PyCompletionRequest { request })
+    }
+}
+
+/// Completion request, see [`completions::Request`]
+#[pyclass(name = "CompletionRequest", frozen)]
+#[derive(Debug, Clone)]
+pub struct PyCompletionRequest {
+    request: completions::Request,
+}
+
+#[pymethods]
+impl PyCompletionRequest {
+    /// Return the request as sent to the api.
+    pub fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.request).map_err(value_error)
+    }
+}
+
+/// Builder of translation requests, see [`translate::RequestBuilder`]
+#[pyclass(name = "TranslateRequestBuilder")]
+#[derive(Debug, Clone, Default)]
+pub struct PyTranslateRequestBuilder {
+    builder: translate::RequestBuilder,
+}
+
+#[pymethods]
+impl PyTranslateRequestBuilder {
+    /// Start a request without any parameter set.
+    #[new]
+    pub fn new() -> Self {
+        PyTranslateRequestBuilder::default()
+    }
+
+    /// Set the texts to translate.
+    pub fn text(mut slf: PyRefMut<'_, Self>, texts: Vec<String>) -> PyRefMut<'_, Self> {
+        slf.builder
+            .text(texts.into_iter().map(Into::into).collect::<Vec<_>>());
+        slf
+    }
+
+    /// Set the language of the texts, or `"auto"` to detect it.
+    pub fn source_lang<'py>(mut slf: PyRefMut<'py, Self>, lang: &str) -> PyRefMut<'py, Self> {
+        slf.builder.source_lang(lang);
+        slf
+    }
+
+    /// Set the language to translate to.
+    pub fn target_lang<'py>(mut slf: PyRefMut<'py, Self>, lang: &str) -> PyRefMut<'py, Self> {
+        slf.builder.target_lang(lang);
+        slf
+    }
+
+    /// Set the number of beams of the beam search.
+    pub fn num_beams(mut slf: PyRefMut<'_, Self>, num_beams: u32) -> PyRefMut<'_, Self> {
+        slf.builder.num_beams(num_beams);
+        slf
+    }
+
+    /// Set whether the texts are translated sentence by sentence.
+    pub fn split_sentences(mut slf: PyRefMut<'_, Self>, split: bool) -> PyRefMut<'_, Self> {
+        slf.builder.split_sentences(split);
+        slf
+    }
+
+    /// Build the request, raising `ValueError` if it is invalid.
+    pub fn build(&self) -> PyResult<PyTranslateRequest> {
+        let request = self.builder.build().map_err(value_error)?;
+        Ok(PyTranslateRequest { request })
+    }
+}
+
+/// Translation request, see [`translate::Request`]
+#[pyclass(name = "TranslateRequest", frozen)]
+#[derive(Debug, Clone)]
+pub struct PyTranslateRequest {
+    request: translate::Request,
+}
+
+#[pymethods]
+impl PyTranslateRequest {
+    /// Return the request as sent to the api.
+    pub fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.request).map_err(value_error)
+    }
+}
+
+/// TextSynth API Client
+#[pyclass(name = "Client")]
+pub struct PyClient {
+    client: Arc<TextSynthClient>,
+    runtime: Arc<Runtime>,
+}
+
+impl PyClient {
+    fn complete_blocking(
+        &self,
+        py: Python<'_>,
+        engine: &str,
+        request: &completions::Request,
+    ) -> PyResult<String> {
+        let engine = completion_engine(engine)?;
+        let response = py
+            .allow_threads(|| {
+                self.runtime
+                    .block_on(self.client.complete(&engine, request))
+            })
+            .map_err(runtime_error)?;
+        Ok(response.text.concat())
+    }
+
+    fn translate_blocking(
+        &self,
+        py: Python<'_>,
+        engine: &str,
+        request: &translate::Request,
+    ) -> PyResult<Vec<String>> {
+        let engine = translation_engine(engine)?;
+        let response = py
+            .allow_threads(|| {
+                self.runtime
+                    .block_on(self.client.translate(&engine, request))
+            })
+            .map_err(runtime_error)?;
+        Ok(response
+            .translations
+            .into_iter()
+            .map(|translation| translation.text)
+            .collect())
+    }
+
+    fn spawn_stream(&self, engine: &str, request: completions::Request) -> PyResult<TextReceiver> {
+        let engine = completion_engine(engine)?;
+        let (task, receiver) = stream_text(self.client.clone(), engine, request);
+        self.runtime.spawn(task);
+        Ok(receiver)
+    }
+}
+
+#[pymethods]
+impl PyClient {
+    #[new]
+    #[pyo3(signature = (api_key, endpoint=None))]
+    fn new(api_key: &str, endpoint: Option<&str>) -> PyResult<Self> {
+        let endpoint = endpoint.unwrap_or("https://api.textsynth.com/v1");
+        let client =
+            TextSynthClient::try_new_with_endpoint(api_key, endpoint).map_err(value_error)?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .map_err(runtime_error)?;
+        Ok(PyClient {
+            client: Arc::new(client),
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// Complete `prompt` and return the generated text.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (engine, prompt, max_tokens=None, temperature=None, top_k=None, top_p=None, stop=None))]
+    fn complete(
+        &self,
+        py: Python<'_>,
+        engine: &str,
+        prompt: &str,
+        max_tokens: Option<u32>,
+        temperature: Option<f64>,
+        top_k: Option<u32>,
+        top_p: Option<f64>,
+        stop: Option<Vec<String>>,
+    ) -> PyResult<String> {
+        let request =
+            completion_request(prompt, false, max_tokens, temperature, top_k, top_p, stop)?;
+        self.complete_blocking(py, engine, &request)
+    }
+
+    /// Send `request`, a `CompletionRequest`, and return the generated text.
+    fn complete_request(
+        &self,
+        py: Python<'_>,
+        engine: &str,
+        request: &PyCompletionRequest,
+    ) -> PyResult<String> {
+        self.complete_blocking(py, engine, &request.request)
+    }
+
+    /// Complete `prompt`, returning an iterator over the generated text as it
+    /// is streamed.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (engine, prompt, max_tokens=None, temperature=None, top_k=None, top_p=None, stop=None))]
+    fn stream(
+        &self,
+        engine: &str,
+        prompt: &str,
+        max_tokens: Option<u32>,
+        temperature: Option<f64>,
+        top_k: Option<u32>,
+        top_p: Option<f64>,
+        stop: Option<Vec<String>>,
+    ) -> PyResult<CompletionIterator> {
+        let request =
+            completion_request(prompt, true, max_tokens, temperature, top_k, top_p, stop)?;
+        let receiver = self.spawn_stream(engine, request)?;
+        Ok(CompletionIterator {
+            receiver: Mutex::new(receiver),
+        })
+    }
+
+    /// Send `request`, a `CompletionRequest` built with `stream(True)`,
+    /// returning an iterator over the generated text as it is streamed.
+    fn stream_request(
+        &self,
+        engine: &str,
+        request: &PyCompletionRequest,
+    ) -> PyResult<CompletionIterator> {
+        let receiver = self.spawn_stream(engine, request.request.clone())?;
+        Ok(CompletionIterator {
+            receiver: Mutex::new(receiver),
+        })
+    }
+
+    /// Send `request`, a `CompletionRequest` built with `stream(True)`,
+    /// returning an async iterator over the generated text as it is
+    /// streamed.
+    fn astream(
+        &self,
+        engine: &str,
+        request: &PyCompletionRequest,
+    ) -> PyResult<AsyncCompletionIterator> {
+        let receiver = self.spawn_stream(engine, request.request.clone())?;
+        Ok(AsyncCompletionIterator {
+            receiver: Arc::new(tokio::sync::Mutex::new(receiver)),
+        })
+    }
+
+    /// Translate `texts` from `source_lang` to `target_lang`, returning the
+    /// translations in order.
+    #[pyo3(signature = (engine, texts, source_lang, target_lang, num_beams=None))]
+    fn translate(
+        &self,
+        py: Python<'_>,
+        engine: &str,
+        texts: Vec<String>,
+        source_lang: &str,
+        target_lang: &str,
+        num_beams: Option<u32>,
+    ) -> PyResult<Vec<String>> {
+        let mut request = translate::RequestBuilder::default();
+        request
+            .text(texts.into_iter().map(Into::into).collect::<Vec<_>>())
+            .source_lang(source_lang)
+            .target_lang(target_lang);
+        if let Some(num_beams) = num_beams {
+            request.num_beams(num_beams);
+        }
+        let request = request.build().map_err(value_error)?;
+        self.translate_blocking(py, engine, &request)
+    }
+
+    /// Send `request`, a `TranslateRequest`, returning the translations in
+    /// order.
+    fn translate_request(
+        &self,
+        py: Python<'_>,
+        engine: &str,
+        request: &PyTranslateRequest,
+    ) -> PyResult<Vec<String>> {
+        self.translate_blocking(py, engine, &request.request)
+    }
+
+    /// Return the token indexes of `text`.
+    fn tokenize(&self, py: Python<'_>, engine: &str, text: &str) -> PyResult<Vec<u32>> {
+        let engine = completion_engine(engine)?;
+        let request = tokenize::RequestBuilder::default()
+            .text(text)
+            .build()
+            .map_err(value_error)?;
+        let response = py
+            .allow_threads(|| {
+                self.runtime
+                    .block_on(self.client.tokenize(&engine, &request))
+            })
+            .map_err(runtime_error)?;
+        Ok(response.tokens)
+    }
+
+    /// Return the log probability of `continuation` following `context` and
+    /// whether it would be generated by greedy sampling.
+    fn logprob(
+        &self,
+        py: Python<'_>,
+        engine: &str,
+        context: &str,
+        continuation: &str,
+    ) -> PyResult<(f64, bool)> {
+        let engine = completion_engine(engine)?;
+        let request = completions::logprob::RequestBuilder::default()
+            .context(context)
+            .continuation(continuation)
+            .build()
+            .map_err(value_error)?;
+        let response = py
+            .allow_threads(|| {
+                self.runtime
+                    .block_on(self.client.logprob(&engine, &request))
+            })
+            .map_err(runtime_error)?;
+        Ok((response.logprob, response.is_greedy))
+    }
+}
+
+/// Iterator over streamed completion text
+#[pyclass]
+pub struct CompletionIterator {
+    receiver: Mutex<TextReceiver>,
+}
+
+#[pymethods]
+impl CompletionIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<Option<String>> {
+        let receiver = &self.receiver;
+        let next = py.allow_threads(|| {
+            receiver
+                .lock()
+                .map_err(runtime_error)
+                .map(|mut receiver| receiver.blocking_recv())
+        })?;
+        match next {
+            Some(Ok(text)) => Ok(Some(text)),
+            Some(Err(err)) => Err(runtime_error(err)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Async iterator over streamed completion text
+#[pyclass]
+pub struct AsyncCompletionIterator {
+    receiver: Arc<tokio::sync::Mutex<TextReceiver>>,
+}
+
+#[pymethods]
+impl AsyncCompletionIterator {
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __anext__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let receiver = self.receiver.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            match receiver.lock().await.recv().await {
+                Some(Ok(text)) => Ok(text),
+                Some(Err(err)) => Err(runtime_error(err)),
+                None => Err(PyStopAsyncIteration::new_err(())),
+            }
+        })
+    }
+}
+
+/// Add the classes of the bindings to `module`
+pub fn register(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyClient>()?;
+    module.add_class::<PyCompletionRequestBuilder>()?;
+    module.add_class::<PyCompletionRequest>()?;
+    module.add_class::<PyTranslateRequestBuilder>()?;
+    module.add_class::<PyTranslateRequest>()?;
+    module.add_class::<CompletionIterator>()?;
+    module.add_class::<AsyncCompletionIterator>()?;
+    Ok(())
+}
+
+/// Python module exposing [`PyClient`] as `Client`
+#[pymodule]
+fn elikoga_textsynth(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    register(module)
+}
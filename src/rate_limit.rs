@@ -0,0 +1,98 @@
+//! Provides client-side rate limiting of requests
+//!
+//! A request counts as in flight until the status of its answer arrived, so
+//! streamed answers don't hold their slot while the chunks arrive.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Limits on the requests a client sends
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimit {
+    /// Average number of requests started per second.
+    pub requests_per_second: f64,
+    /// Number of requests that may be started at once after being idle.
+    pub burst: u32,
+    /// Maximum number of requests in flight at the same time, unlimited if
+    /// none.
+    pub max_in_flight: Option<usize>,
+}
+
+impl RateLimit {
+    /// Start at most `requests_per_second` requests per second, without
+    /// bursts or a limit on the requests in flight
+    pub fn new(requests_per_second: f64) -> Self {
+        RateLimit {
+            requests_per_second,
+            burst: 1,
+            max_in_flight: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+/// Token bucket and in-flight limit shared by the requests of a client
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    limit: RateLimit,
+    bucket: Mutex<Bucket>,
+    in_flight: Option<Semaphore>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        RateLimiter {
+            bucket: Mutex::new(Bucket {
+                tokens: f64::from(limit.burst.max(1)),
+                refilled: Instant::now(),
+            }),
+            in_flight: limit
+                .max_in_flight
+                .map(|max_in_flight| Semaphore::new(max_in_flight.max(1))),
+            limit,
+        }
+    }
+
+    /// Wait until a request may be started, holding its in-flight slot
+    pub(crate) async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        let permit = match &self.in_flight {
+            Some(in_flight) => Some(
+                in_flight
+                    .acquire()
+                    .await
+                    .expect("the semaphore is never closed"),
+            ),
+            None => None,
+        };
+        while let Some(wait) = self.take_token() {
+            tokio::time::sleep(wait).await;
+        }
+        permit
+    }
+
+    /// Take a token from the bucket, or return how long until one is
+    /// available
+    fn take_token(&self) -> Option<Duration> {
+        let mut bucket = self.bucket.lock().expect("rate limiter lock poisoned");
+        let now = Instant::now();
+        let rate = self.limit.requests_per_second.max(f64::MIN_POSITIVE);
+        let refill = (now - bucket.refilled).as_secs_f64() * rate;
+        bucket.tokens = (bucket.tokens + refill).min(f64::from(self.limit.burst.max(1)));
+        bucket.refilled = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+}
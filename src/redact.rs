@@ -0,0 +1,61 @@
+//! Provides printing requests without their large texts
+//!
+//! The `Debug` output of requests holds their whole prompt, which can flood
+//! logs. [`Redacted`] prints a request as json with every long text cut
+//! short instead.
+
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Request printed as json with texts longer than a limit cut short
+pub struct Redacted<'a, T> {
+    request: &'a T,
+    max_chars: usize,
+}
+
+impl<'a, T: Serialize> Redacted<'a, T> {
+    /// Print `request` with the texts longer than `max_chars` characters cut
+    /// short
+    pub fn new(request: &'a T, max_chars: usize) -> Self {
+        Redacted { request, max_chars }
+    }
+}
+
+/// Cut the texts of `value` longer than `max_chars` characters short
+fn redact(value: &mut Value, max_chars: usize) {
+    match value {
+        Value::String(text) => {
+            if let Some((end, _)) = text.char_indices().nth(max_chars) {
+                let omitted = text[end..].chars().count();
+                *text = format!("{}… ({} more characters)", &text[..end], omitted);
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                redact(value, max_chars);
+            }
+        }
+        Value::Object(values) => {
+            for value in values.values_mut() {
+                redact(value, max_chars);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl<T: Serialize> fmt::Display for Redacted<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut value = serde_json::to_value(self.request).map_err(|_| fmt::Error)?;
+        redact(&mut value, self.max_chars);
+        write!(f, "{}", value)
+    }
+}
+
+impl<T: Serialize> fmt::Debug for Redacted<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
@@ -0,0 +1,108 @@
+//! Provides retrying of requests failing with transient errors
+//!
+//! Only sending a request and receiving the status of its answer is retried,
+//! so streamed answers are never retried once their first byte arrived.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use reqwest::{Request, Response, StatusCode};
+
+use crate::{retry_after, transport::Transport};
+
+/// How often and how long apart requests are retried
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one. 1 disables
+    /// retrying.
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for every further retry.
+    pub base_delay: Duration,
+    /// Upper bound of the delay between attempts.
+    pub max_delay: Duration,
+    /// Fraction of the delay, between 0 and 1, that is randomly shortened so
+    /// that many clients don't retry in lockstep.
+    pub jitter: f64,
+    /// If true, answers with 429 Too Many Requests are retried after the
+    /// time their `Retry-After` header asks for, or the usual delay without
+    /// it. Answers asking to wait longer than `max_delay` aren't retried.
+    pub rate_limited: bool,
+}
+
+impl RetryPolicy {
+    /// Make up to `max_attempts` attempts, waiting 500ms before the first
+    /// retry
+    pub fn new(max_attempts: u32) -> Self {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: 0.5,
+            rate_limited: true,
+        }
+    }
+
+    /// Delay before the retry following `attempt`, counted from 1
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        delay.mul_f64(1.0 - self.jitter.clamp(0.0, 1.0) * random)
+    }
+
+    /// Send `request`, retrying transient failures. The last answer is
+    /// returned even if its status is an error.
+    pub(crate) async fn send(
+        &self,
+        transport: &dyn Transport,
+        request: Request,
+    ) -> Result<Response, reqwest::Error> {
+        let mut attempt = 1;
+        loop {
+            // requests with a streamed body can't be cloned and aren't retried
+            let retry = match request.try_clone() {
+                Some(retry) if attempt < self.max_attempts => retry,
+                _ => return transport.execute(request).await,
+            };
+            let delay = match transport.execute(retry).await {
+                Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => {
+                    match retry_after(response.headers()) {
+                        _ if !self.rate_limited => return Ok(response),
+                        Some(delay) if delay > self.max_delay => return Ok(response),
+                        Some(delay) => delay,
+                        None => self.delay(attempt),
+                    }
+                }
+                Ok(response) if !is_transient_status(response.status()) => return Ok(response),
+                Err(err) if !is_transient_error(&err) => return Err(err),
+                _ => self.delay(attempt),
+            };
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Don't retry
+    fn default() -> Self {
+        RetryPolicy::new(1)
+    }
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+fn is_transient_error(err: &reqwest::Error) -> bool {
+    err.is_connect() || err.is_timeout()
+}
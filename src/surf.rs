@@ -0,0 +1,106 @@
+//! Provides sending requests with surf, for programs on async-std or smol
+//!
+//! The http client of the crate, reqwest, needs a tokio runtime. A
+//! [`SurfClient`] sends the requests with surf on async-std instead, so
+//! programs running on async-std, smol or any other executor use the client
+//! without a tokio runtime:
+//!
+//! ```no_run
+//! # use elikoga_textsynth::{surf::SurfClient, transport::HttpClientTransport, TextSynthClient};
+//! let client = TextSynthClient::builder("api key")
+//!     .transport(HttpClientTransport::new(SurfClient::new()))
+//!     .build();
+//! ```
+//!
+//! Retries, rate limits, stream read timeouts and prefetching still wait on
+//! tokio timers and tasks, so they must stay disabled, as they are by
+//! default. Request timeouts are handled by reqwest and don't apply to
+//! requests sent with surf.
+
+use std::str::FromStr;
+
+use bytes::Bytes;
+use futures::{stream, AsyncReadExt};
+
+use crate::transport::{BoxError, HttpBody, HttpClient, HttpFuture};
+
+/// Size of the chunks the body of a response is read in
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// [`HttpClient`] sending requests with a [`surf::Client`]
+#[derive(Debug, Clone)]
+pub struct SurfClient {
+    client: ::surf::Client,
+}
+
+impl SurfClient {
+    /// Send requests with a new surf client
+    pub fn new() -> Self {
+        SurfClient::with_client(::surf::Client::new())
+    }
+
+    /// Send requests with `client`, e.g. one with middleware or a custom
+    /// http client
+    pub fn with_client(client: ::surf::Client) -> Self {
+        SurfClient { client }
+    }
+}
+
+impl Default for SurfClient {
+    fn default() -> Self {
+        SurfClient::new()
+    }
+}
+
+/// `request` as a request of surf
+fn surf_request(request: http::Request<Vec<u8>>) -> Result<::surf::Request, BoxError> {
+    let (parts, body) = request.into_parts();
+    let method = ::surf::http::Method::from_str(parts.method.as_str())?;
+    let url = ::surf::Url::parse(&parts.uri.to_string())?;
+    let mut sent = ::surf::Request::new(method, url);
+    for (name, value) in &parts.headers {
+        sent.append_header(name.as_str(), value.to_str()?);
+    }
+    sent.set_body(body);
+    Ok(sent)
+}
+
+/// The body of `response`, read in chunks until it ends or fails
+fn body(response: &mut ::surf::Response) -> HttpBody {
+    let body = Some(response.take_body());
+    Box::pin(stream::unfold(body, |body| async move {
+        let mut body = body?;
+        let mut chunk = vec![0; CHUNK_SIZE];
+        match body.read(&mut chunk).await {
+            Ok(0) => None,
+            Ok(read) => {
+                chunk.truncate(read);
+                Some((Ok(Bytes::from(chunk)), Some(body)))
+            }
+            Err(error) => Some((Err(error.into()), None)),
+        }
+    }))
+}
+
+impl HttpClient for SurfClient {
+    fn send(&self, request: http::Request<Vec<u8>>) -> HttpFuture<'_> {
+        Box::pin(async move {
+            let mut response = self
+                .client
+                .send(surf_request(request)?)
+                .await
+                .map_err(|error| error.into_inner())?;
+            let mut builder = http::Response::builder().status(u16::from(response.status()));
+            // async-h1 can parse values that aren't valid headers, like the
+            // `date` header with its line ending, which are dropped
+            for (name, values) in response.iter() {
+                for value in values {
+                    if let Ok(value) = http::HeaderValue::from_str(value.as_str()) {
+                        builder = builder.header(name.as_str(), value);
+                    }
+                }
+            }
+            Ok(builder.body(body(&mut response))?)
+        })
+    }
+}
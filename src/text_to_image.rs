@@ -0,0 +1,330 @@
+//! Provides text to image api
+
+use std::{fmt, fs, io, path::Path, sync::Arc};
+
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_with::skip_serializing_none;
+use thiserror::Error;
+
+use crate::{
+    engine::{engine_info, EngineInfo},
+    validation::ValidationError,
+    IsEngine, TextSynthClient, WithMeta,
+};
+
+/// Enum for the different image generation engines available for TextSynth
+#[derive(strum::IntoStaticStr, Debug, Clone, PartialEq, Eq)]
+pub enum Engine {
+    /// Stable Diffusion is a 1 billion parameter text to image model trained
+    /// to generate 512x512 pixel images from English text.
+    #[strum(serialize = "stable_diffusion")]
+    StableDiffusion,
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.into())
+    }
+}
+
+impl Engine {
+    /// Metadata of the engine
+    pub fn info(&self) -> EngineInfo {
+        engine_info(self.into())
+            .cloned()
+            .expect("built-in engines are in the registry")
+    }
+}
+
+impl IsEngine for Engine {}
+
+/// Struct for a text to image request
+#[skip_serializing_none]
+#[derive(Serialize, Builder, Debug, Clone)]
+#[builder(setter(into))]
+#[builder(derive(Debug))]
+#[builder(build_fn(validate = "Self::validate", error = "ValidationError"))]
+pub struct Request {
+    /// The text prompt describing the image.
+    prompt: Arc<str>,
+    /// Number of images to generate. At most 4 images can be generated with
+    /// one request.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    image_count: Option<u32>,
+    /// Width of the generated images in pixels, 512 by default. It must be a
+    /// multiple of 64 between 256 and 1024.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    width: Option<u32>,
+    /// Height of the generated images in pixels, 512 by default. It must be a
+    /// multiple of 64 between 256 and 1024.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    height: Option<u32>,
+    /// Number of diffusion steps. A larger number gives better images but
+    /// takes longer to generate.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    timesteps: Option<u32>,
+    /// How closely the image follows the prompt. A larger value gives images
+    /// closer to the prompt but less diverse.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    guidance_scale: Option<f64>,
+    /// Random seed, 0 for a random one. Set it to get reproducible images.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    seed: Option<u32>,
+    /// Text describing what should not be in the image. Requires a
+    /// guidance_scale larger than 1 if it is set.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    negative_prompt: Option<Arc<str>>,
+}
+
+/// Error of building a [`Request`]
+pub type RequestBuilderError = ValidationError;
+
+impl Request {
+    /// Builder with the required `prompt` set, so that building fails only on
+    /// invalid parameters
+    pub fn builder(prompt: impl Into<Arc<str>>) -> RequestBuilder {
+        let mut builder = RequestBuilder::default();
+        builder.prompt(prompt);
+        builder
+    }
+}
+
+fn check_prompt(prompt: &str) -> Result<(), ValidationError> {
+    if prompt.trim().is_empty() {
+        return Err(ValidationError::invalid("prompt", "not empty", prompt));
+    }
+    Ok(())
+}
+
+fn check_image_count(image_count: u32) -> Result<(), ValidationError> {
+    if !(1..=4).contains(&image_count) {
+        return Err(ValidationError::invalid(
+            "image_count",
+            "between 1 and 4",
+            image_count,
+        ));
+    }
+    Ok(())
+}
+
+fn check_size(field: &'static str, size: u32) -> Result<(), ValidationError> {
+    if !(256..=1024).contains(&size) || !size.is_multiple_of(64) {
+        return Err(ValidationError::invalid(
+            field,
+            "a multiple of 64 between 256 and 1024",
+            size,
+        ));
+    }
+    Ok(())
+}
+
+fn check_timesteps(timesteps: u32) -> Result<(), ValidationError> {
+    if timesteps == 0 {
+        return Err(ValidationError::invalid("timesteps", "positive", timesteps));
+    }
+    Ok(())
+}
+
+fn check_guidance_scale(guidance_scale: f64) -> Result<(), ValidationError> {
+    if !(0.0..=f64::MAX).contains(&guidance_scale) {
+        return Err(ValidationError::invalid(
+            "guidance_scale",
+            "not negative",
+            guidance_scale,
+        ));
+    }
+    Ok(())
+}
+
+impl RequestBuilder {
+    fn validate(&self) -> Result<(), ValidationError> {
+        if let Some(prompt) = &self.prompt {
+            check_prompt(prompt)?;
+        }
+        if let Some(Some(image_count)) = self.image_count {
+            check_image_count(image_count)?;
+        }
+        if let Some(Some(width)) = self.width {
+            check_size("width", width)?;
+        }
+        if let Some(Some(height)) = self.height {
+            check_size("height", height)?;
+        }
+        if let Some(Some(timesteps)) = self.timesteps {
+            check_timesteps(timesteps)?;
+        }
+        if let Some(Some(guidance_scale)) = self.guidance_scale {
+            check_guidance_scale(guidance_scale)?;
+            // the negative prompt only steers the image with guidance
+            if let Some(Some(negative_prompt)) = &self.negative_prompt {
+                if guidance_scale <= 1.0 {
+                    return Err(ValidationError::invalid(
+                        "negative_prompt",
+                        "unset when guidance_scale is 1 or less",
+                        negative_prompt,
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Set `image_count`, failing immediately if it is out of range
+    pub fn try_image_count<VALUE: Into<u32>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let image_count = value.into();
+        check_image_count(image_count)?;
+        Ok(self.image_count(image_count))
+    }
+
+    /// Set `width`, failing immediately if it is not a valid size
+    pub fn try_width<VALUE: Into<u32>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let width = value.into();
+        check_size("width", width)?;
+        Ok(self.width(width))
+    }
+
+    /// Set `height`, failing immediately if it is not a valid size
+    pub fn try_height<VALUE: Into<u32>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let height = value.into();
+        check_size("height", height)?;
+        Ok(self.height(height))
+    }
+
+    /// Set `timesteps`, failing immediately if it is zero
+    pub fn try_timesteps<VALUE: Into<u32>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let timesteps = value.into();
+        check_timesteps(timesteps)?;
+        Ok(self.timesteps(timesteps))
+    }
+
+    /// Set `guidance_scale`, failing immediately if it is negative
+    pub fn try_guidance_scale<VALUE: Into<f64>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let guidance_scale = value.into();
+        check_guidance_scale(guidance_scale)?;
+        Ok(self.guidance_scale(guidance_scale))
+    }
+}
+
+/// Struct for a text to image answer
+#[derive(Deserialize, Debug)]
+pub struct Response {
+    /// The generated images.
+    pub images: Vec<Image>,
+}
+
+/// A single generated image
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    /// The JPEG encoded image.
+    #[serde(deserialize_with = "base64_bytes")]
+    pub data: Vec<u8>,
+}
+
+impl Image {
+    /// The JPEG encoded image
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Write the JPEG encoded image to `path`, e.g. `astronaut.jpg`
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, &self.data)
+    }
+
+    /// The decoded image
+    #[cfg(feature = "image")]
+    pub fn to_dynamic_image(&self) -> Result<image::DynamicImage, image::ImageError> {
+        image::load_from_memory_with_format(&self.data, image::ImageFormat::Jpeg)
+    }
+
+    /// The image encoded as PNG instead, e.g. for formats without JPEG
+    /// support or lossless editing
+    #[cfg(feature = "image")]
+    pub fn to_png_bytes(&self) -> Result<Vec<u8>, image::ImageError> {
+        let mut png = io::Cursor::new(Vec::new());
+        self.to_dynamic_image()?
+            .write_to(&mut png, image::ImageOutputFormat::Png)?;
+        Ok(png.into_inner())
+    }
+}
+
+fn base64_bytes<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let data = String::deserialize(deserializer)?;
+    base64::decode(data).map_err(de::Error::custom)
+}
+
+#[derive(Error, Debug)]
+/// Error for a text to image answer
+pub enum Error {
+    /// Serde error
+    #[error("Serde error: {0}")]
+    SerdeError(#[from] serde_json::Error),
+    /// Error from Reqwest
+    #[error("Reqwest error: {0}")]
+    RequestError(#[from] reqwest::Error),
+    /// Error answer of the api
+    #[error("API error: {0}")]
+    ApiError(#[from] crate::ApiError),
+    /// The api rate limited the request
+    #[error("Rate limited: {0}")]
+    RateLimited(#[from] crate::RateLimited),
+    /// The api rejected the api key
+    #[error("Unauthorized: {0}")]
+    Unauthorized(#[from] crate::Unauthorized),
+    /// The api key isn't allowed to make the request
+    #[error("Forbidden: {0}")]
+    Forbidden(#[from] crate::Forbidden),
+    /// Successful answer that couldn't be parsed
+    #[error("HTTP error: {0}")]
+    Http(#[from] crate::HttpError),
+}
+
+impl TextSynthClient {
+    /// Perform a text to image request
+    pub async fn text_to_image(
+        &self,
+        engine: &Engine,
+        request: &Request,
+    ) -> Result<Response, Error> {
+        Ok(self
+            .text_to_image_with_meta(engine, request)
+            .await?
+            .response)
+    }
+
+    /// Perform a text to image request, keeping the metadata of the response
+    pub async fn text_to_image_with_meta(
+        &self,
+        engine: &Engine,
+        request: &Request,
+    ) -> Result<WithMeta<Response>, Error> {
+        let request_json = serde_json::to_string(&request)?;
+        let url = format!("{}/engines/{}/text_to_image", self.base_url, engine);
+        self.post_with_meta(&url, request_json).await
+    }
+}
@@ -4,22 +4,68 @@ use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use thiserror::Error;
 
-use crate::{IsEngine, TextSynthClient};
+use crate::{validation::ValidationError, IsEngine, TextSynthClient, WithMeta};
 
 /// Struct for a tokenize request
 #[skip_serializing_none]
-#[derive(Serialize, Builder)]
+#[derive(Serialize, Builder, Debug, Clone)]
 #[builder(setter(into))]
+#[builder(derive(Debug))]
+#[builder(build_fn(error = "ValidationError"))]
 pub struct Request {
     /// Input text.
     text: String,
+    /// How the content of every token is returned in
+    /// [`Response::token_content`]. No content is returned by default.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    token_content_type: Option<TokenContentType>,
+}
+
+/// Error of building a [`Request`]
+pub type RequestBuilderError = ValidationError;
+
+impl Request {
+    /// Builder with the required `text` set, so that building fails only on
+    /// invalid parameters
+    pub fn builder(text: impl Into<String>) -> RequestBuilder {
+        let mut builder = RequestBuilder::default();
+        builder.text(text);
+        builder
+    }
+}
+
+/// Format of the token contents of a tokenization answer
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenContentType {
+    /// Don't return the token contents.
+    None,
+    /// Return the token contents as text. Tokens holding only part of a
+    /// multi-byte character can't be represented exactly.
+    Text,
+    /// Return the bytes of the token contents, base64 encoded.
+    Base64,
 }
 
 /// Struct for a tokenization answer
-#[derive(Deserialize, Debug)]
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Response {
     /// Token indexes corresponding to the input text.
     pub tokens: Vec<u32>,
+    /// Content of every token, in the format requested with
+    /// `token_content_type`.
+    pub token_content: Option<Vec<String>>,
+}
+
+impl Response {
+    /// Bytes of every token, if the contents were requested as
+    /// [`TokenContentType::Base64`]
+    pub fn token_bytes(&self) -> Option<Result<Vec<Vec<u8>>, base64::DecodeError>> {
+        let token_content = self.token_content.as_ref()?;
+        Some(token_content.iter().map(base64::decode).collect())
+    }
 }
 
 #[derive(Error, Debug)]
@@ -31,6 +77,27 @@ pub enum Error {
     /// Error from Reqwest
     #[error("Reqwest error: {0}")]
     RequestError(#[from] reqwest::Error),
+    /// Error answer of the api
+    #[error("API error: {0}")]
+    ApiError(#[from] crate::ApiError),
+    /// The api rate limited the request
+    #[error("Rate limited: {0}")]
+    RateLimited(#[from] crate::RateLimited),
+    /// The api rejected the api key
+    #[error("Unauthorized: {0}")]
+    Unauthorized(#[from] crate::Unauthorized),
+    /// The api key isn't allowed to make the request
+    #[error("Forbidden: {0}")]
+    Forbidden(#[from] crate::Forbidden),
+    /// Successful answer that couldn't be parsed
+    #[error("HTTP error: {0}")]
+    Http(#[from] crate::HttpError),
+    /// The token contents aren't valid base64
+    #[error("Invalid token content: {0}")]
+    TokenContentError(#[from] base64::DecodeError),
+    /// The answer has no token contents though they were requested
+    #[error("Missing token content")]
+    MissingTokenContent,
 }
 
 impl TextSynthClient {
@@ -40,9 +107,45 @@ impl TextSynthClient {
         engine: &impl IsEngine,
         request: &Request,
     ) -> Result<Response, Error> {
+        Ok(self.tokenize_with_meta(engine, request).await?.response)
+    }
+
+    /// Tokenize `text`, returning its token indexes
+    pub async fn tokenize_str(
+        &self,
+        engine: &impl IsEngine,
+        text: &str,
+    ) -> Result<Vec<u32>, Error> {
+        let request = RequestBuilder::default()
+            .text(text)
+            .build()
+            .expect("tokenize requests have no constraints");
+        Ok(self.tokenize(engine, &request).await?.tokens)
+    }
+
+    /// Perform a tokenization request, keeping the metadata of the response
+    pub async fn tokenize_with_meta(
+        &self,
+        engine: &impl IsEngine,
+        request: &Request,
+    ) -> Result<WithMeta<Response>, Error> {
         let request_json = serde_json::to_string(&request)?;
         let url = format!("{}/engines/{}/tokenize", self.base_url, engine);
-        let response = self.client.post(&url).body(request_json).send().await?;
-        response.json().await.map_err(|e| e.into())
+        self.post_with_meta(&url, request_json).await
+    }
+
+    /// Perform a tokenization request given as raw json, returning the raw
+    /// json answer
+    pub async fn tokenize_raw(
+        &self,
+        engine: &impl IsEngine,
+        request: &serde_json::Value,
+    ) -> Result<serde_json::Value, Error> {
+        let request_json = serde_json::to_string(request)?;
+        let url = format!("{}/engines/{}/tokenize", self.base_url, engine);
+        Ok(self
+            .post_with_meta::<_, Error>(&url, request_json)
+            .await?
+            .response)
     }
 }
@@ -0,0 +1,488 @@
+//! Provides speech to text api
+//!
+//! The audio file is uploaded as multipart/form-data. Besides bytes in
+//! memory, [`Audio`] can be a file on disk, an [`AsyncRead`] or a [`Stream`]
+//! of bytes, which are streamed when the request is sent instead of loaded
+//! into memory. Audio captured live can so be transcribed as it is recorded.
+
+pub mod subtitles;
+
+use std::{fmt, sync::Arc};
+#[cfg(feature = "streaming")]
+use std::{
+    io,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Mutex,
+    task::{Context, Poll},
+};
+
+#[cfg(feature = "streaming")]
+use bytes::Bytes;
+#[cfg(feature = "streaming")]
+use futures::{
+    stream::{self, BoxStream},
+    Stream, StreamExt,
+};
+use reqwest::multipart::{Form, Part};
+#[cfg(feature = "streaming")]
+use reqwest::Body;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use thiserror::Error;
+
+use crate::{IsEngine, TextSynthClient, WithMeta};
+#[cfg(feature = "streaming")]
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Size of the chunks a streamed audio file is read in
+#[cfg(feature = "streaming")]
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Enum for the different speech to text engines available for TextSynth
+#[derive(strum::IntoStaticStr, Debug, Clone, PartialEq, Eq)]
+pub enum Engine {
+    /// Whisper Large v3 is a speech to text model supporting about 100
+    /// languages.
+    #[strum(serialize = "whisper_large_v3")]
+    WhisperLargeV3,
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.into())
+    }
+}
+
+impl IsEngine for Engine {}
+
+/// Format of an audio file, sent as content type of the upload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AudioFormat {
+    /// MPEG audio layer 3
+    Mp3,
+    /// Waveform audio
+    Wav,
+    /// Ogg container, e.g. with vorbis or opus
+    Ogg,
+    /// MPEG-4 audio, e.g. with aac
+    M4a,
+}
+
+impl AudioFormat {
+    /// The format of files with extension `extension`, ignoring case
+    pub fn from_extension(extension: &str) -> Option<AudioFormat> {
+        match extension.to_ascii_lowercase().as_str() {
+            "mp3" => Some(AudioFormat::Mp3),
+            "wav" | "wave" => Some(AudioFormat::Wav),
+            "ogg" | "oga" | "opus" => Some(AudioFormat::Ogg),
+            "m4a" | "mp4" => Some(AudioFormat::M4a),
+            _ => None,
+        }
+    }
+
+    /// The format of the file named `file_name`, from its extension
+    pub fn from_file_name(file_name: &str) -> Option<AudioFormat> {
+        let (_, extension) = file_name.rsplit_once('.')?;
+        AudioFormat::from_extension(extension)
+    }
+
+    /// The format of the audio starting with `head`, from its magic bytes
+    pub fn sniff(head: &[u8]) -> Option<AudioFormat> {
+        match head {
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => {
+                Some(AudioFormat::Wav)
+            }
+            [b'O', b'g', b'g', b'S', ..] => Some(AudioFormat::Ogg),
+            [_, _, _, _, b'f', b't', b'y', b'p', ..] => Some(AudioFormat::M4a),
+            [b'I', b'D', b'3', ..] => Some(AudioFormat::Mp3),
+            [0xFF, second, ..] if second & 0xE0 == 0xE0 => Some(AudioFormat::Mp3),
+            _ => None,
+        }
+    }
+
+    /// The MIME type of the format
+    pub fn mime(self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "audio/mpeg",
+            AudioFormat::Wav => "audio/wav",
+            AudioFormat::Ogg => "audio/ogg",
+            AudioFormat::M4a => "audio/mp4",
+        }
+    }
+
+    /// The usual file extension of the format
+    pub fn extension(self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Wav => "wav",
+            AudioFormat::Ogg => "ogg",
+            AudioFormat::M4a => "m4a",
+        }
+    }
+}
+
+/// The audio file of a transcript request
+///
+/// Bytes convert into it with [`Into`], and so do paths with the `streaming`
+/// feature.
+#[derive(Clone)]
+pub struct Audio(Source);
+
+#[derive(Clone)]
+enum Source {
+    Bytes(Arc<[u8]>),
+    #[cfg(feature = "streaming")]
+    Path(PathBuf),
+    #[cfg(feature = "streaming")]
+    Stream(Arc<Mutex<Option<AudioStream>>>),
+}
+
+#[cfg(feature = "streaming")]
+type AudioStream = BoxStream<'static, io::Result<Bytes>>;
+
+impl Audio {
+    /// The audio file in memory
+    pub fn from_bytes(bytes: impl Into<Arc<[u8]>>) -> Audio {
+        Audio(Source::Bytes(bytes.into()))
+    }
+
+    /// The audio file at `path`, streamed from disk when the request is sent
+    #[cfg(feature = "streaming")]
+    pub fn from_path(path: impl Into<PathBuf>) -> Audio {
+        Audio(Source::Path(path.into()))
+    }
+
+    /// The audio file read from `reader`, streamed when the request is sent
+    ///
+    /// The reader is read only once, so requests with it can be sent only
+    /// once. Later attempts, including retries, fail with an io error.
+    #[cfg(feature = "streaming")]
+    pub fn from_reader(reader: impl AsyncRead + Send + 'static) -> Audio {
+        Audio::from_io_stream(read_chunks(reader))
+    }
+
+    /// The audio file made of the chunks of `stream`, uploaded as they
+    /// arrive
+    ///
+    /// Like readers, the stream is consumed by the first request sent with
+    /// it.
+    #[cfg(feature = "streaming")]
+    pub fn from_stream(stream: impl Stream<Item = Bytes> + Send + 'static) -> Audio {
+        Audio::from_io_stream(stream.map(Ok))
+    }
+
+    #[cfg(feature = "streaming")]
+    fn from_io_stream(stream: impl Stream<Item = io::Result<Bytes>> + Send + 'static) -> Audio {
+        Audio(Source::Stream(Arc::new(Mutex::new(Some(stream.boxed())))))
+    }
+
+    /// Name of the file on disk
+    fn file_name(&self) -> Option<String> {
+        match &self.0 {
+            #[cfg(feature = "streaming")]
+            Source::Path(path) => Some(path.file_name()?.to_string_lossy().into_owned()),
+            _ => None,
+        }
+    }
+
+    /// Format detected from the leading bytes, if they are known without
+    /// reading
+    fn sniff(&self) -> Option<AudioFormat> {
+        match &self.0 {
+            Source::Bytes(bytes) => AudioFormat::sniff(bytes),
+            #[cfg(feature = "streaming")]
+            _ => None,
+        }
+    }
+
+    /// The whole audio, read without an async runtime
+    #[cfg(feature = "ureq")]
+    pub(crate) fn read_blocking(&self) -> io::Result<Vec<u8>> {
+        match &self.0 {
+            Source::Bytes(bytes) => Ok(bytes.to_vec()),
+            Source::Path(path) => std::fs::read(path),
+            Source::Stream(stream) => {
+                let stream = stream
+                    .lock()
+                    .expect("audio stream lock poisoned")
+                    .take()
+                    .ok_or_else(|| io::Error::other("audio stream was already read"))?;
+                let mut audio = Vec::new();
+                for chunk in futures::executor::block_on_stream(stream) {
+                    audio.extend_from_slice(&chunk?);
+                }
+                Ok(audio)
+            }
+        }
+    }
+
+    /// The multipart part uploading the audio
+    async fn part(&self) -> Result<Part, Error> {
+        match &self.0 {
+            Source::Bytes(bytes) => Ok(Part::bytes(bytes.to_vec())),
+            #[cfg(feature = "streaming")]
+            Source::Path(path) => {
+                let file = tokio::fs::File::open(path).await?;
+                let length = file.metadata().await?.len();
+                let body = Body::wrap_stream(SyncStream(Mutex::new(read_chunks(file).boxed())));
+                Ok(Part::stream_with_length(body, length))
+            }
+            #[cfg(feature = "streaming")]
+            Source::Stream(stream) => {
+                let stream = stream
+                    .lock()
+                    .expect("audio stream lock poisoned")
+                    .take()
+                    .ok_or_else(|| io::Error::other("audio stream was already read"))?;
+                Ok(Part::stream(Body::wrap_stream(SyncStream(Mutex::new(
+                    stream,
+                )))))
+            }
+        }
+    }
+}
+
+/// The chunks read from `reader`
+#[cfg(feature = "streaming")]
+fn read_chunks(
+    reader: impl AsyncRead + Send + 'static,
+) -> impl Stream<Item = io::Result<Bytes>> + Send {
+    stream::try_unfold(Box::pin(reader), |mut reader| async move {
+        let mut chunk = vec![0; CHUNK_SIZE];
+        let read = reader.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        chunk.truncate(read);
+        Ok(Some((Bytes::from(chunk), reader)))
+    })
+}
+
+/// Stream that is [`Sync`], as request bodies must be, by only ever being
+/// polled through a unique reference
+#[cfg(feature = "streaming")]
+struct SyncStream(Mutex<AudioStream>);
+
+#[cfg(feature = "streaming")]
+impl Stream for SyncStream {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut()
+            .0
+            .get_mut()
+            .expect("audio stream lock poisoned")
+            .as_mut()
+            .poll_next(cx)
+    }
+}
+
+impl fmt::Debug for Audio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Source::Bytes(bytes) => write!(f, "Audio({} bytes)", bytes.len()),
+            #[cfg(feature = "streaming")]
+            Source::Path(path) => write!(f, "Audio({})", path.display()),
+            #[cfg(feature = "streaming")]
+            Source::Stream(_) => f.write_str("Audio(stream)"),
+        }
+    }
+}
+
+impl From<Vec<u8>> for Audio {
+    fn from(bytes: Vec<u8>) -> Self {
+        Audio::from_bytes(bytes)
+    }
+}
+
+impl From<&[u8]> for Audio {
+    fn from(bytes: &[u8]) -> Self {
+        Audio::from_bytes(bytes)
+    }
+}
+
+impl From<Arc<[u8]>> for Audio {
+    fn from(bytes: Arc<[u8]>) -> Self {
+        Audio::from_bytes(bytes)
+    }
+}
+
+#[cfg(feature = "streaming")]
+impl From<PathBuf> for Audio {
+    fn from(path: PathBuf) -> Self {
+        Audio::from_path(path)
+    }
+}
+
+#[cfg(feature = "streaming")]
+impl From<&Path> for Audio {
+    fn from(path: &Path) -> Self {
+        Audio::from_path(path)
+    }
+}
+
+/// Struct for a transcript request
+#[skip_serializing_none]
+#[derive(Serialize, Builder, Debug, Clone)]
+#[builder(setter(into))]
+#[builder(derive(Debug))]
+pub struct Request {
+    /// The audio file, in any format supported by ffmpeg. It is uploaded
+    /// rather than serialized with the other parameters.
+    #[serde(skip)]
+    audio: Audio,
+    /// Name of the uploaded audio file. Its extension may help the server
+    /// detect the audio format. Defaults to the name of the file on disk, or
+    /// "audio" with the extension of the format.
+    #[serde(skip)]
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    file_name: Option<String>,
+    /// Format of the audio file, sent as content type of the upload. Detected
+    /// from the file name or the leading bytes of the audio if not given.
+    #[serde(skip)]
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    format: Option<AudioFormat>,
+    /// Two or three character ISO language code of the speech. The language
+    /// is detected if it isn't given.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    language: Option<String>,
+}
+
+impl Request {
+    /// Builder with the required `audio` set, so that building fails only on
+    /// invalid parameters
+    pub fn builder(audio: impl Into<Audio>) -> RequestBuilder {
+        let mut builder = RequestBuilder::default();
+        builder.audio(audio);
+        builder
+    }
+
+    /// Name and format the audio is uploaded with, as given, from the file
+    /// name or sniffed from the audio
+    pub(crate) fn upload_name(&self) -> (String, Option<AudioFormat>) {
+        let file_name = self.file_name.clone().or_else(|| self.audio.file_name());
+        let format = self
+            .format
+            .or_else(|| file_name.as_deref().and_then(AudioFormat::from_file_name))
+            .or_else(|| self.audio.sniff());
+        let file_name = file_name.unwrap_or_else(|| match format {
+            Some(format) => format!("audio.{}", format.extension()),
+            None => "audio".to_string(),
+        });
+        (file_name, format)
+    }
+
+    /// The audio to upload
+    #[cfg(feature = "ureq")]
+    pub(crate) fn audio(&self) -> &Audio {
+        &self.audio
+    }
+
+    /// The multipart part uploading the audio, named and typed
+    async fn file_part(&self) -> Result<Part, Error> {
+        let (file_name, format) = self.upload_name();
+        let part = self.audio.part().await?.file_name(file_name);
+        Ok(match format {
+            Some(format) => part.mime_str(format.mime())?,
+            None => part,
+        })
+    }
+}
+
+/// Struct for a transcript answer
+#[derive(Deserialize, Debug)]
+pub struct Response {
+    /// The transcribed text.
+    pub text: String,
+    /// ISO language code of the speech, as given or detected.
+    pub language: String,
+    /// Duration of the audio in seconds.
+    pub duration: Option<f64>,
+    /// The transcription split into timed segments.
+    #[serde(default)]
+    pub segments: Vec<Segment>,
+}
+
+/// A timed segment of a transcription
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Segment {
+    /// Index of the segment.
+    pub id: u32,
+    /// Start of the segment in seconds.
+    pub start: f64,
+    /// End of the segment in seconds.
+    pub end: f64,
+    /// The transcribed text of the segment.
+    pub text: String,
+    /// The words of the segment with their timestamps, if the api reported
+    /// them.
+    #[serde(default)]
+    pub words: Vec<Word>,
+}
+
+/// A timed word of a transcription segment
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Word {
+    /// The transcribed word, with its leading space if any.
+    pub word: String,
+    /// Start of the word in seconds.
+    pub start: f64,
+    /// End of the word in seconds.
+    pub end: f64,
+}
+
+#[derive(Error, Debug)]
+/// Error for a transcript answer
+pub enum Error {
+    /// Serde error
+    #[error("Serde error: {0}")]
+    SerdeError(#[from] serde_json::Error),
+    /// Error from Reqwest
+    #[error("Reqwest error: {0}")]
+    RequestError(#[from] reqwest::Error),
+    /// Error reading the audio file
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    /// Error answer of the api
+    #[error("API error: {0}")]
+    ApiError(#[from] crate::ApiError),
+    /// The api rate limited the request
+    #[error("Rate limited: {0}")]
+    RateLimited(#[from] crate::RateLimited),
+    /// The api rejected the api key
+    #[error("Unauthorized: {0}")]
+    Unauthorized(#[from] crate::Unauthorized),
+    /// The api key isn't allowed to make the request
+    #[error("Forbidden: {0}")]
+    Forbidden(#[from] crate::Forbidden),
+    /// Successful answer that couldn't be parsed
+    #[error("HTTP error: {0}")]
+    Http(#[from] crate::HttpError),
+}
+
+impl TextSynthClient {
+    /// Perform a transcript request, uploading the audio file
+    pub async fn transcript(&self, engine: &Engine, request: &Request) -> Result<Response, Error> {
+        Ok(self.transcript_with_meta(engine, request).await?.response)
+    }
+
+    /// Perform a transcript request, keeping the metadata of the response
+    pub async fn transcript_with_meta(
+        &self,
+        engine: &Engine,
+        request: &Request,
+    ) -> Result<WithMeta<Response>, Error> {
+        let request_json = serde_json::to_string(&request)?;
+        let form = Form::new()
+            .text("json", request_json)
+            .part("file", request.file_part().await?);
+        let url = format!("{}/engines/{}/transcript", self.base_url, engine);
+        self.send_with_meta(self.client.post(&url).multipart(form))
+            .await
+    }
+}
@@ -0,0 +1,68 @@
+//! Provides subtitles of transcripts
+//!
+//! Every [`Segment`] of a [`Response`] becomes one cue, shown from the start
+//! to the end of the segment.
+
+use std::fmt::Write;
+
+use super::{Response, Segment};
+
+/// Format of a subtitle file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Srt,
+    Vtt,
+}
+
+impl Response {
+    /// The segments as SubRip subtitles, the `.srt` format
+    pub fn to_srt(&self) -> String {
+        subtitles(&self.segments, Format::Srt)
+    }
+
+    /// The segments as WebVTT subtitles, the `.vtt` format
+    pub fn to_vtt(&self) -> String {
+        subtitles(&self.segments, Format::Vtt)
+    }
+}
+
+/// `segments` as subtitles in `format`, skipping segments without text
+fn subtitles(segments: &[Segment], format: Format) -> String {
+    let mut subtitles = String::new();
+    if format == Format::Vtt {
+        subtitles.push_str("WEBVTT\n\n");
+    }
+    let cues = segments
+        .iter()
+        .filter(|segment| !segment.text.trim().is_empty());
+    for (index, segment) in cues.enumerate() {
+        if format == Format::Srt {
+            let _ = writeln!(subtitles, "{}", index + 1);
+        }
+        let _ = writeln!(
+            subtitles,
+            "{} --> {}\n{}\n",
+            timestamp(segment.start, format),
+            timestamp(segment.end, format),
+            segment.text.trim()
+        );
+    }
+    subtitles
+}
+
+/// `seconds` as `hh:mm:ss,mmm`, with a dot before the milliseconds for WebVTT
+fn timestamp(seconds: f64, format: Format) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    let separator = match format {
+        Format::Srt => ',',
+        Format::Vtt => '.',
+    };
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
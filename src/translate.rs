@@ -1,43 +1,124 @@
 //! Provides translate api
 
+pub mod language;
+pub mod memory;
+pub mod pipeline;
+
+use std::{fmt, str::FromStr, sync::Arc};
+
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use thiserror::Error;
 
-use crate::{IsEngine, TextSynthClient};
+use crate::{
+    engine::{engine_info, EngineDescriptor, EngineInfo, ParseEngineError},
+    record_tokens,
+    validation::ValidationError,
+    IsEngine, TextSynthClient, WithMeta,
+};
+
+pub use language::Language;
+
+/// Maximum number of characters of a text sent to detect its language, see
+/// [`TextSynthClient::detect_language`]
+pub const DETECTION_SAMPLE_CHARS: usize = 200;
 
 /// Enum for the different translation engines available for TextSynth
-#[derive(strum::Display)]
+///
+/// New engines are added over time, use [`Engine::Other`] for the ones this
+/// version of the crate doesn't know yet.
+#[derive(strum::IntoStaticStr, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Engine {
     /// M2M100 1.2B is a 1.2 billion parameter language model specialized for
     /// translation. It supports multilingual translation between 100 languages.
     #[strum(serialize = "m2m100_1_2B")]
     M2M10012B,
+    /// An engine the crate doesn't know about, see the `engine` module.
+    Custom(&'static EngineDescriptor),
+    /// An engine the crate doesn't know about, by its api name.
+    Other(String),
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Engine::Custom(descriptor) => f.write_str(descriptor.name),
+            Engine::Other(id) => f.write_str(id),
+            engine => f.write_str(engine.into()),
+        }
+    }
+}
+
+impl Engine {
+    /// Metadata of the engine, unknown for [`Engine::Other`] engines the
+    /// crate doesn't know
+    pub fn info(&self) -> Option<EngineInfo> {
+        match self {
+            Engine::Custom(descriptor) => Some(descriptor.info()),
+            engine => engine_info(&engine.to_string()).cloned(),
+        }
+    }
+
+    /// Find the engine with the api name `id`
+    pub(crate) fn from_id(id: &str) -> Option<Engine> {
+        [Engine::M2M10012B]
+            .into_iter()
+            .find(|engine| engine.to_string() == id)
+    }
+}
+
+/// Parses the api name of a built-in engine
+impl FromStr for Engine {
+    type Err = ParseEngineError;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        Engine::from_id(id).ok_or_else(|| ParseEngineError::new("translation", id))
+    }
+}
+
+impl TryFrom<&str> for Engine {
+    type Error = ParseEngineError;
+
+    fn try_from(id: &str) -> Result<Self, Self::Error> {
+        id.parse()
+    }
 }
 
 impl IsEngine for Engine {
+    fn is_completion(&self) -> bool {
+        match self {
+            Engine::Custom(descriptor) => descriptor.is_completion(),
+            _ => false,
+        }
+    }
+
     fn is_translation(&self) -> bool {
-        true
+        match self {
+            Engine::Custom(descriptor) => descriptor.is_translation(),
+            _ => true,
+        }
     }
 }
 
 /// Struct for a translation request
 #[skip_serializing_none]
-#[derive(Serialize, Builder)]
+#[derive(Serialize, Builder, Debug, Clone)]
 #[builder(setter(into))]
-#[builder(build_fn(validate = "Self::validate"))]
+#[builder(derive(Debug))]
+#[builder(build_fn(validate = "Self::validate", error = "ValidationError"))]
 pub struct Request {
     /// Each string is an independent text to translate. Batches of at most 64
-    /// texts can be provided.
-    text: Vec<String>,
-    /// Two or three character ISO language code for the source language. The
-    /// special value "auto" indicates to auto-detect the source language. The
-    /// language auto-detection does not support all languages and is based on
-    /// heuristics. Hence if you know the source language you should explicitly
-    /// indicate it.
-    source_lang: String,
-    /// Two or three character ISO language code for the target language.
-    target_lang: String,
+    /// texts can be provided. Shared rather than copied when the request or
+    /// builder is cloned.
+    text: Vec<Arc<str>>,
+    /// The source language. [`Language::Auto`] indicates to auto-detect the
+    /// source language. The language auto-detection does not support all
+    /// languages and is based on heuristics. Hence if you know the source
+    /// language you should explicitly indicate it.
+    source_lang: Language,
+    /// The target language.
+    target_lang: Language,
     /// Number of beams used to generate the translated text. The translation is
     /// usually better with a larger number of beams. Each beam requires
     /// generating a separate translated text, hence the number of generated
@@ -56,49 +137,138 @@ pub struct Request {
     split_sentences: Option<bool>,
 }
 
+/// Maximum number of texts of a single translation request
+pub const MAX_TEXTS: usize = 64;
+
+/// Error of building a [`Request`]
+pub type RequestBuilderError = ValidationError;
+
+impl Request {
+    /// Builder with the required `text`, `source_lang` and `target_lang` set,
+    /// so that building fails only on invalid parameters
+    pub fn builder(
+        text: impl Into<Vec<Arc<str>>>,
+        source_lang: impl Into<Language>,
+        target_lang: impl Into<Language>,
+    ) -> RequestBuilder {
+        let mut builder = RequestBuilder::default();
+        builder
+            .text(text)
+            .source_lang(source_lang)
+            .target_lang(target_lang);
+        builder
+    }
+}
+
+fn check_text(text: &[Arc<str>]) -> Result<(), ValidationError> {
+    // text has length 1 to 64
+    if !(1..=MAX_TEXTS).contains(&text.len()) {
+        return Err(ValidationError::invalid(
+            "text",
+            "1 to 64 elements",
+            format!("{} elements", text.len()),
+        ));
+    }
+    Ok(())
+}
+
+/// Constraint of languages, which must be among the ones of M2M100
+const SUPPORTED_LANGUAGE: &str = "a language supported by M2M100";
+
+fn check_code(field: &'static str, lang: &Language) -> Result<(), ValidationError> {
+    // the api rejects codes M2M100 doesn't support
+    if let Language::Other(code) = lang {
+        return Err(ValidationError::invalid(field, SUPPORTED_LANGUAGE, code));
+    }
+    Ok(())
+}
+
+fn check_source_lang(source_lang: &Language) -> Result<(), ValidationError> {
+    check_code("source_lang", source_lang)
+}
+
+fn check_target_lang(target_lang: &Language) -> Result<(), ValidationError> {
+    if *target_lang == Language::Auto {
+        return Err(ValidationError::invalid(
+            "target_lang",
+            "a language other than auto",
+            "auto",
+        ));
+    }
+    check_code("target_lang", target_lang)
+}
+
+fn check_num_beams(num_beams: u32) -> Result<(), ValidationError> {
+    // num_beams has range 1 to 5
+    if !(1..=5).contains(&num_beams) {
+        return Err(ValidationError::invalid(
+            "num_beams",
+            "between 1 and 5",
+            num_beams,
+        ));
+    }
+    Ok(())
+}
+
 impl RequestBuilder {
-    fn validate(&self) -> Result<(), String> {
-        // text has length 1 to 64
-        match &self.text {
-            Some(text) if !(1..=64).contains(&text.len()) => {
-                return Err("text has to have 1 to 64 elements".to_string());
-            }
-            _ => {}
+    fn validate(&self) -> Result<(), ValidationError> {
+        if let Some(text) = &self.text {
+            check_text(text)?;
         }
-        // source_lang is 2 or 3 characters long or is "auto"
-        match &self.source_lang {
-            Some(source_lang)
-                if !(source_lang.len() == 2 || source_lang.len() == 3 || source_lang == "auto") =>
-            {
-                return Err(
-                    "source_lang has to be a 2 or 3 characters long iso language code or be \"auto\""
-                        .to_string(),
-                );
-            }
-            _ => {}
+        if let Some(source_lang) = &self.source_lang {
+            check_source_lang(source_lang)?;
         }
-        // target_lang is 2 or 3 characters long
-        match &self.target_lang {
-            Some(target_lang) if !(target_lang.len() == 2 || target_lang.len() == 3) => {
-                return Err(
-                    "target_lang has to be a 2 or 3 characters long iso language code".to_string(),
-                );
-            }
-            _ => {}
+        if let Some(target_lang) = &self.target_lang {
+            check_target_lang(target_lang)?;
         }
-        // num_beams has range 1 to 5
-        match self.num_beams {
-            Some(Some(num_beams)) if !(1..=5).contains(&num_beams) => {
-                return Err("num_beams has to be in the range 1 to 5".to_string());
-            }
-            _ => {}
+        if let Some(Some(num_beams)) = self.num_beams {
+            check_num_beams(num_beams)?;
         }
         Ok(())
     }
+
+    /// Set `text`, failing immediately if it has too few or too many elements
+    pub fn try_text<VALUE: Into<Vec<Arc<str>>>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let text = value.into();
+        check_text(&text)?;
+        Ok(self.text(text))
+    }
+
+    /// Set `source_lang` from an ISO code or "auto", failing immediately if it
+    /// isn't a known language
+    pub fn try_source_lang(&mut self, code: &str) -> Result<&mut Self, RequestBuilderError> {
+        let source_lang = code
+            .parse::<Language>()
+            .map_err(|_| ValidationError::invalid("source_lang", SUPPORTED_LANGUAGE, code))?;
+        Ok(self.source_lang(source_lang))
+    }
+
+    /// Set `target_lang` from an ISO code, failing immediately if it isn't a
+    /// known language
+    pub fn try_target_lang(&mut self, code: &str) -> Result<&mut Self, RequestBuilderError> {
+        let target_lang = code
+            .parse::<Language>()
+            .map_err(|_| ValidationError::invalid("target_lang", SUPPORTED_LANGUAGE, code))?;
+        check_target_lang(&target_lang)?;
+        Ok(self.target_lang(target_lang))
+    }
+
+    /// Set `num_beams`, failing immediately if it is out of range
+    pub fn try_num_beams<VALUE: Into<u32>>(
+        &mut self,
+        value: VALUE,
+    ) -> Result<&mut Self, RequestBuilderError> {
+        let num_beams = value.into();
+        check_num_beams(num_beams)?;
+        Ok(self.num_beams(num_beams))
+    }
 }
 
 /// Struct for a translation answer
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Response {
     /// Array of translation objects.
     pub translations: Vec<Translation>,
@@ -111,7 +281,7 @@ pub struct Response {
 }
 
 /// a single translation result
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Translation {
     /// translated text
     pub text: String,
@@ -129,14 +299,129 @@ pub enum Error {
     /// Error from Reqwest
     #[error("Reqwest error: {0}")]
     RequestError(#[from] reqwest::Error),
+    /// Error answer of the api
+    #[error("API error: {0}")]
+    ApiError(#[from] crate::ApiError),
+    /// The api rate limited the request
+    #[error("Rate limited: {0}")]
+    RateLimited(#[from] crate::RateLimited),
+    /// The api rejected the api key
+    #[error("Unauthorized: {0}")]
+    Unauthorized(#[from] crate::Unauthorized),
+    /// The api key isn't allowed to make the request
+    #[error("Forbidden: {0}")]
+    Forbidden(#[from] crate::Forbidden),
+    /// Successful answer that couldn't be parsed
+    #[error("HTTP error: {0}")]
+    Http(#[from] crate::HttpError),
+    /// The request couldn't be built
+    #[error("Invalid request: {0}")]
+    BuilderError(#[from] RequestBuilderError),
+    /// The api answered with another number of translations than texts sent
+    #[error("Expected {expected} translations, got {got}")]
+    TranslationCount {
+        /// Number of texts sent
+        expected: usize,
+        /// Number of translations in the answer
+        got: usize,
+    },
 }
 
 impl TextSynthClient {
     /// Perform a completion request
     pub async fn translate(&self, engine: &Engine, request: &Request) -> Result<Response, Error> {
+        Ok(self.translate_with_meta(engine, request).await?.response)
+    }
+
+    /// Translate a single `text` from `source_lang` to `target_lang`
+    pub async fn translate_one(
+        &self,
+        engine: &Engine,
+        text: &str,
+        source_lang: impl Into<Language>,
+        target_lang: impl Into<Language>,
+    ) -> Result<String, Error> {
+        let request = RequestBuilder::default()
+            .text(vec![Arc::from(text)])
+            .source_lang(source_lang.into())
+            .target_lang(target_lang.into())
+            .build()?;
+        Ok(self.translate_single(engine, &request).await?.text)
+    }
+
+    /// Detect the language of `text` with the translation `engine`, returning
+    /// its ISO code
+    ///
+    /// Only the first [`DETECTION_SAMPLE_CHARS`] characters are translated,
+    /// with a single beam and as one sentence, to keep the cost of the
+    /// translation low.
+    pub async fn detect_language(&self, engine: &Engine, text: &str) -> Result<String, Error> {
+        let sample = match text.char_indices().nth(DETECTION_SAMPLE_CHARS) {
+            Some((end, _)) => &text[..end],
+            None => text,
+        };
+        let request = RequestBuilder::default()
+            .text(vec![Arc::from(sample)])
+            .source_lang("auto")
+            .target_lang("en")
+            .num_beams(1_u32)
+            .split_sentences(false)
+            .build()?;
+        Ok(self
+            .translate_single(engine, &request)
+            .await?
+            .detected_source_lang)
+    }
+
+    /// Perform a translation `request` of a single text, failing if the
+    /// answer doesn't hold exactly one translation
+    async fn translate_single(
+        &self,
+        engine: &Engine,
+        request: &Request,
+    ) -> Result<Translation, Error> {
+        let response = self.translate(engine, request).await?;
+        match <[Translation; 1]>::try_from(response.translations) {
+            Ok([translation]) => Ok(translation),
+            Err(translations) => Err(Error::TranslationCount {
+                expected: 1,
+                got: translations.len(),
+            }),
+        }
+    }
+
+    /// Perform a translation request, keeping the metadata of the response
+    pub async fn translate_with_meta(
+        &self,
+        engine: &Engine,
+        request: &Request,
+    ) -> Result<WithMeta<Response>, Error> {
         let request_json = serde_json::to_string(&request)?;
         let url = format!("{}/engines/{}/translate", self.base_url, engine);
-        let response = self.client.post(&url).body(request_json).send().await?;
-        response.json().await.map_err(|e| e.into())
+        let response = self
+            .post_with_meta::<Response, Error>(&url, request_json)
+            .await?;
+        record_tokens(
+            self.usage.as_ref(),
+            engine,
+            Some(response.response.input_tokens),
+            Some(response.response.output_tokens),
+        );
+        Ok(response)
+    }
+
+    /// Perform a translation request given as raw json, returning the raw
+    /// json answer
+    pub async fn translate_raw(
+        &self,
+        engine: &Engine,
+        request: &serde_json::Value,
+    ) -> Result<serde_json::Value, Error> {
+        let request_json = serde_json::to_string(request)?;
+        let url = format!("{}/engines/{}/translate", self.base_url, engine);
+        Ok(self
+            .post_with_meta::<_, Error>(&url, request_json)
+            .await?
+            .response)
     }
 }
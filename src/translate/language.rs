@@ -0,0 +1,298 @@
+//! Provides the languages supported by the translation engine
+
+use std::{fmt, str::FromStr};
+
+use serde::{Serialize, Serializer};
+use thiserror::Error;
+
+/// Declare the `Language` enum together with the ISO codes of its variants
+macro_rules! languages {
+    ($($(#[$meta:meta])* $name:ident = $code:literal,)*) => {
+        /// Language of a translation, identified by its ISO code
+        ///
+        /// Covers the 100 languages of M2M100. Other codes become
+        /// [`Language::Other`], which requests reject as M2M100 doesn't
+        /// support them.
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum Language {
+            /// Auto-detect the source language, only valid as source.
+            Auto,
+            $($(#[$meta])* $name,)*
+            /// A language M2M100 doesn't support, by its ISO code.
+            Other(String),
+        }
+
+        impl Language {
+            /// Every language known to the crate, without `Auto`
+            pub const ALL: &'static [Language] = &[$(Language::$name,)*];
+
+            /// The ISO code of the language, or "auto"
+            pub fn code(&self) -> &str {
+                match self {
+                    Language::Auto => "auto",
+                    $(Language::$name => $code,)*
+                    Language::Other(code) => code,
+                }
+            }
+
+            /// Find the known language with the ISO code `code`
+            fn from_code(code: &str) -> Option<Language> {
+                match code {
+                    "auto" => Some(Language::Auto),
+                    $($code => Some(Language::$name),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+languages! {
+    /// Afrikaans
+    Afrikaans = "af",
+    /// Amharic
+    Amharic = "am",
+    /// Arabic
+    Arabic = "ar",
+    /// Asturian
+    Asturian = "ast",
+    /// Azerbaijani
+    Azerbaijani = "az",
+    /// Bashkir
+    Bashkir = "ba",
+    /// Belarusian
+    Belarusian = "be",
+    /// Bulgarian
+    Bulgarian = "bg",
+    /// Bengali
+    Bengali = "bn",
+    /// Breton
+    Breton = "br",
+    /// Bosnian
+    Bosnian = "bs",
+    /// Catalan
+    Catalan = "ca",
+    /// Cebuano
+    Cebuano = "ceb",
+    /// Czech
+    Czech = "cs",
+    /// Welsh
+    Welsh = "cy",
+    /// Danish
+    Danish = "da",
+    /// German
+    German = "de",
+    /// Greek
+    Greek = "el",
+    /// English
+    English = "en",
+    /// Spanish
+    Spanish = "es",
+    /// Estonian
+    Estonian = "et",
+    /// Persian
+    Persian = "fa",
+    /// Fulah
+    Fulah = "ff",
+    /// Finnish
+    Finnish = "fi",
+    /// French
+    French = "fr",
+    /// Western Frisian
+    WesternFrisian = "fy",
+    /// Irish
+    Irish = "ga",
+    /// Scottish Gaelic
+    ScottishGaelic = "gd",
+    /// Galician
+    Galician = "gl",
+    /// Gujarati
+    Gujarati = "gu",
+    /// Hausa
+    Hausa = "ha",
+    /// Hebrew
+    Hebrew = "he",
+    /// Hindi
+    Hindi = "hi",
+    /// Croatian
+    Croatian = "hr",
+    /// Haitian Creole
+    HaitianCreole = "ht",
+    /// Hungarian
+    Hungarian = "hu",
+    /// Armenian
+    Armenian = "hy",
+    /// Indonesian
+    Indonesian = "id",
+    /// Igbo
+    Igbo = "ig",
+    /// Iloko
+    Iloko = "ilo",
+    /// Icelandic
+    Icelandic = "is",
+    /// Italian
+    Italian = "it",
+    /// Japanese
+    Japanese = "ja",
+    /// Javanese
+    Javanese = "jv",
+    /// Georgian
+    Georgian = "ka",
+    /// Kazakh
+    Kazakh = "kk",
+    /// Khmer
+    Khmer = "km",
+    /// Kannada
+    Kannada = "kn",
+    /// Korean
+    Korean = "ko",
+    /// Luxembourgish
+    Luxembourgish = "lb",
+    /// Ganda
+    Ganda = "lg",
+    /// Lingala
+    Lingala = "ln",
+    /// Lao
+    Lao = "lo",
+    /// Lithuanian
+    Lithuanian = "lt",
+    /// Latvian
+    Latvian = "lv",
+    /// Malagasy
+    Malagasy = "mg",
+    /// Macedonian
+    Macedonian = "mk",
+    /// Malayalam
+    Malayalam = "ml",
+    /// Mongolian
+    Mongolian = "mn",
+    /// Marathi
+    Marathi = "mr",
+    /// Malay
+    Malay = "ms",
+    /// Burmese
+    Burmese = "my",
+    /// Nepali
+    Nepali = "ne",
+    /// Dutch
+    Dutch = "nl",
+    /// Norwegian
+    Norwegian = "no",
+    /// Northern Sotho
+    NorthernSotho = "ns",
+    /// Occitan
+    Occitan = "oc",
+    /// Oriya
+    Oriya = "or",
+    /// Punjabi
+    Punjabi = "pa",
+    /// Polish
+    Polish = "pl",
+    /// Pashto
+    Pashto = "ps",
+    /// Portuguese
+    Portuguese = "pt",
+    /// Romanian
+    Romanian = "ro",
+    /// Russian
+    Russian = "ru",
+    /// Sindhi
+    Sindhi = "sd",
+    /// Sinhala
+    Sinhala = "si",
+    /// Slovak
+    Slovak = "sk",
+    /// Slovenian
+    Slovenian = "sl",
+    /// Somali
+    Somali = "so",
+    /// Albanian
+    Albanian = "sq",
+    /// Serbian
+    Serbian = "sr",
+    /// Swati
+    Swati = "ss",
+    /// Sundanese
+    Sundanese = "su",
+    /// Swedish
+    Swedish = "sv",
+    /// Swahili
+    Swahili = "sw",
+    /// Tamil
+    Tamil = "ta",
+    /// Thai
+    Thai = "th",
+    /// Tagalog
+    Tagalog = "tl",
+    /// Tswana
+    Tswana = "tn",
+    /// Turkish
+    Turkish = "tr",
+    /// Ukrainian
+    Ukrainian = "uk",
+    /// Urdu
+    Urdu = "ur",
+    /// Uzbek
+    Uzbek = "uz",
+    /// Vietnamese
+    Vietnamese = "vi",
+    /// Wolof
+    Wolof = "wo",
+    /// Xhosa
+    Xhosa = "xh",
+    /// Yiddish
+    Yiddish = "yi",
+    /// Yoruba
+    Yoruba = "yo",
+    /// Chinese
+    Chinese = "zh",
+    /// Zulu
+    Zulu = "zu",
+}
+
+impl Language {
+    /// Returns true if the language is known to the crate
+    pub fn is_known(&self) -> bool {
+        !matches!(self, Language::Other(_))
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Error parsing an unknown language code
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("unknown language code {0}")]
+pub struct ParseLanguageError(pub String);
+
+/// Parses the code of a known language, failing for any other code
+impl FromStr for Language {
+    type Err = ParseLanguageError;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        Language::from_code(code).ok_or_else(|| ParseLanguageError(code.to_string()))
+    }
+}
+
+/// Known codes become their language, any other code [`Language::Other`]
+impl From<&str> for Language {
+    fn from(code: &str) -> Self {
+        Language::from_code(code).unwrap_or_else(|| Language::Other(code.to_string()))
+    }
+}
+
+impl From<String> for Language {
+    fn from(code: String) -> Self {
+        Language::from_code(&code).unwrap_or(Language::Other(code))
+    }
+}
+
+impl Serialize for Language {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+}
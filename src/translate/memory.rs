@@ -0,0 +1,224 @@
+//! Provides a translation memory consulted before the translate api
+
+use std::collections::HashMap;
+
+use crate::TextSynthClient;
+
+use super::{Engine, Error, Request, Response, Translation};
+
+/// A stored translation returned by a translation memory lookup
+#[derive(Debug)]
+pub struct Match {
+    /// Source text of the stored entry.
+    pub source: String,
+    /// Translated text of the stored entry.
+    pub text: String,
+    /// Source language the api detected when the entry was recorded.
+    pub detected_source_lang: String,
+    /// Similarity between the looked up text and the stored source, between
+    /// 0.0 and 1.0. A value of 1.0 is an exact match.
+    pub similarity: f64,
+}
+
+/// Backend storing translations keyed by source text and language pair
+pub trait TranslationMemory {
+    /// Returns the stored translation of `text` whose source is at least
+    /// `threshold` similar to it, preferring the most similar one.
+    fn lookup(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        threshold: f64,
+    ) -> Option<Match>;
+    /// Records a translation of `text`.
+    fn insert(
+        &mut self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        translation: &Translation,
+    );
+}
+
+/// Translation memory kept in process memory
+#[derive(Default, Debug)]
+pub struct InMemoryStore {
+    /// (source_lang, target_lang) -> source text -> (text, detected_source_lang)
+    entries: HashMap<(String, String), HashMap<String, (String, String)>>,
+}
+
+impl InMemoryStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of stored translations
+    pub fn len(&self) -> usize {
+        self.entries.values().map(HashMap::len).sum()
+    }
+
+    /// Returns true if no translation is stored
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl TranslationMemory for InMemoryStore {
+    fn lookup(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        threshold: f64,
+    ) -> Option<Match> {
+        let pair = self
+            .entries
+            .get(&(source_lang.to_string(), target_lang.to_string()))?;
+        let to_match = |(source, (text, detected_source_lang)): (&String, &(String, String)),
+                        similarity| Match {
+            source: source.clone(),
+            text: text.clone(),
+            detected_source_lang: detected_source_lang.clone(),
+            similarity,
+        };
+        if let Some(entry) = pair.get_key_value(text) {
+            return Some(to_match(entry, 1.0));
+        }
+        if threshold >= 1.0 {
+            return None;
+        }
+        pair.iter()
+            .map(|entry| (similarity(text, entry.0), entry))
+            .filter(|(score, _)| *score >= threshold)
+            .max_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(score, entry)| to_match(entry, score))
+    }
+
+    fn insert(
+        &mut self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        translation: &Translation,
+    ) {
+        self.entries
+            .entry((source_lang.to_string(), target_lang.to_string()))
+            .or_default()
+            .insert(
+                text.to_string(),
+                (
+                    translation.text.clone(),
+                    translation.detected_source_lang.clone(),
+                ),
+            );
+    }
+}
+
+/// Similarity of two texts between 0.0 and 1.0, based on the character level
+/// levenshtein distance relative to the length of the longer text.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    // single row levenshtein
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    1.0 - row[b.len()] as f64 / longest as f64
+}
+
+impl TextSynthClient {
+    /// Perform a translation request, answering texts from `memory` where a
+    /// stored translation is at least `threshold` similar and recording the
+    /// translations of the remaining texts.
+    pub async fn translate_with_memory(
+        &self,
+        engine: &Engine,
+        request: &Request,
+        memory: &mut impl TranslationMemory,
+        threshold: f64,
+    ) -> Result<Response, Error> {
+        let mut translations: Vec<Option<Translation>> = request
+            .text
+            .iter()
+            .map(|text| {
+                memory
+                    .lookup(
+                        text,
+                        request.source_lang.code(),
+                        request.target_lang.code(),
+                        threshold,
+                    )
+                    .map(|found| Translation {
+                        text: found.text,
+                        detected_source_lang: found.detected_source_lang,
+                    })
+            })
+            .collect();
+        let missing: Vec<_> = request
+            .text
+            .iter()
+            .zip(&translations)
+            .filter(|(_, translation)| translation.is_none())
+            .map(|(text, _)| text.clone())
+            .collect();
+        if missing.is_empty() {
+            return Ok(Response {
+                translations: translations.into_iter().flatten().collect(),
+                input_tokens: 0,
+                output_tokens: 0,
+            });
+        }
+        let response = self
+            .translate(
+                engine,
+                &Request {
+                    text: missing,
+                    source_lang: request.source_lang.clone(),
+                    target_lang: request.target_lang.clone(),
+                    num_beams: request.num_beams,
+                    split_sentences: request.split_sentences,
+                },
+            )
+            .await?;
+        // translations are matched to their texts by position
+        let expected = translations.iter().filter(|slot| slot.is_none()).count();
+        if response.translations.len() != expected {
+            return Err(Error::TranslationCount {
+                expected,
+                got: response.translations.len(),
+            });
+        }
+        let mut fresh = response.translations.into_iter();
+        for (text, slot) in request.text.iter().zip(translations.iter_mut()) {
+            if slot.is_none() {
+                if let Some(translation) = fresh.next() {
+                    memory.insert(
+                        text,
+                        request.source_lang.code(),
+                        request.target_lang.code(),
+                        &translation,
+                    );
+                    *slot = Some(translation);
+                }
+            }
+        }
+        Ok(Response {
+            translations: translations.into_iter().flatten().collect(),
+            input_tokens: response.input_tokens,
+            output_tokens: response.output_tokens,
+        })
+    }
+}
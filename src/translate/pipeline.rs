@@ -0,0 +1,246 @@
+//! Provides pre- and post-processing stages applied around translation
+
+use crate::{validation::ValidationError, TextSynthClient};
+
+use super::{Engine, Error, Request, Response};
+
+/// Per text state shared by the stages of a pipeline
+#[derive(Debug, Default)]
+pub struct Context {
+    /// Text as it was before pre-processing.
+    pub source: String,
+    /// Masked spans as (placeholder, original) pairs. They are restored in the
+    /// translated text before the post-processors run.
+    pub masked: Vec<(String, String)>,
+}
+
+impl Context {
+    /// Create the context for `source`
+    pub fn new(source: impl Into<String>) -> Self {
+        Context {
+            source: source.into(),
+            masked: Vec::new(),
+        }
+    }
+
+    /// Hide `original` from the translation engine, returning the placeholder
+    /// to put in its place.
+    ///
+    /// Placeholders are numbers in brackets, like `[0]`, skipping those
+    /// already in the source text so that they are restored in place of the
+    /// masked span only.
+    pub fn mask(&mut self, original: &str) -> String {
+        let mut index = self.masked.len();
+        let placeholder = loop {
+            let placeholder = format!("[{}]", index);
+            let taken = self.source.contains(&placeholder)
+                || self.masked.iter().any(|(masked, _)| *masked == placeholder);
+            if !taken {
+                break placeholder;
+            }
+            index += 1;
+        };
+        self.masked
+            .push((placeholder.clone(), original.to_string()));
+        placeholder
+    }
+
+    /// Put the masked spans back into `text`
+    pub fn unmask(&self, text: String) -> String {
+        self.masked
+            .iter()
+            .fold(text, |text, (placeholder, original)| {
+                text.replacen(placeholder, original, 1)
+            })
+    }
+}
+
+/// Stage transforming a text before it is translated
+pub trait PreProcessor {
+    /// Transform `text` before translation.
+    fn pre_process(&self, text: String, context: &mut Context) -> String;
+}
+
+/// Stage transforming a translated text
+pub trait PostProcessor {
+    /// Transform the translated `text`.
+    fn post_process(&self, text: String, context: &Context) -> String;
+}
+
+/// Chain of pre- and post-processors, each run in the order they were added
+#[derive(Default)]
+pub struct Pipeline {
+    pre: Vec<Box<dyn PreProcessor + Send + Sync>>,
+    post: Vec<Box<dyn PostProcessor + Send + Sync>>,
+}
+
+impl Pipeline {
+    /// Create an empty pipeline
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a pre-processing stage
+    pub fn pre(mut self, stage: impl PreProcessor + Send + Sync + 'static) -> Self {
+        self.pre.push(Box::new(stage));
+        self
+    }
+
+    /// Append a post-processing stage
+    pub fn post(mut self, stage: impl PostProcessor + Send + Sync + 'static) -> Self {
+        self.post.push(Box::new(stage));
+        self
+    }
+
+    /// Run the pre-processors on `text`
+    pub fn pre_process(&self, text: String, context: &mut Context) -> String {
+        self.pre
+            .iter()
+            .fold(text, |text, stage| stage.pre_process(text, context))
+    }
+
+    /// Restore masked spans and run the post-processors on `text`
+    pub fn post_process(&self, text: String, context: &Context) -> String {
+        self.post.iter().fold(context.unmask(text), |text, stage| {
+            stage.post_process(text, context)
+        })
+    }
+}
+
+/// Masks spans between `open` and `close` (`{` and `}` by default) so
+/// template placeholders pass through translation untouched
+#[derive(Debug, Clone)]
+pub struct MaskPlaceholders {
+    open: String,
+    close: String,
+}
+
+impl MaskPlaceholders {
+    /// Mask spans between `open` and `close`, failing if either delimiter is
+    /// empty
+    pub fn new(open: &str, close: &str) -> Result<Self, ValidationError> {
+        if open.is_empty() {
+            return Err(ValidationError::invalid("open", "not empty", open));
+        }
+        if close.is_empty() {
+            return Err(ValidationError::invalid("close", "not empty", close));
+        }
+        Ok(MaskPlaceholders {
+            open: open.to_string(),
+            close: close.to_string(),
+        })
+    }
+
+    /// Delimiter opening a span
+    pub fn open(&self) -> &str {
+        &self.open
+    }
+
+    /// Delimiter closing a span
+    pub fn close(&self) -> &str {
+        &self.close
+    }
+}
+
+impl Default for MaskPlaceholders {
+    fn default() -> Self {
+        MaskPlaceholders {
+            open: "{".to_string(),
+            close: "}".to_string(),
+        }
+    }
+}
+
+impl PreProcessor for MaskPlaceholders {
+    fn pre_process(&self, text: String, context: &mut Context) -> String {
+        let mut masked = String::with_capacity(text.len());
+        let mut rest = text.as_str();
+        while let Some(start) = rest.find(&self.open) {
+            let inner = start + self.open.len();
+            let end = match rest[inner..].find(&self.close) {
+                Some(end) => inner + end + self.close.len(),
+                None => break,
+            };
+            masked.push_str(&rest[..start]);
+            masked.push_str(&context.mask(&rest[start..end]));
+            rest = &rest[end..];
+        }
+        masked.push_str(rest);
+        masked
+    }
+}
+
+/// Makes the first letter of the translation upper or lower case, following
+/// the first letter of the source text
+#[derive(Debug, Clone, Default)]
+pub struct MatchInitialCase;
+
+impl PostProcessor for MatchInitialCase {
+    fn post_process(&self, text: String, context: &Context) -> String {
+        let upper = match context.source.chars().find(|c| c.is_alphabetic()) {
+            Some(first) if first.is_uppercase() => true,
+            Some(first) if first.is_lowercase() => false,
+            _ => return text,
+        };
+        match text.char_indices().find(|(_, c)| c.is_alphabetic()) {
+            Some((i, first)) => {
+                let mut cased = text[..i].to_string();
+                if upper {
+                    cased.extend(first.to_uppercase());
+                } else {
+                    cased.extend(first.to_lowercase());
+                }
+                cased.push_str(&text[i + first.len_utf8()..]);
+                cased
+            }
+            None => text,
+        }
+    }
+}
+
+impl TextSynthClient {
+    /// Perform a translation request, running every text through the
+    /// pre-processors of `pipeline` and every translation through its
+    /// post-processors.
+    pub async fn translate_with_pipeline(
+        &self,
+        engine: &Engine,
+        request: &Request,
+        pipeline: &Pipeline,
+    ) -> Result<Response, Error> {
+        let mut contexts = Vec::with_capacity(request.text.len());
+        let text = request
+            .text
+            .iter()
+            .map(|text| {
+                let mut context = Context::new(&**text);
+                let text = pipeline.pre_process(text.to_string(), &mut context);
+                contexts.push(context);
+                text.into()
+            })
+            .collect();
+        let mut response = self
+            .translate(
+                engine,
+                &Request {
+                    text,
+                    source_lang: request.source_lang.clone(),
+                    target_lang: request.target_lang.clone(),
+                    num_beams: request.num_beams,
+                    split_sentences: request.split_sentences,
+                },
+            )
+            .await?;
+        if response.translations.len() != contexts.len() {
+            return Err(Error::TranslationCount {
+                expected: contexts.len(),
+                got: response.translations.len(),
+            });
+        }
+        for (translation, context) in response.translations.iter_mut().zip(&contexts) {
+            let text = std::mem::take(&mut translation.text);
+            translation.text = pipeline.post_process(text, context);
+        }
+        Ok(response)
+    }
+}
@@ -0,0 +1,159 @@
+//! Provides replacing how requests are sent, e.g. to test offline
+//!
+//! Every request of a [`TextSynthClient`](crate::TextSynthClient) is sent
+//! through its [`Transport`], the http client by default. A
+//! [`MockTransport`] answers requests with canned json instead, without a
+//! server or spending credits.
+//!
+//! An [`HttpClient`] sends requests given as [`http`] types instead, so the
+//! client can run on an HTTP client and runtime other than reqwest and tokio,
+//! wrapped in an [`HttpClientTransport`]. Retries, rate limits, stream read
+//! timeouts and prefetching wait on tokio timers and tasks, so they must stay
+//! disabled without a tokio runtime.
+
+use std::{fmt, future::Future, pin::Pin};
+
+#[cfg(feature = "streaming")]
+use bytes::Bytes;
+#[cfg(feature = "streaming")]
+use futures::{stream, Stream};
+use reqwest::{Client, Request, Response, StatusCode};
+
+/// Future of the response of a [`Transport`]
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Response, reqwest::Error>> + Send + 'a>>;
+
+/// Sends the requests of a client
+pub trait Transport: fmt::Debug + Send + Sync {
+    /// Send `request`, returning the response of the api
+    fn execute(&self, request: Request) -> TransportFuture<'_>;
+}
+
+impl Transport for Client {
+    fn execute(&self, request: Request) -> TransportFuture<'_> {
+        Box::pin(Client::execute(self, request))
+    }
+}
+
+type Handler = dyn Fn(&Request) -> (StatusCode, String) + Send + Sync;
+
+/// Answers every request with the status and json body returned by a handler
+pub struct MockTransport {
+    handler: Box<Handler>,
+}
+
+impl MockTransport {
+    /// Answer requests with the status and body returned by `handler`
+    pub fn new(handler: impl Fn(&Request) -> (StatusCode, String) + Send + Sync + 'static) -> Self {
+        MockTransport {
+            handler: Box::new(handler),
+        }
+    }
+
+    /// Answer every request successfully with `body`
+    pub fn json(body: impl Into<String>) -> Self {
+        let body = body.into();
+        MockTransport::new(move |_| (StatusCode::OK, body.clone()))
+    }
+}
+
+impl fmt::Debug for MockTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockTransport").finish_non_exhaustive()
+    }
+}
+
+impl Transport for MockTransport {
+    fn execute(&self, request: Request) -> TransportFuture<'_> {
+        let (status, body) = (self.handler)(&request);
+        let response = http::Response::builder()
+            .status(status)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .expect("canned responses are valid");
+        Box::pin(async move { Ok(Response::from(response)) })
+    }
+}
+
+/// Error of an [`HttpClient`]
+#[cfg(feature = "streaming")]
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Body of the response of an [`HttpClient`], streamed in chunks
+#[cfg(feature = "streaming")]
+pub type HttpBody = Pin<Box<dyn Stream<Item = Result<Bytes, BoxError>> + Send + Sync>>;
+
+/// Future of the response of an [`HttpClient`]
+#[cfg(feature = "streaming")]
+pub type HttpFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<http::Response<HttpBody>, BoxError>> + Send + 'a>>;
+
+/// Sends requests given as [`http`] types, e.g. with the HTTP client of
+/// another async runtime
+#[cfg(feature = "streaming")]
+pub trait HttpClient: fmt::Debug + Send + Sync {
+    /// Send `request`, returning the response with its streamed body
+    fn send(&self, request: http::Request<Vec<u8>>) -> HttpFuture<'_>;
+}
+
+/// Transport sending the requests with an [`HttpClient`]
+///
+/// Errors of the client are reported as body errors of reqwest, with the
+/// error of the client as their source. Requests with streamed bodies, like
+/// transcripts of audio files on disk, fail as their body isn't available in
+/// memory.
+#[cfg(feature = "streaming")]
+#[derive(Debug)]
+pub struct HttpClientTransport<C> {
+    client: C,
+}
+
+#[cfg(feature = "streaming")]
+impl<C: HttpClient> HttpClientTransport<C> {
+    /// Send requests with `client`
+    pub fn new(client: C) -> Self {
+        HttpClientTransport { client }
+    }
+}
+
+/// `error` as a [`reqwest::Error`], which can only be made by reqwest
+#[cfg(feature = "streaming")]
+pub(crate) async fn reqwest_error(error: BoxError) -> reqwest::Error {
+    let body = reqwest::Body::wrap_stream(stream::iter([Err::<Bytes, _>(error)]));
+    Response::from(http::Response::new(body))
+        .bytes()
+        .await
+        .expect_err("the body fails")
+}
+
+#[cfg(feature = "streaming")]
+impl<C: HttpClient> Transport for HttpClientTransport<C> {
+    fn execute(&self, request: Request) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let body = match request.body() {
+                None => Vec::new(),
+                Some(body) => match body.as_bytes() {
+                    Some(body) => body.to_vec(),
+                    None => {
+                        let error = "streamed request bodies can't be sent by an HttpClient";
+                        return Err(reqwest_error(error.into()).await);
+                    }
+                },
+            };
+            let mut builder = http::Request::builder()
+                .method(request.method().clone())
+                .uri(request.url().as_str());
+            for (name, value) in request.headers() {
+                builder = builder.header(name, value);
+            }
+            let sent = match builder.body(body) {
+                Ok(sent) => sent,
+                Err(error) => return Err(reqwest_error(error.into()).await),
+            };
+            match self.client.send(sent).await {
+                Ok(response) => Ok(Response::from(response.map(reqwest::Body::wrap_stream))),
+                Err(error) => Err(reqwest_error(error).await),
+            }
+        })
+    }
+}
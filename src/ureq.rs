@@ -0,0 +1,380 @@
+//! Provides a synchronous client on ureq, for small tools
+//!
+//! [`TextSynthUreqClient`] sends requests with ureq on the calling thread,
+//! without an async runtime. It supports every endpoint of the api, streamed
+//! completions and chats as iterators over their chunks:
+//!
+//! ```no_run
+//! # use elikoga_textsynth::{completions::{Engine, Request}, ureq::TextSynthUreqClient};
+//! let client = TextSynthUreqClient::new("api key");
+//! let request = Request::builder("The capital of France is")
+//!     .build()
+//!     .expect("completion request should build");
+//! for chunk in client.completions(&Engine::GPTJ6B, &request)? {
+//!     print!("{}", chunk?.text.concat());
+//! }
+//! # Ok::<_, elikoga_textsynth::completions::Error>(())
+//! ```
+//!
+//! Errors of ureq are reported as body errors of reqwest, with the error of
+//! ureq as their source, as for an
+//! [`HttpClient`](crate::transport::HttpClient).
+
+use std::{
+    fmt,
+    io::{BufReader, Read},
+};
+
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE},
+    StatusCode,
+};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    chat,
+    completions::{self, logprob, read_values, ResponseChunk},
+    credits, error_answer, new_request_id, normalize_endpoint, parse_body, text_to_image, tokenize,
+    transcript, translate,
+    transport::{reqwest_error, BoxError},
+    BuildError, IsEngine, ResponseError, DEFAULT_USER_AGENT, REQUEST_ID_HEADER,
+};
+
+/// Synchronous TextSynth API Client on ureq
+///
+/// Its `Debug` output shows the endpoint but never the api key.
+#[derive(Clone)]
+pub struct TextSynthUreqClient {
+    /// endpoint of TextSynth API
+    base_url: String,
+    /// `Authorization` header sent with every request
+    authorization: String,
+    /// Agent making requests to the TextSynth API
+    agent: ::ureq::Agent,
+}
+
+impl fmt::Debug for TextSynthUreqClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TextSynthUreqClient")
+            .field("base_url", &self.base_url)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Status, headers and unread body of a successful answer
+struct Answer {
+    status: StatusCode,
+    headers: HeaderMap,
+    response: ::ureq::Response,
+}
+
+impl Answer {
+    /// Parse the json body of the answer
+    fn parse<T, E>(self) -> Result<T, E>
+    where
+        T: DeserializeOwned,
+        E: ResponseError,
+    {
+        let mut body = Vec::new();
+        self.response
+            .into_reader()
+            .read_to_end(&mut body)
+            .map_err(ureq_error)?;
+        Ok(parse_body(self.status, &self.headers, &body)?)
+    }
+
+    /// Parse the json values streamed in the body of the answer
+    fn values<T: DeserializeOwned + Send + 'static>(
+        self,
+    ) -> serde_json::Result<impl Iterator<Item = serde_json::Result<T>>> {
+        let event_stream = self
+            .response
+            .content_type()
+            .starts_with("text/event-stream");
+        read_values(BufReader::new(self.response.into_reader()), event_stream)
+    }
+}
+
+/// `error` of ureq as a [`reqwest::Error`]
+fn ureq_error(error: impl Into<BoxError>) -> reqwest::Error {
+    futures::executor::block_on(reqwest_error(error.into()))
+}
+
+/// The headers of `response` valid in [`http`]
+fn headers(response: &::ureq::Response) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for name in response.headers_names() {
+        let Ok(name) = HeaderName::from_bytes(name.as_bytes()) else {
+            continue;
+        };
+        for value in response.all(name.as_str()) {
+            if let Ok(value) = HeaderValue::from_str(value) {
+                headers.append(name.clone(), value);
+            }
+        }
+    }
+    headers
+}
+
+/// `name` escaped for a quoted multipart header parameter
+fn quoted(name: &str) -> String {
+    name.replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+impl TextSynthUreqClient {
+    /// Create a new ureq TextSynth API Client with a custom endpoint
+    ///
+    /// # Panics
+    ///
+    /// Panics if the client can't be built, see
+    /// [`TextSynthUreqClient::try_new_with_endpoint`].
+    pub fn new_with_endpoint(api_key: &str, endpoint: &str) -> Self {
+        Self::try_new_with_endpoint(api_key, endpoint)
+            .expect("failed to build ureq TextSynth client")
+    }
+
+    /// Create a new ureq TextSynth API Client
+    ///
+    /// # Panics
+    ///
+    /// Panics if the client can't be built, see
+    /// [`TextSynthUreqClient::try_new`].
+    pub fn new(api_key: &str) -> Self {
+        Self::new_with_endpoint(api_key, "https://api.textsynth.com/v1")
+    }
+
+    /// Create a new ureq TextSynth API Client with a custom endpoint,
+    /// failing if the endpoint isn't an http or https url or the api key
+    /// isn't valid in a header
+    pub fn try_new_with_endpoint(api_key: &str, endpoint: &str) -> Result<Self, BuildError> {
+        let authorization = format!("Bearer {}", api_key);
+        HeaderValue::from_str(&authorization)?;
+        Ok(TextSynthUreqClient {
+            base_url: normalize_endpoint(endpoint)?,
+            authorization,
+            agent: ::ureq::AgentBuilder::new()
+                .user_agent(DEFAULT_USER_AGENT)
+                .build(),
+        })
+    }
+
+    /// Create a new ureq TextSynth API Client, failing if the api key isn't
+    /// valid in a header
+    pub fn try_new(api_key: &str) -> Result<Self, BuildError> {
+        Self::try_new_with_endpoint(api_key, "https://api.textsynth.com/v1")
+    }
+
+    /// Send requests with `agent`, e.g. one with timeouts or a proxy
+    pub fn with_agent(mut self, agent: ::ureq::Agent) -> Self {
+        self.agent = agent;
+        self
+    }
+
+    /// Send `request` with `body`, failing with the error answer of the api
+    /// if the response isn't successful
+    fn send<E>(&self, request: ::ureq::Request, body: Option<&[u8]>) -> Result<Answer, E>
+    where
+        E: ResponseError,
+    {
+        let request_id = new_request_id();
+        let request = request.set("authorization", &self.authorization).set(
+            REQUEST_ID_HEADER,
+            request_id.to_str().expect("request ids are hex digits"),
+        );
+        let response = match body {
+            Some(body) => request.send_bytes(body),
+            None => request.call(),
+        };
+        let response = match response {
+            Ok(response) | Err(::ureq::Error::Status(_, response)) => response,
+            Err(error) => return Err(ureq_error(error).into()),
+        };
+        let status =
+            StatusCode::from_u16(response.status()).expect("ureq only parses valid statuses");
+        let mut headers = headers(&response);
+        headers.entry(REQUEST_ID_HEADER).or_insert(request_id);
+        if status.is_success() {
+            return Ok(Answer {
+                status,
+                headers,
+                response,
+            });
+        }
+        let body = response.into_string().map_err(ureq_error)?;
+        Err(error_answer(status, &headers, body))
+    }
+
+    /// Post the json `body` to `url`
+    fn post<E>(&self, url: &str, body: &str) -> Result<Answer, E>
+    where
+        E: ResponseError,
+    {
+        let request = self
+            .agent
+            .post(url)
+            .set(CONTENT_TYPE.as_str(), "application/json");
+        self.send(request, Some(body.as_bytes()))
+    }
+
+    /// `request` as json, without its `stream` option
+    fn without_stream(request: &impl serde::Serialize) -> serde_json::Result<String> {
+        let mut request_json = serde_json::to_value(request)?;
+        if let Some(request_json) = request_json.as_object_mut() {
+            request_json.remove("stream");
+        }
+        serde_json::to_string(&request_json)
+    }
+
+    /// Perform a completion request and wait for the complete answer. The
+    /// `stream` option of the request is ignored.
+    pub fn complete(
+        &self,
+        engine: &completions::Engine,
+        request: &completions::Request,
+    ) -> Result<ResponseChunk, completions::Error> {
+        let url = format!("{}/engines/{}/completions", self.base_url, engine);
+        self.post::<completions::Error>(&url, &Self::without_stream(request)?)?
+            .parse()
+    }
+
+    /// Perform a completion request, iterating over the chunks as they are
+    /// streamed
+    pub fn completions(
+        &self,
+        engine: &completions::Engine,
+        request: &completions::Request,
+    ) -> Result<impl Iterator<Item = Result<ResponseChunk, completions::Error>>, completions::Error>
+    {
+        let url = format!("{}/engines/{}/completions", self.base_url, engine);
+        let answer = self.post::<completions::Error>(&url, &serde_json::to_string(request)?)?;
+        Ok(answer
+            .values()?
+            .map(|chunk| chunk.map_err(completions::Error::from)))
+    }
+
+    /// Perform a chat request and wait for the complete answer. The `stream`
+    /// option of the request is ignored.
+    pub fn chat(
+        &self,
+        engine: &chat::Engine,
+        request: &chat::Request,
+    ) -> Result<completions::ResponseChunk, chat::Error> {
+        let url = format!("{}/engines/{}/chat", self.base_url, engine);
+        self.post::<chat::Error>(&url, &Self::without_stream(request)?)?
+            .parse()
+    }
+
+    /// Perform a chat request, iterating over the chunks as they are
+    /// streamed
+    pub fn chat_stream(
+        &self,
+        engine: &chat::Engine,
+        request: &chat::Request,
+    ) -> Result<impl Iterator<Item = Result<completions::ResponseChunk, chat::Error>>, chat::Error>
+    {
+        let mut request_json = serde_json::to_value(request)?;
+        if let Some(request_json) = request_json.as_object_mut() {
+            request_json.insert("stream".to_string(), true.into());
+        }
+        let url = format!("{}/engines/{}/chat", self.base_url, engine);
+        let answer = self.post::<chat::Error>(&url, &serde_json::to_string(&request_json)?)?;
+        Ok(answer
+            .values()?
+            .map(|chunk| chunk.map_err(chat::Error::from)))
+    }
+
+    /// Perform a translation request
+    pub fn translate(
+        &self,
+        engine: &translate::Engine,
+        request: &translate::Request,
+    ) -> Result<translate::Response, translate::Error> {
+        let url = format!("{}/engines/{}/translate", self.base_url, engine);
+        self.post::<translate::Error>(&url, &serde_json::to_string(request)?)?
+            .parse()
+    }
+
+    /// Perform a tokenization request
+    pub fn tokenize(
+        &self,
+        engine: &impl IsEngine,
+        request: &tokenize::Request,
+    ) -> Result<tokenize::Response, tokenize::Error> {
+        let url = format!("{}/engines/{}/tokenize", self.base_url, engine);
+        self.post::<tokenize::Error>(&url, &serde_json::to_string(request)?)?
+            .parse()
+    }
+
+    /// Perform a logprob request
+    pub fn logprob(
+        &self,
+        engine: &completions::Engine,
+        request: &logprob::Request,
+    ) -> Result<logprob::Response, logprob::Error> {
+        let url = format!("{}/engines/{}/logprob", self.base_url, engine);
+        self.post::<logprob::Error>(&url, &serde_json::to_string(request)?)?
+            .parse()
+    }
+
+    /// Perform a text to image request
+    pub fn text_to_image(
+        &self,
+        engine: &text_to_image::Engine,
+        request: &text_to_image::Request,
+    ) -> Result<text_to_image::Response, text_to_image::Error> {
+        let url = format!("{}/engines/{}/text_to_image", self.base_url, engine);
+        self.post::<text_to_image::Error>(&url, &serde_json::to_string(request)?)?
+            .parse()
+    }
+
+    /// Perform a transcript request, uploading the audio file
+    ///
+    /// The whole audio is read into memory before it is uploaded.
+    pub fn transcript(
+        &self,
+        engine: &transcript::Engine,
+        request: &transcript::Request,
+    ) -> Result<transcript::Response, transcript::Error> {
+        let request_json = serde_json::to_string(request)?;
+        let (file_name, format) = request.upload_name();
+        let audio = request.audio().read_blocking()?;
+        let boundary = format!(
+            "textsynth-{}",
+            new_request_id()
+                .to_str()
+                .expect("request ids are hex digits")
+        );
+        let mut body = format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"json\"\r\n\r\n{}\r\n",
+            boundary, request_json
+        );
+        body += &format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n",
+            boundary,
+            quoted(&file_name)
+        );
+        if let Some(format) = format {
+            body += &format!("Content-Type: {}\r\n", format.mime());
+        }
+        body += "\r\n";
+        let mut body = body.into_bytes();
+        body.extend_from_slice(&audio);
+        body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+        let url = format!("{}/engines/{}/transcript", self.base_url, engine);
+        let request = self.agent.post(&url).set(
+            CONTENT_TYPE.as_str(),
+            &format!("multipart/form-data; boundary={}", boundary),
+        );
+        self.send::<transcript::Error>(request, Some(&body))?
+            .parse()
+    }
+
+    /// Get the remaining credits of the account
+    pub fn credits(&self) -> Result<credits::Response, credits::Error> {
+        let url = format!("{}/credits", self.base_url);
+        self.send::<credits::Error>(self.agent.get(&url), None)?
+            .parse()
+    }
+}
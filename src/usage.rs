@@ -0,0 +1,171 @@
+//! Provides recording the usage of the api
+//!
+//! A [`UsageRecorder`] given to
+//! [`TextSynthClientBuilder::usage_recorder`](crate::TextSynthClientBuilder::usage_recorder)
+//! counts the requests, errors and tokens of the client per engine, e.g. to
+//! attribute costs to the features of an application using one client each.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Upper bounds of the buckets of [`LatencyHistogram`]
+pub const LATENCY_BUCKETS: &[Duration] = &[
+    Duration::from_millis(10),
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(250),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_millis(2500),
+    Duration::from_secs(5),
+    Duration::from_secs(10),
+    Duration::from_secs(30),
+];
+
+/// Distribution of the latencies of requests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    /// Number of requests per bucket of [`LATENCY_BUCKETS`], followed by the
+    /// number of requests slower than the last bucket.
+    pub counts: Vec<u64>,
+    /// Sum of all latencies.
+    pub total: Duration,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        LatencyHistogram {
+            counts: vec![0; LATENCY_BUCKETS.len() + 1],
+            total: Duration::ZERO,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    fn record(&mut self, latency: Duration) {
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| latency <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.counts[bucket] += 1;
+        self.total += latency;
+    }
+
+    /// Number of recorded requests
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Average latency, zero if no request was recorded
+    pub fn mean(&self) -> Duration {
+        match self.count() {
+            0 => Duration::ZERO,
+            count => self.total / count as u32,
+        }
+    }
+}
+
+/// Usage of a single engine
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EngineUsage {
+    /// Number of requests sent.
+    pub requests: u64,
+    /// Number of requests that failed or were answered with an error.
+    pub errors: u64,
+    /// Number of input tokens reported by the api.
+    pub input_tokens: u64,
+    /// Number of generated tokens reported by the api.
+    pub output_tokens: u64,
+    /// Latencies until the answer started.
+    pub latency: LatencyHistogram,
+}
+
+impl EngineUsage {
+    fn add(&mut self, other: &EngineUsage) {
+        self.requests += other.requests;
+        self.errors += other.errors;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        for (count, other) in self.latency.counts.iter_mut().zip(&other.latency.counts) {
+            *count += other;
+        }
+        self.latency.total += other.latency.total;
+    }
+}
+
+/// Usage recorded up to one point in time
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// Usage per engine api name. Requests not made to an engine, e.g. of the
+    /// credits api, are recorded under the empty name.
+    pub engines: HashMap<String, EngineUsage>,
+}
+
+impl Usage {
+    /// Usage of all engines together
+    pub fn total(&self) -> EngineUsage {
+        let mut total = EngineUsage::default();
+        for usage in self.engines.values() {
+            total.add(usage);
+        }
+        total
+    }
+}
+
+/// Handle recording the usage of a client, shared by its clones
+#[derive(Debug, Clone, Default)]
+pub struct UsageRecorder {
+    usage: Arc<Mutex<Usage>>,
+}
+
+impl UsageRecorder {
+    /// A recorder with nothing recorded yet
+    pub fn new() -> Self {
+        UsageRecorder::default()
+    }
+
+    /// The usage recorded so far
+    pub fn snapshot(&self) -> Usage {
+        self.usage.lock().expect("usage lock poisoned").clone()
+    }
+
+    /// Forget the usage recorded so far
+    pub fn reset(&self) {
+        *self.usage.lock().expect("usage lock poisoned") = Usage::default();
+    }
+
+    fn update(&self, engine: Option<&str>, update: impl FnOnce(&mut EngineUsage)) {
+        let mut usage = self.usage.lock().expect("usage lock poisoned");
+        update(
+            usage
+                .engines
+                .entry(engine.unwrap_or_default().to_string())
+                .or_default(),
+        );
+    }
+
+    pub(crate) fn record_request(&self, engine: Option<&str>, latency: Duration, success: bool) {
+        self.update(engine, |usage| {
+            usage.requests += 1;
+            if !success {
+                usage.errors += 1;
+            }
+            usage.latency.record(latency);
+        });
+    }
+
+    pub(crate) fn record_tokens(
+        &self,
+        engine: &str,
+        input_tokens: Option<u32>,
+        output_tokens: Option<u32>,
+    ) {
+        self.update(Some(engine), |usage| {
+            usage.input_tokens += u64::from(input_tokens.unwrap_or(0));
+            usage.output_tokens += u64::from(output_tokens.unwrap_or(0));
+        });
+    }
+}
@@ -0,0 +1,55 @@
+//! Provides the error of building an invalid request
+//!
+//! Every request builder fails with a [`ValidationError`] naming the
+//! parameter at fault, so applications can point users at the field to fix
+//! instead of showing a message.
+
+use derive_builder::UninitializedFieldError;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+/// Error of building a request
+pub enum ValidationError {
+    /// A required parameter wasn't set
+    #[error("{0} must be set")]
+    UninitializedField(&'static str),
+    /// A parameter was set to a value violating a constraint
+    #[error("{field} must be {constraint}, got {value}")]
+    Invalid {
+        /// Name of the parameter, as in the api
+        field: &'static str,
+        /// Constraint the value violates, e.g. "between 1 and 16"
+        constraint: &'static str,
+        /// The value provided
+        value: String,
+    },
+}
+
+impl ValidationError {
+    /// `value` of `field` violates `constraint`
+    pub(crate) fn invalid(
+        field: &'static str,
+        constraint: &'static str,
+        value: impl ToString,
+    ) -> Self {
+        ValidationError::Invalid {
+            field,
+            constraint,
+            value: value.to_string(),
+        }
+    }
+
+    /// Name of the parameter at fault
+    pub fn field(&self) -> &'static str {
+        match self {
+            ValidationError::UninitializedField(field) => field,
+            ValidationError::Invalid { field, .. } => field,
+        }
+    }
+}
+
+impl From<UninitializedFieldError> for ValidationError {
+    fn from(error: UninitializedFieldError) -> Self {
+        ValidationError::UninitializedField(error.field_name())
+    }
+}
@@ -0,0 +1,254 @@
+//! Provides recording api interactions and replaying them in tests
+//!
+//! A [`Cassette`] is a [`Transport`] that sends requests to the api and
+//! records every interaction to a json fixture file, or answers requests from
+//! a fixture recorded before. Tests recorded once with an api key then run
+//! deterministically without one:
+//!
+//! ```no_run
+//! # use elikoga_textsynth::{vcr::{Cassette, Mode}, TextSynthClient};
+//! let api_key = std::env::var("TEXT_SYNTH_API_KEY").unwrap_or_default();
+//! let cassette = Cassette::new("tests/fixtures/complete.json", Mode::Auto)
+//!     .expect("cassette should load");
+//! let client = TextSynthClient::builder(&api_key)
+//!     .transport(cassette)
+//!     .build();
+//! ```
+//!
+//! Interactions are matched by method, path and body, each replayed once in
+//! the order recorded. Request headers, and so the api key, aren't recorded.
+//! Requests recorded while the fixture can't be written fail with the io
+//! error as the source of a reqwest error.
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use reqwest::{Client, Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use crate::transport::{reqwest_error, Transport, TransportFuture};
+
+/// Whether a [`Cassette`] records or replays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Send requests to the api and record them, replacing the fixture.
+    Record,
+    /// Answer requests from the fixture only, never sending them.
+    Replay,
+    /// Replay if the fixture exists, record it otherwise.
+    Auto,
+}
+
+/// A recorded request and the response of the api
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Interaction {
+    /// The HTTP method.
+    pub method: String,
+    /// The path and query of the url, without the host so fixtures work
+    /// with any endpoint.
+    pub path: String,
+    /// The request body, empty for streamed bodies.
+    pub body: String,
+    /// The status of the response.
+    pub status: u16,
+    /// The headers of the response.
+    pub headers: Vec<(String, String)>,
+    /// The body of the response.
+    pub response: String,
+}
+
+impl Interaction {
+    /// Returns true if `request` is the request of the interaction
+    fn matches(&self, method: &str, path: &str, body: &str) -> bool {
+        self.method == method && self.path == path && self.body == body
+    }
+}
+
+/// Recorded interactions and which of them were replayed
+#[derive(Debug, Default)]
+struct Tape {
+    interactions: Vec<Interaction>,
+    replayed: Vec<bool>,
+}
+
+/// Transport recording interactions to a fixture file or replaying them
+pub struct Cassette {
+    path: PathBuf,
+    recording: bool,
+    transport: Box<dyn Transport>,
+    tape: Mutex<Tape>,
+}
+
+impl Cassette {
+    /// Record to or replay from the fixture at `path`, depending on `mode`
+    ///
+    /// Fails if the fixture is replayed but can't be read.
+    pub fn new(path: impl AsRef<Path>, mode: Mode) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let recording = match mode {
+            Mode::Record => true,
+            Mode::Replay => false,
+            Mode::Auto => !path.exists(),
+        };
+        let mut tape = Tape::default();
+        if !recording {
+            tape.interactions = serde_json::from_slice(&fs::read(&path)?)?;
+            tape.replayed = vec![false; tape.interactions.len()];
+        }
+        Ok(Cassette {
+            path,
+            recording,
+            transport: Box::new(Client::new()),
+            tape: Mutex::new(tape),
+        })
+    }
+
+    /// Send the requests to record with `transport` instead of a default
+    /// http client
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Box::new(transport);
+        self
+    }
+
+    /// Returns true if the cassette records rather than replays
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// The interactions recorded so far or loaded from the fixture
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.tape
+            .lock()
+            .expect("tape lock poisoned")
+            .interactions
+            .clone()
+    }
+
+    /// Answer from the first interaction of the fixture matching the request
+    /// not replayed yet, or with a 404 error answer if there is none
+    fn replay(&self, method: &str, path: &str, body: &str) -> http::Response<String> {
+        let mut tape = self.tape.lock().expect("tape lock poisoned");
+        let Tape {
+            interactions,
+            replayed,
+        } = &mut *tape;
+        let found = interactions
+            .iter()
+            .zip(replayed.iter_mut())
+            .find(|(interaction, replayed)| !**replayed && interaction.matches(method, path, body));
+        match found {
+            Some((interaction, replayed)) => {
+                *replayed = true;
+                let mut response = http::Response::builder().status(interaction.status);
+                for (name, value) in &interaction.headers {
+                    response = response.header(name, value);
+                }
+                response
+                    .body(interaction.response.clone())
+                    .expect("recorded responses are valid")
+            }
+            None => {
+                let error = format!(
+                    "no interaction recorded in {} for {} {}",
+                    self.path.display(),
+                    method,
+                    path
+                );
+                http::Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .body(serde_json::json!({ "error": error }).to_string())
+                    .expect("error answers are valid")
+            }
+        }
+    }
+
+    /// Record `interaction` and write the fixture
+    ///
+    /// The interaction is kept even if the fixture can't be written, so it
+    /// is part of the fixture written with the next one.
+    fn record(&self, interaction: Interaction) -> io::Result<()> {
+        let mut tape = self.tape.lock().expect("tape lock poisoned");
+        tape.interactions.push(interaction);
+        tape.replayed.push(true);
+        let fixture = serde_json::to_string_pretty(&tape.interactions)?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, fixture)
+    }
+}
+
+impl fmt::Debug for Cassette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cassette")
+            .field("path", &self.path)
+            .field("recording", &self.recording)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Transport for Cassette {
+    fn execute(&self, request: Request) -> TransportFuture<'_> {
+        let method = request.method().to_string();
+        let path = match request.url().query() {
+            Some(query) => format!("{}?{}", request.url().path(), query),
+            None => request.url().path().to_string(),
+        };
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(|body| String::from_utf8_lossy(body).into_owned())
+            .unwrap_or_default();
+        // multipart boundaries are random, so they would never match
+        let boundary = request
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .and_then(|content_type| content_type.split_once("boundary="))
+            .map(|(_, boundary)| boundary.to_string());
+        let body = match boundary {
+            Some(boundary) => body.replace(&boundary, "boundary"),
+            None => body,
+        };
+        if !self.recording {
+            let response = self.replay(&method, &path, &body);
+            return Box::pin(async move { Ok(Response::from(response)) });
+        }
+        Box::pin(async move {
+            let response = self.transport.execute(request).await?;
+            let status = response.status();
+            let headers: Vec<_> = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect();
+            let response = String::from_utf8_lossy(&response.bytes().await?).into_owned();
+            let recorded = self.record(Interaction {
+                method,
+                path,
+                body,
+                status: status.as_u16(),
+                headers: headers.clone(),
+                response: response.clone(),
+            });
+            if let Err(error) = recorded {
+                return Err(reqwest_error(error.into()).await);
+            }
+            let mut replayed = http::Response::builder().status(status);
+            for (name, value) in &headers {
+                replayed = replayed.header(name, value);
+            }
+            Ok(Response::from(
+                replayed
+                    .body(response)
+                    .expect("recorded responses are valid"),
+            ))
+        })
+    }
+}
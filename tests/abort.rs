@@ -0,0 +1,96 @@
+#![cfg(feature = "streaming")]
+
+mod common;
+
+use std::time::Duration;
+
+use common::{assert_closed, endpoint, serve_stream};
+use elikoga_textsynth::{completions, TextSynthClient};
+use futures::StreamExt;
+
+/// The single chunk streamed before the server waits for the client to close
+/// the connection
+const CHUNK: &[u8] = b"{\"text\":\" Paris\",\"reached_end\":false}\n\n";
+
+#[tokio::test]
+async fn abort() {
+    let (port, connection_closed) = serve_stream(CHUNK.to_vec(), true);
+    let client = TextSynthClient::new_with_endpoint("key", &endpoint(port));
+    let request = completions::RequestBuilder::default()
+        .prompt("The capital of France is")
+        .stream(true)
+        .build()
+        .expect("failed to build completion request");
+    let (mut stream, abort_handle) = client
+        .completions_abortable(&completions::Engine::GPTJ6B, &request)
+        .await
+        .expect("failed to complete");
+    let chunk = stream
+        .next()
+        .await
+        .expect("stream ended early")
+        .expect("failed to parse chunk");
+    assert_eq!(chunk.text, [" Paris"]);
+    abort_handle.abort();
+    assert!(stream.next().await.is_none());
+    assert_closed(connection_closed).await;
+}
+
+#[tokio::test]
+async fn drop_stream() {
+    let (port, connection_closed) = serve_stream(CHUNK.to_vec(), true);
+    let client = TextSynthClient::new_with_endpoint("key", &endpoint(port));
+    let request = completions::RequestBuilder::default()
+        .prompt("The capital of France is")
+        .stream(true)
+        .build()
+        .expect("failed to build completion request");
+    let mut stream = Box::pin(
+        client
+            .completions(&completions::Engine::GPTJ6B, &request)
+            .await
+            .expect("failed to complete"),
+    );
+    let chunk = stream
+        .next()
+        .await
+        .expect("stream ended early")
+        .expect("failed to parse chunk");
+    assert_eq!(chunk.text, [" Paris"]);
+    drop(stream);
+    assert_closed(connection_closed).await;
+}
+
+#[tokio::test]
+async fn timeout_closes_connection() {
+    let (port, connection_closed) = serve_stream(CHUNK.to_vec(), true);
+    let client = TextSynthClient::builder("key")
+        .endpoint(&endpoint(port))
+        .stream_read_timeout(Duration::from_millis(50))
+        .build();
+    let request = completions::RequestBuilder::default()
+        .prompt("The capital of France is")
+        .stream(true)
+        .build()
+        .expect("failed to build completion request");
+    let mut stream = Box::pin(
+        client
+            .completions(&completions::Engine::GPTJ6B, &request)
+            .await
+            .expect("failed to complete"),
+    );
+    stream
+        .next()
+        .await
+        .expect("stream ended early")
+        .expect("failed to parse chunk");
+    let error = stream
+        .next()
+        .await
+        .expect("stream ended without timing out")
+        .expect_err("no chunk arrives in time");
+    assert!(matches!(error, completions::Error::StreamTimeout(_)));
+    // the stream is kept, the connection must be closed anyway
+    assert_closed(connection_closed).await;
+    assert!(stream.next().await.is_none());
+}
@@ -0,0 +1,160 @@
+use elikoga_textsynth::{completions, tokenize, transport::MockTransport, Error, TextSynthClient};
+use reqwest::StatusCode;
+
+#[tokio::test]
+async fn api_error() {
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(|_| {
+            (
+                StatusCode::PAYMENT_REQUIRED,
+                r#"{"error":"not enough credits"}"#.to_string(),
+            )
+        }))
+        .build();
+    let error = client
+        .tokenize_str(&completions::Engine::GPTJ6B, "Hello")
+        .await
+        .expect_err("the api answered with an error");
+    match error {
+        tokenize::Error::ApiError(error) => {
+            assert_eq!(error.status.as_u16(), 402);
+            assert_eq!(error.message, "not enough credits");
+        }
+        error => panic!("unexpected error {:?}", error),
+    }
+
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(|_| {
+            (StatusCode::BAD_GATEWAY, "upstream unavailable".to_string())
+        }))
+        .build();
+    let error = client
+        .complete_str(&completions::Engine::GPTJ6B, "Hello", 4)
+        .await
+        .expect_err("the api answered with an error");
+    match error {
+        completions::Error::ApiError(error) => {
+            assert_eq!(error.status.as_u16(), 502);
+            assert_eq!(error.message, "upstream unavailable");
+        }
+        error => panic!("unexpected error {:?}", error),
+    }
+}
+
+#[tokio::test]
+async fn unified_error() {
+    async fn tokens(client: &TextSynthClient) -> Result<usize, Error> {
+        let engine: completions::Engine = "gptj_6B".parse()?;
+        let tokens = client.tokenize_str(&engine, "Hello").await?;
+        Ok(tokens.len())
+    }
+
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(|_| {
+            (
+                StatusCode::PAYMENT_REQUIRED,
+                r#"{"error":"not enough credits"}"#.to_string(),
+            )
+        }))
+        .build();
+    let error = tokens(&client)
+        .await
+        .expect_err("the api answered with an error");
+    assert!(matches!(error, Error::Tokenize(_)));
+    let api_error = error.api_error().expect("not an api error");
+    assert_eq!(api_error.status.as_u16(), 402);
+    assert_eq!(api_error.message, "not enough credits");
+}
+
+#[test]
+fn module_errors() {
+    use elikoga_textsynth::{interop::openai, translate::language::Language};
+
+    fn language(code: &str) -> Result<Language, Error> {
+        Ok(code.parse()?)
+    }
+    fn request() -> Result<completions::Request, Error> {
+        Ok(completions::RequestBuilder::default().build()?)
+    }
+    fn converted(request: openai::CompletionRequest) -> Result<completions::Request, Error> {
+        Ok(request.try_into()?)
+    }
+
+    assert!(matches!(language("english"), Err(Error::ParseLanguage(_))));
+    assert!(matches!(request(), Err(Error::Validation(_))));
+    let request = openai::CompletionRequest {
+        model: "gptj_6B".to_string(),
+        prompt: "Hello".to_string(),
+        top_p: Some(1.5),
+        ..openai::CompletionRequest::default()
+    };
+    assert!(matches!(converted(request), Err(Error::Conversion(_))));
+
+    #[cfg(feature = "local-tokenizer")]
+    {
+        use elikoga_textsynth::local_tokenizer::LocalTokenizer;
+
+        fn tokenizer(bytes: &[u8]) -> Result<LocalTokenizer, Error> {
+            Ok(LocalTokenizer::from_bytes(bytes)?)
+        }
+        assert!(matches!(
+            tokenizer(b"not a tokenizer"),
+            Err(Error::LocalTokenizer(_))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn auth_error() {
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(|_| {
+            (
+                StatusCode::UNAUTHORIZED,
+                r#"{"error":"invalid api key"}"#.to_string(),
+            )
+        }))
+        .build();
+    let error = client
+        .tokenize_str(&completions::Engine::GPTJ6B, "Hello")
+        .await
+        .expect_err("the api answered with an error");
+    match error {
+        tokenize::Error::Unauthorized(error) => assert_eq!(error.message, "invalid api key"),
+        error => panic!("unexpected error {:?}", error),
+    }
+
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(|_| {
+            (
+                StatusCode::FORBIDDEN,
+                r#"{"error":"engine not allowed"}"#.to_string(),
+            )
+        }))
+        .build();
+    let error = Error::from(
+        client
+            .complete_str(&completions::Engine::GPTJ6B, "Hello", 4)
+            .await
+            .expect_err("the api answered with an error"),
+    );
+    assert!(error.unauthorized().is_none());
+    let forbidden = error.forbidden().expect("not a forbidden answer");
+    assert_eq!(forbidden.message, "engine not allowed");
+}
+
+#[tokio::test]
+async fn http_error() {
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(|_| {
+            (StatusCode::OK, "<html>maintenance</html>".to_string())
+        }))
+        .build();
+    let error = client
+        .tokenize_str(&completions::Engine::GPTJ6B, "Hello")
+        .await
+        .map_err(Error::from)
+        .expect_err("the answer isn't json");
+    let http_error = error.http_error().expect("not an http error");
+    assert_eq!(http_error.status.as_u16(), 200);
+    assert_eq!(http_error.body, "<html>maintenance</html>");
+}
@@ -0,0 +1,20 @@
+#![cfg(feature = "proptest")]
+
+use elikoga_textsynth::{completions, translate};
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn arbitrary_completion_requests(request in any::<completions::Request>()) {
+        let json = serde_json::to_value(&request).expect("request should serialize");
+        prop_assert!(json["prompt"].is_string());
+        prop_assert!(json.get("n").is_none_or(|n| n.as_u64().is_some()));
+    }
+
+    #[test]
+    fn arbitrary_translation_requests(request in any::<translate::Request>()) {
+        let json = serde_json::to_value(&request).expect("request should serialize");
+        let texts = json["text"].as_array().expect("text should be an array");
+        prop_assert!((1..=64).contains(&texts.len()));
+    }
+}
@@ -0,0 +1,118 @@
+#![cfg(feature = "streaming")]
+
+mod common;
+
+use common::{assert_closed, endpoint, serve_stream};
+use elikoga_textsynth::{completions, TextSynthClient};
+use futures::StreamExt;
+
+fn request() -> completions::Request {
+    completions::RequestBuilder::default()
+        .prompt("The capital of France is")
+        .stream(true)
+        .build()
+        .expect("failed to build completion request")
+}
+
+#[tokio::test]
+async fn max_pending_bytes() {
+    let mut body = br#"{"text":""#.to_vec();
+    body.extend([b'x'; 1000]);
+    let (port, connection_closed) = serve_stream(body, true);
+    let client = TextSynthClient::builder("key")
+        .endpoint(&endpoint(port))
+        .stream_max_pending_bytes(100)
+        .build();
+    let mut stream = client
+        .completions(&completions::Engine::GPTJ6B, &request())
+        .await
+        .expect("failed to complete");
+    let error = stream
+        .next()
+        .await
+        .expect("stream ended without an error")
+        .expect_err("the chunk is too large");
+    assert!(matches!(
+        error,
+        completions::Error::PendingBytesExceeded(100)
+    ));
+    assert!(stream.next().await.is_none());
+    assert_closed(connection_closed).await;
+}
+
+#[tokio::test]
+async fn max_pending_bytes_complete_values() {
+    // a single read holding two values larger than the limit together
+    let body = (0..2)
+        .map(|index| {
+            format!(
+                "{{\"text\":\"{}{}\",\"reached_end\":{}}}\n\n",
+                index,
+                "x".repeat(40),
+                index == 1
+            )
+        })
+        .collect::<String>();
+    assert!(body.len() > 100);
+    let (port, _) = serve_stream(body.into_bytes(), false);
+    let client = TextSynthClient::builder("key")
+        .endpoint(&endpoint(port))
+        .stream_max_pending_bytes(100)
+        .build();
+    let texts: Vec<_> = client
+        .completions(&completions::Engine::GPTJ6B, &request())
+        .await
+        .expect("failed to complete")
+        .map(|chunk| chunk.expect("nothing is pending").text.concat())
+        .collect()
+        .await;
+    assert_eq!(texts.len(), 2);
+}
+
+#[tokio::test]
+async fn prefetch() {
+    let body = (0..3)
+        .map(|index| {
+            format!(
+                "{{\"text\":\"{}\",\"reached_end\":{}}}\n\n",
+                index,
+                index == 2
+            )
+        })
+        .collect::<String>();
+    let (port, _) = serve_stream(body.into_bytes(), false);
+    let client = TextSynthClient::builder("key")
+        .endpoint(&endpoint(port))
+        .stream_prefetch(2)
+        .build();
+    let texts: Vec<_> = client
+        .completions(&completions::Engine::GPTJ6B, &request())
+        .await
+        .expect("failed to complete")
+        .map(|chunk| chunk.expect("failed to parse chunk").text.concat())
+        .collect()
+        .await;
+    assert_eq!(texts, ["0", "1", "2"]);
+}
+
+#[tokio::test]
+async fn prefetch_drop() {
+    let body = b"{\"text\":\" Paris\",\"reached_end\":false}\n\n".to_vec();
+    let (port, connection_closed) = serve_stream(body, true);
+    let client = TextSynthClient::builder("key")
+        .endpoint(&endpoint(port))
+        .stream_prefetch(4)
+        .build();
+    let mut stream = client
+        .completions(&completions::Engine::GPTJ6B, &request())
+        .await
+        .expect("failed to complete");
+    let chunk = stream
+        .next()
+        .await
+        .expect("stream ended early")
+        .expect("failed to parse chunk");
+    assert_eq!(chunk.text, [" Paris"]);
+    drop(stream);
+    assert_closed(connection_closed).await;
+}
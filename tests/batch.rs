@@ -0,0 +1,102 @@
+#![cfg(feature = "batch")]
+
+use std::time::Duration;
+
+use elikoga_textsynth::batch;
+
+#[tokio::test]
+async fn batch() {
+    // later inputs finish first, results still come back in input order
+    let results = batch::run(0..5_u64, 5, |i| async move {
+        tokio::time::sleep(Duration::from_millis(50 - 10 * i)).await;
+        if i == 3 {
+            Err(format!("failed {}", i))
+        } else {
+            Ok(i * 2)
+        }
+    })
+    .await;
+    assert_eq!(results.len(), 5);
+    assert!(!results.is_all_ok());
+    assert_eq!(
+        results.successes().collect::<Vec<_>>(),
+        [(0, &0), (1, &2), (2, &4), (4, &8)]
+    );
+    assert_eq!(
+        results.errors().collect::<Vec<_>>(),
+        [(3, &"failed 3".to_string())]
+    );
+    assert_eq!(results.into_result(), Err("failed 3".to_string()));
+}
+
+#[tokio::test]
+async fn duplex() {
+    use futures::{SinkExt, StreamExt};
+
+    let (mut sink, results) = batch::duplex(2, 3, |i: u64| async move {
+        tokio::time::sleep(Duration::from_millis(50 - 10 * i)).await;
+        Ok::<_, String>(i * 2)
+    });
+    let producer = tokio::spawn(async move {
+        for i in 0..5 {
+            sink.send(i).await.expect("stream was dropped");
+        }
+    });
+    let results: Vec<_> = results.collect().await;
+    producer.await.expect("producer panicked");
+    assert_eq!(results, [Ok(0), Ok(2), Ok(4), Ok(6), Ok(8)]);
+}
+
+#[tokio::test]
+async fn completions_batch() {
+    use elikoga_textsynth::{
+        completions::{Engine, RequestBuilder},
+        transport::MockTransport,
+        TextSynthClient,
+    };
+    use reqwest::StatusCode;
+
+    // answer with the prompt, failing the empty one
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(|request| {
+            let body: serde_json::Value = request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .and_then(|body| serde_json::from_slice(body).ok())
+                .expect("requests have a json body");
+            match body["prompt"].as_str() {
+                Some("") | None => (
+                    StatusCode::BAD_REQUEST,
+                    r#"{"error":"empty prompt"}"#.to_string(),
+                ),
+                Some(prompt) => (
+                    StatusCode::OK,
+                    serde_json::json!({"text": prompt, "reached_end": true}).to_string(),
+                ),
+            }
+        }))
+        .build();
+    let requests = ["a", "b", "", "d"].map(|prompt| {
+        RequestBuilder::default()
+            .prompt(prompt)
+            .build()
+            .expect("completion request should build")
+    });
+    let results = client.completions_batch(&Engine::GPTJ6B, requests, 2).await;
+    let texts: Vec<_> = results
+        .successes()
+        .map(|(index, response)| (index, response.text.concat()))
+        .collect();
+    assert_eq!(
+        texts,
+        [
+            (0, "a".to_string()),
+            (1, "b".to_string()),
+            (3, "d".to_string())
+        ]
+    );
+    assert_eq!(
+        results.errors().map(|(index, _)| index).collect::<Vec<_>>(),
+        [2]
+    );
+}
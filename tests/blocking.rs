@@ -0,0 +1,92 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use common::{endpoint, read_request, respond_with, serve};
+use elikoga_textsynth::{
+    blocking::TextSynthBlockingClient,
+    completions::{Engine, RequestBuilder},
+};
+
+#[test]
+fn blocking_completions() {
+    // answer a single streamed completion request on a local port
+    let port = serve(|mut stream| {
+        read_request(&mut stream);
+        let body = "{\"text\":\" Paris\",\"reached_end\":false}\n\n{\"text\":\".\",\"reached_end\":true}\n\n";
+        respond_with(
+            &mut stream,
+            "200 OK",
+            "content-type: application/json\r\n",
+            body.as_bytes(),
+        );
+    });
+    let client = TextSynthBlockingClient::new_with_endpoint("key", &endpoint(port));
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .stream(true)
+        .build()
+        .expect("failed to build completion request");
+    let text: String = client
+        .completions(&Engine::GPTJ6B, &request)
+        .expect("failed to complete")
+        .map(|chunk| chunk.expect("failed to stream").text.concat())
+        .collect();
+    assert_eq!(text, " Paris.");
+}
+
+#[test]
+fn blocking_tokenize() {
+    // get API Key from env
+    let api_key = std::env::var("TEXT_SYNTH_API_KEY").expect("TEXT_SYNTH_API_KEY not set");
+    let client = TextSynthBlockingClient::new(&api_key);
+    let request = elikoga_textsynth::tokenize::RequestBuilder::default()
+        .text("The quick brown fox jumps over the lazy dog")
+        .build()
+        .expect("tokenize request should build");
+    let response = client
+        .tokenize(&Engine::GPTJ6B, &request)
+        .expect("tokenize request should succeed");
+    assert_eq!(
+        response.tokens,
+        [464, 2068, 7586, 21831, 18045, 625, 262, 16931, 3290]
+    );
+}
+
+#[test]
+fn blocking_sse_completions() {
+    // answer a single streamed completion request framed as server-sent events
+    let port = serve(|mut stream| {
+        read_request(&mut stream);
+        let body = ": keep-alive\n\ndata: {\"text\":\" Paris\",\"reached_end\":false}\n\ndata: {\"text\":\".\",\"reached_end\":true}\n\ndata: [DONE]\n\n";
+        respond_with(
+            &mut stream,
+            "200 OK",
+            "content-type: text/event-stream\r\n",
+            body.as_bytes(),
+        );
+    });
+    let client = TextSynthBlockingClient::new_with_endpoint("key", &endpoint(port));
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .stream(true)
+        .build()
+        .expect("failed to build completion request");
+    let text: String = client
+        .completions(&Engine::GPTJ6B, &request)
+        .expect("failed to complete")
+        .map(|chunk| chunk.expect("failed to stream").text.concat())
+        .collect();
+    assert_eq!(text, " Paris.");
+}
+
+#[test]
+fn blocking_client() {
+    let client =
+        TextSynthBlockingClient::try_new_with_endpoint("secret-key", "http://127.0.0.1/v1")
+            .expect("client should build");
+    let debug = format!("{:?}", client);
+    assert!(!debug.contains("secret"));
+    assert!(debug.contains("http://127.0.0.1/v1"));
+    assert!(TextSynthBlockingClient::try_new("bad\nkey").is_err());
+}
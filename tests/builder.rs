@@ -0,0 +1,53 @@
+use elikoga_textsynth::{
+    chat::{self, Message},
+    completions::{self, logprob},
+    text_to_image, tokenize, transcript,
+    translate::{self, Language},
+};
+
+#[test]
+fn required_fields() {
+    let request = completions::Request::builder("Hello")
+        .max_tokens(8_u32)
+        .build()
+        .expect("completion request should build");
+    assert_eq!(
+        serde_json::to_value(&request).expect("request should serialize"),
+        serde_json::json!({"prompt": "Hello", "max_tokens": 8})
+    );
+    let request = translate::Request::builder(["Hello".into()], "en", Language::German)
+        .build()
+        .expect("translation request should build");
+    assert_eq!(
+        serde_json::to_value(&request).expect("request should serialize"),
+        serde_json::json!({"text": ["Hello"], "source_lang": "en", "target_lang": "de"})
+    );
+    chat::Request::builder([Message::user("Hello")])
+        .build()
+        .expect("chat request should build");
+    tokenize::Request::builder("Hello")
+        .build()
+        .expect("tokenize request should build");
+    logprob::Request::builder("The capital of France is", " Paris")
+        .build()
+        .expect("logprob request should build");
+    text_to_image::Request::builder("an astronaut riding a horse")
+        .build()
+        .expect("text to image request should build");
+    transcript::Request::builder(b"RIFF audio".to_vec())
+        .build()
+        .expect("transcript request should build");
+}
+
+#[test]
+fn required_fields_validated() {
+    let error = completions::Request::builder("Hello")
+        .max_tokens(0_u32)
+        .build()
+        .expect_err("max_tokens must be positive");
+    assert_eq!(error.field(), "max_tokens");
+    let error = translate::Request::builder(["Hello".into()], "en", "abc")
+        .build()
+        .expect_err("abc isn't supported");
+    assert_eq!(error.field(), "target_lang");
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "streaming")]
+
+use elikoga_textsynth::{
+    completions::{Engine, RequestBuilder},
+    transport::MockTransport,
+    TextSynthClient,
+};
+
+#[tokio::test]
+async fn completions_with() {
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::json(
+            r#"{"text":" Paris","reached_end":false}
+
+{"text":"","reached_end":false}
+
+{"text":".","reached_end":true,"input_tokens":5,"output_tokens":2}
+
+"#,
+        ))
+        .build();
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .stream(true)
+        .build()
+        .expect("completion request should build");
+    let mut deltas = Vec::new();
+    let completions = client
+        .completions_with(&Engine::GPTJ6B, &request, |delta| {
+            deltas.push(delta.to_string())
+        })
+        .await
+        .expect("canned answer should parse");
+    assert_eq!(deltas, [" Paris", "."]);
+    assert_eq!(completions.texts().collect::<Vec<_>>(), [" Paris."]);
+    assert!(completions.reached_end);
+    assert_eq!(completions.output_tokens, Some(2));
+}
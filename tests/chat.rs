@@ -0,0 +1,47 @@
+use elikoga_textsynth::{
+    chat::{Engine, Message, RequestBuilder},
+    TextSynthClient,
+};
+
+#[test]
+fn chat_request() {
+    let request = RequestBuilder::default()
+        .messages(vec![
+            Message::system("Answer in one word."),
+            Message::user("What is the capital of France?"),
+        ])
+        .max_tokens(8_u32)
+        .build()
+        .expect("failed to build chat request");
+    let json = serde_json::to_value(&request).expect("request should serialize");
+    assert_eq!(json["messages"][0]["role"], "system");
+    assert_eq!(
+        json["messages"][1]["content"],
+        "What is the capital of France?"
+    );
+    assert!(RequestBuilder::default()
+        .messages(vec![Message::user("Hi"), Message::assistant("Hello!")])
+        .build()
+        .is_err());
+    assert!(RequestBuilder::default()
+        .messages(Vec::new())
+        .build()
+        .is_err());
+}
+
+#[tokio::test]
+async fn chat() {
+    // get API Key from env
+    let api_key = std::env::var("TEXT_SYNTH_API_KEY").expect("TEXT_SYNTH_API_KEY not set");
+    let client = TextSynthClient::new(&api_key);
+    let request = RequestBuilder::default()
+        .messages(vec![Message::user("What is the capital of France?")])
+        .max_tokens(16_u32)
+        .build()
+        .expect("failed to build chat request");
+    let response = client
+        .chat(&Engine::Mistral7BInstruct, &request)
+        .await
+        .expect("failed to chat");
+    assert!(response.text[0].contains("Paris"));
+}
@@ -0,0 +1,34 @@
+#![cfg(feature = "streaming")]
+
+use elikoga_textsynth::completions::{
+    checkpoint::{Checkpoint, CheckpointStore, FileStore},
+    RequestBuilder,
+};
+
+#[test]
+fn checkpoint() {
+    let request = RequestBuilder::default()
+        .prompt("Once upon a time")
+        .max_tokens(100_u32)
+        .stream(true)
+        .build()
+        .expect("failed to build completion request");
+    let checkpoint = Checkpoint {
+        prompt: "Once upon a time".into(),
+        generated: ", there was".into(),
+        chunks: 3,
+        output_tokens: 3,
+        reached_end: false,
+    };
+    let resumed =
+        serde_json::to_value(checkpoint.resume(&request)).expect("request should serialize");
+    assert_eq!(resumed["prompt"], "Once upon a time, there was");
+    assert_eq!(resumed["max_tokens"], 97);
+
+    let path = std::env::temp_dir().join(format!("checkpoint-{}.json", std::process::id()));
+    let mut store = FileStore::new(&path);
+    assert_eq!(store.load().expect("load should succeed"), None);
+    store.save(&checkpoint).expect("save should succeed");
+    assert_eq!(store.load().expect("load should succeed"), Some(checkpoint));
+    std::fs::remove_file(path).expect("checkpoint file should exist");
+}
@@ -0,0 +1,63 @@
+use elikoga_textsynth::{
+    completions::{choices::Choice, Engine, RequestBuilder},
+    transport::MockTransport,
+    TextSynthClient,
+};
+
+#[tokio::test]
+async fn complete_choices() {
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::json(
+            r#"{"text":[" Paris."," Paris, of course."],"reached_end":true,"input_tokens":5,"output_tokens":7}"#,
+        ))
+        .build();
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .n(2_u32)
+        .build()
+        .expect("completion request should build");
+    let completions = client
+        .complete_choices(&Engine::GPTJ6B, &request)
+        .await
+        .expect("canned answer should parse");
+    assert_eq!(
+        completions.choices,
+        [
+            Choice {
+                index: 0,
+                text: " Paris.".to_string()
+            },
+            Choice {
+                index: 1,
+                text: " Paris, of course.".to_string()
+            },
+        ]
+    );
+    assert!(completions.reached_end);
+    assert_eq!(completions.output_tokens, Some(7));
+}
+
+#[cfg(feature = "streaming")]
+#[tokio::test]
+async fn collect_choices() {
+    use elikoga_textsynth::completions::{
+        choices::{collect_choices, Completions},
+        ResponseChunk,
+    };
+
+    let chunks = [
+        r#"{"text":[" Paris"," Paris"],"reached_end":false}"#,
+        r#"{"text":[".",","],"reached_end":false}"#,
+        r#"{"text":[""," of course."],"reached_end":true,"output_tokens":7}"#,
+    ]
+    .map(|chunk| serde_json::from_str::<ResponseChunk>(chunk).map_err(Into::into));
+    let completions: Completions = collect_choices(futures::stream::iter(chunks))
+        .await
+        .expect("chunks are ok");
+    assert_eq!(
+        completions.texts().collect::<Vec<_>>(),
+        [" Paris.", " Paris, of course."]
+    );
+    assert!(completions.reached_end);
+    assert_eq!(completions.output_tokens, Some(7));
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use common::serve_json;
+use elikoga_textsynth::{
+    completions::Engine, transport::MockTransport, usage::UsageRecorder, BuildError, ProxyConfig,
+    TextSynthClient, DEFAULT_USER_AGENT,
+};
+
+#[tokio::test]
+async fn client_builder() {
+    // headers configured on the injected client are kept
+    let (port, requests) = serve_json([("200 OK", r#"{"tokens":[1]}"#)]);
+    let client = TextSynthClient::builder("key")
+        .endpoint(&common::endpoint(port))
+        .client_builder(reqwest::Client::builder().user_agent("my-app/1.0"))
+        .try_build()
+        .expect("client should build");
+    client
+        .tokenize_str(&Engine::GPTJ6B, "Hello")
+        .await
+        .expect("tokenize request should succeed");
+    let received = requests.recv().expect("no request").to_lowercase();
+    assert!(received.contains("user-agent: my-app/1.0"));
+    assert!(received.contains("authorization: bearer key"));
+
+    assert!(TextSynthClient::builder("bad\nkey").try_build().is_err());
+    assert!(matches!(
+        TextSynthClient::try_new("bad\nkey"),
+        Err(BuildError::InvalidApiKey(_))
+    ));
+}
+
+#[tokio::test]
+async fn shared_client() {
+    fn shareable<T: Clone + Send + Sync + 'static>(_: &T) {}
+
+    let usage = UsageRecorder::new();
+    let client = TextSynthClient::builder("key")
+        .usage_recorder(usage.clone())
+        .transport(MockTransport::json(r#"{"tokens":[1,2]}"#))
+        .build();
+    shareable(&client);
+    let tasks: Vec<_> = (0..3)
+        .map(|_| {
+            let client = client.clone();
+            tokio::spawn(async move { client.tokenize_str(&Engine::GPTJ6B, "Hello").await })
+        })
+        .collect();
+    for task in tasks {
+        let tokens = task
+            .await
+            .expect("task panicked")
+            .expect("canned answer should parse");
+        assert_eq!(tokens, [1, 2]);
+    }
+    // the clones record into the same usage
+    assert_eq!(usage.snapshot().total().requests, 3);
+}
+
+#[test]
+fn redacted_debug() {
+    let builder = TextSynthClient::builder("secret-key")
+        .endpoint("http://127.0.0.1:8080/v1")
+        .proxy(ProxyConfig::new("http://proxy:3128").basic_auth("user", "secret-password"));
+    let debug = format!("{:?}", builder);
+    assert!(!debug.contains("secret"));
+    assert!(debug.contains("user"));
+    let client = builder.build();
+    let debug = format!("{:?}", client);
+    assert!(!debug.contains("secret"));
+    assert!(debug.contains("http://127.0.0.1:8080/v1"));
+}
+
+#[test]
+fn endpoint() {
+    let client = TextSynthClient::try_new_with_endpoint("key", "http://localhost:8080/v1/")
+        .expect("endpoint is valid");
+    assert!(format!("{:?}", client).contains(r#"base_url: "http://localhost:8080/v1""#));
+    let client = TextSynthClient::try_new_with_endpoint("key", "https://api.textsynth.com")
+        .expect("endpoint is valid");
+    assert!(format!("{:?}", client).contains(r#"base_url: "https://api.textsynth.com""#));
+
+    for endpoint in [
+        "api.textsynth.com/v1",
+        "localhost:8080",
+        "ftp://host/v1",
+        "",
+    ] {
+        assert!(
+            matches!(
+                TextSynthClient::try_new_with_endpoint("key", endpoint),
+                Err(BuildError::InvalidEndpoint(_))
+            ),
+            "{} should be rejected",
+            endpoint
+        );
+    }
+}
+
+#[tokio::test]
+async fn user_agent() {
+    let echo = |request: &reqwest::Request| {
+        let user_agent = request.headers()[reqwest::header::USER_AGENT]
+            .to_str()
+            .expect("user agent is text");
+        (
+            reqwest::StatusCode::OK,
+            format!(r#"{{"tokens":[{}]}}"#, user_agent.len()),
+        )
+    };
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(echo))
+        .build();
+    let tokens = client
+        .tokenize_str(&Engine::GPTJ6B, "Hello")
+        .await
+        .expect("canned answer should parse");
+    assert_eq!(tokens, [DEFAULT_USER_AGENT.len() as u32]);
+    assert!(DEFAULT_USER_AGENT.starts_with("elikoga-textsynth-rs/"));
+
+    let client = TextSynthClient::builder("key")
+        .user_agent("my-app/1.0")
+        .transport(MockTransport::new(echo))
+        .build();
+    let tokens = client
+        .tokenize_str(&Engine::GPTJ6B, "Hello")
+        .await
+        .expect("canned answer should parse");
+    assert_eq!(tokens, ["my-app/1.0".len() as u32]);
+
+    assert!(matches!(
+        TextSynthClient::builder("key")
+            .user_agent("bad\nagent")
+            .try_build(),
+        Err(BuildError::InvalidUserAgent(_))
+    ));
+}
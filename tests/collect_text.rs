@@ -0,0 +1,40 @@
+#![cfg(feature = "streaming")]
+
+use elikoga_textsynth::completions::{self, CollectedText, ResponseChunk};
+use futures::stream;
+
+fn chunk(json: &str) -> Result<ResponseChunk, completions::Error> {
+    Ok(serde_json::from_str(json).expect("invalid chunk"))
+}
+
+#[tokio::test]
+async fn collect_text() {
+    let chunks = stream::iter([
+        chunk(r#"{"text":" Paris","reached_end":false}"#),
+        chunk(r#"{"text":".","reached_end":false}"#),
+        chunk(
+            r#"{"text":"","reached_end":true,"truncated_prompt":false,"input_tokens":5,"output_tokens":2}"#,
+        ),
+    ]);
+    let collected = completions::collect_text(chunks)
+        .await
+        .expect("stream has no errors");
+    assert_eq!(
+        collected,
+        CollectedText {
+            text: " Paris.".to_string(),
+            reached_end: true,
+            truncated_prompt: false,
+            input_tokens: Some(5),
+            output_tokens: Some(2),
+        }
+    );
+
+    let chunks = stream::iter([
+        chunk(r#"{"text":" Paris","reached_end":false}"#),
+        Err(serde_json::from_str::<ResponseChunk>("{")
+            .unwrap_err()
+            .into()),
+    ]);
+    assert!(completions::collect_text(chunks).await.is_err());
+}
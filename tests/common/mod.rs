@@ -0,0 +1,147 @@
+//! Fake api servers on local ports, shared by the integration tests
+
+#![allow(dead_code)]
+
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+/// Accept connections on a local port, handing them to `handler` one after
+/// the other, returning the port
+pub fn serve(mut handler: impl FnMut(TcpStream) + Send + 'static) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind");
+    let port = listener.local_addr().expect("no local address").port();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            handler(stream.expect("failed to accept"));
+        }
+    });
+    port
+}
+
+/// Answer each request on a local port with the next of `responses`, a
+/// status and json body, reporting the requests received
+pub fn serve_json<I>(responses: I) -> (u16, mpsc::Receiver<String>)
+where
+    I: IntoIterator<Item = (&'static str, &'static str)>,
+    I::IntoIter: Send + 'static,
+{
+    let mut responses = responses.into_iter();
+    let (sender, requests) = mpsc::channel();
+    let port = serve(move |mut stream| {
+        let _ = sender.send(read_request(&mut stream));
+        let (status, body) = responses.next().expect("unexpected request");
+        respond(&mut stream, status, body);
+    });
+    (port, requests)
+}
+
+/// Answer a streaming request on a local port with `body`, keeping the
+/// connection open if `hold`, and report when the client closes it
+pub fn serve_stream(body: Vec<u8>, hold: bool) -> (u16, mpsc::Receiver<()>) {
+    let (closed, connection_closed) = mpsc::channel();
+    let port = serve(move |mut stream| {
+        read_request(&mut stream);
+        if hold {
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\r\n")
+                .expect("failed to write");
+            stream.write_all(&body).expect("failed to write");
+            wait_closed(&mut stream);
+        } else {
+            respond_with(
+                &mut stream,
+                "200 OK",
+                "content-type: application/json\r\n",
+                &body,
+            );
+        }
+        let _ = closed.send(());
+    });
+    (port, connection_closed)
+}
+
+/// Endpoint of the api served on the local `port`
+pub fn endpoint(port: u16) -> String {
+    format!("http://127.0.0.1:{}/v1", port)
+}
+
+/// A local port nothing listens on
+pub fn closed_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .expect("failed to bind")
+        .local_addr()
+        .expect("no local address")
+        .port()
+}
+
+/// Read a whole HTTP request, relying on its content-length header or the
+/// last chunk of a chunked body, empty if the connection was closed first
+pub fn read_request(stream: &mut impl Read) -> String {
+    let mut request = Vec::new();
+    let mut buffer = [0; 4096];
+    loop {
+        let read = stream.read(&mut buffer).expect("failed to read");
+        request.extend_from_slice(&buffer[..read]);
+        let text = String::from_utf8_lossy(&request);
+        if let Some(end) = text.find("\r\n\r\n") {
+            let head = text[..end].to_lowercase();
+            let length = head
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length: "))
+                .and_then(|length| length.parse::<usize>().ok())
+                .unwrap_or(0);
+            let chunked = head.contains("transfer-encoding: chunked");
+            if !chunked && request.len() >= end + 4 + length
+                || chunked && text.ends_with("\r\n0\r\n\r\n")
+            {
+                return text.into_owned();
+            }
+        }
+        if read == 0 {
+            return text.into_owned();
+        }
+    }
+}
+
+/// Answer with `status` and a json `body`, closing the connection
+pub fn respond(stream: &mut impl Write, status: &str, body: &str) {
+    respond_with(
+        stream,
+        status,
+        "content-type: application/json\r\n",
+        body.as_bytes(),
+    );
+}
+
+/// Answer with `status`, the `headers` lines and `body`, closing the
+/// connection
+pub fn respond_with(stream: &mut impl Write, status: &str, headers: &str, body: &[u8]) {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\n{}content-length: {}\r\nconnection: close\r\n\r\n",
+        status,
+        headers,
+        body.len()
+    )
+    .expect("failed to write");
+    stream.write_all(body).expect("failed to write");
+}
+
+/// Read from `stream` until the client closes the connection
+pub fn wait_closed(stream: &mut impl Read) {
+    let mut buffer = [0; 4096];
+    while !matches!(stream.read(&mut buffer), Ok(0) | Err(_)) {}
+}
+
+/// Wait up to a second for the server to report the connection closed
+pub async fn assert_closed(connection_closed: mpsc::Receiver<()>) {
+    tokio::task::spawn_blocking(move || connection_closed.recv_timeout(Duration::from_secs(1)))
+        .await
+        .expect("waiting for the server panicked")
+        .expect("connection still open");
+}
@@ -0,0 +1,24 @@
+use elikoga_textsynth::{
+    completions::{Engine, RequestBuilder},
+    TextSynthClient,
+};
+
+#[tokio::test]
+async fn complete() {
+    // get API Key from env
+    let api_key = std::env::var("TEXT_SYNTH_API_KEY").expect("TEXT_SYNTH_API_KEY not set");
+    let client = TextSynthClient::new(&api_key);
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .max_tokens(1_u32)
+        .temperature(0.0)
+        .stream(true)
+        .build()
+        .expect("failed to build completion request");
+    let response = client
+        .complete(&Engine::GPTJ6B, &request)
+        .await
+        .expect("failed to complete");
+    assert_eq!(response.text.len(), 1);
+    assert!(response.reached_end);
+}
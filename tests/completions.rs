@@ -1,3 +1,5 @@
+#![cfg(feature = "streaming")]
+
 use elikoga_textsynth::{
     completions::{Engine, RequestBuilder},
     TextSynthClient,
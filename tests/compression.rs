@@ -0,0 +1,44 @@
+#![cfg(feature = "compression")]
+
+mod common;
+
+use std::{io::Write, sync::mpsc};
+
+use common::{endpoint, read_request, respond_with, serve};
+use elikoga_textsynth::{completions::Engine, TextSynthClient};
+use flate2::{write::GzEncoder, Compression};
+
+#[tokio::test]
+async fn compression() {
+    // the body is gzip compressed for requests accepting it
+    let (sender, requests) = mpsc::channel();
+    let port = serve(move |mut stream| {
+        let received = read_request(&mut stream).to_lowercase();
+        let body = br#"{"tokens":[1,2,3]}"#;
+        let (headers, body) = if received.contains("accept-encoding: gzip") {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(body).expect("failed to compress");
+            (
+                "content-type: application/json\r\ncontent-encoding: gzip\r\n",
+                encoder.finish().expect("failed to compress"),
+            )
+        } else {
+            ("content-type: application/json\r\n", body.to_vec())
+        };
+        let _ = sender.send(received);
+        respond_with(&mut stream, "200 OK", headers, &body);
+    });
+    for compression in [true, false] {
+        let client = TextSynthClient::builder("key")
+            .endpoint(&endpoint(port))
+            .compression(compression)
+            .build();
+        let tokens = client
+            .tokenize_str(&Engine::GPTJ6B, "Hello")
+            .await
+            .expect("tokenize request should succeed");
+        assert_eq!(tokens, [1, 2, 3]);
+        let received = requests.recv().expect("no request");
+        assert_eq!(received.contains("accept-encoding: gzip"), compression);
+    }
+}
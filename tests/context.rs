@@ -0,0 +1,100 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use elikoga_textsynth::{
+    completions::{Engine, Error, RequestBuilder},
+    define_engine,
+    transport::MockTransport,
+    TextSynthClient,
+};
+use reqwest::StatusCode;
+
+define_engine!(TINY = "tiny" {
+    context_length: 5,
+    completion: true,
+});
+
+#[tokio::test]
+async fn fit_to_context() {
+    // "The", " quick", " brown", " fox", " jumps"
+    let sent = Arc::new(AtomicUsize::new(0));
+    let counter = sent.clone();
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            (
+                StatusCode::OK,
+                r#"{"tokens":[464,2068,7586,21831,18045],"token_content":["VGhl","IHF1aWNr","IGJyb3du","IGZveA==","IGp1bXBz"]}"#
+                    .to_string(),
+            )
+        }))
+        .build();
+    let request = RequestBuilder::default()
+        .prompt("The quick brown fox jumps")
+        .max_tokens(2_u32)
+        .build()
+        .expect("completion request should build");
+    let fitted = request
+        .fit_to_context(&client, &Engine::Custom(&TINY))
+        .await
+        .expect("tokenize request should succeed");
+    assert_eq!(sent.load(Ordering::SeqCst), 1);
+    let body = serde_json::to_value(&fitted).expect("request should serialize");
+    assert_eq!(body["prompt"], " brown fox jumps");
+    assert_eq!(body["max_tokens"], 2);
+
+    // nothing to fit without a known context length
+    let unchanged = request
+        .fit_to_context(&client, &Engine::Other("unknown".to_string()))
+        .await
+        .expect("no request is sent");
+    assert_eq!(sent.load(Ordering::SeqCst), 1);
+    let body = serde_json::to_value(&unchanged).expect("request should serialize");
+    assert_eq!(body["prompt"], "The quick brown fox jumps");
+}
+
+#[tokio::test]
+async fn check_context() {
+    assert_eq!(Engine::GPTJ6B.max_context_tokens(), Some(2048));
+    assert_eq!(Engine::GPTNeoX20B.max_context_tokens(), Some(1024));
+
+    let sent = Arc::new(AtomicUsize::new(0));
+    let counter = sent.clone();
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            (StatusCode::OK, r#"{"tokens":[464,2068,7586]}"#.to_string())
+        }))
+        .build();
+    let engine = Engine::Custom(&TINY);
+    let request = RequestBuilder::default()
+        .prompt("The quick brown")
+        .max_tokens(2_u32)
+        .build()
+        .expect("completion request should build");
+    request
+        .check_context(&client, &engine)
+        .await
+        .expect("request should fit");
+
+    let request = RequestBuilder::default()
+        .prompt("The quick brown")
+        .max_tokens(3_u32)
+        .build()
+        .expect("completion request should build");
+    let error = request
+        .check_context(&client, &engine)
+        .await
+        .expect_err("request shouldn't fit");
+    assert_eq!(sent.load(Ordering::SeqCst), 2);
+    assert!(matches!(
+        error,
+        Error::ContextLengthExceeded {
+            prompt_tokens: 3,
+            max_tokens: 3,
+            context_length: 5,
+        }
+    ));
+}
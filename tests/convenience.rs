@@ -0,0 +1,51 @@
+use elikoga_textsynth::{completions, translate, transport::MockTransport, TextSynthClient};
+
+#[tokio::test]
+async fn convenience() {
+    // get API Key from env
+    let api_key = std::env::var("TEXT_SYNTH_API_KEY").expect("TEXT_SYNTH_API_KEY not set");
+    let client = TextSynthClient::new(&api_key);
+    let tokens = client
+        .tokenize_str(
+            &completions::Engine::GPTJ6B,
+            "The quick brown fox jumps over the lazy dog",
+        )
+        .await
+        .expect("tokenize request should succeed");
+    assert_eq!(
+        tokens,
+        [464, 2068, 7586, 21831, 18045, 625, 262, 16931, 3290]
+    );
+    let translation = client
+        .translate_one(&translate::Engine::M2M10012B, "Hello, world!", "en", "de")
+        .await
+        .expect("translation request should succeed");
+    assert!(!translation.is_empty());
+    let completion = client
+        .complete_str(&completions::Engine::GPTJ6B, "The capital of France is", 4)
+        .await
+        .expect("completion request should succeed");
+    assert!(!completion.is_empty());
+    let error = client
+        .translate_one(&translate::Engine::M2M10012B, "Hello", "english", "de")
+        .await
+        .expect_err("source language is not a language code");
+    assert!(matches!(error, translate::Error::BuilderError(_)));
+}
+
+#[tokio::test]
+async fn complete_str_invalid() {
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(|_| {
+            panic!("invalid requests aren't sent");
+        }))
+        .build();
+    let error = client
+        .complete_str(&completions::Engine::GPTJ6B, "The capital of France is", 0)
+        .await
+        .expect_err("max_tokens must be positive");
+    let completions::Error::BuilderError(error) = error else {
+        panic!("not a builder error: {:?}", error);
+    };
+    assert_eq!(error.field(), "max_tokens");
+}
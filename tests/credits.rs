@@ -0,0 +1,20 @@
+use elikoga_textsynth::{credits::Response, TextSynthClient};
+
+#[test]
+fn dollars() {
+    let response: Response =
+        serde_json::from_str(r#"{"credits":2500000000}"#).expect("response should parse");
+    assert_eq!(response.dollars(), 2.5);
+}
+
+#[tokio::test]
+async fn credits() {
+    // get API Key from env
+    let api_key = std::env::var("TEXT_SYNTH_API_KEY").expect("TEXT_SYNTH_API_KEY not set");
+    let client = TextSynthClient::new(&api_key);
+    let response = client
+        .credits()
+        .await
+        .expect("credits request should succeed");
+    assert!(response.credits >= 0);
+}
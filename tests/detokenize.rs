@@ -0,0 +1,59 @@
+use std::sync::{Arc, Mutex};
+
+use elikoga_textsynth::{
+    completions::Engine,
+    detokenize::{UnknownToken, Vocabulary},
+    tokenize,
+    transport::MockTransport,
+    TextSynthClient,
+};
+use reqwest::StatusCode;
+
+#[tokio::test]
+async fn detokenize() {
+    // "Hello" and " w\xc3" / "\xa9rld", splitting the "é"
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let seen = requests.clone();
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(move |request| {
+            let body = request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .expect("request should have a body");
+            seen.lock()
+                .expect("lock poisoned")
+                .push(String::from_utf8_lossy(body).into_owned());
+            (
+                StatusCode::OK,
+                r#"{"tokens":[15496,266,1000],"token_content":["SGVsbG8=","IHfD","qXJsZA=="]}"#
+                    .to_string(),
+            )
+        }))
+        .build();
+    let mut vocabulary = Vocabulary::default();
+    let ids = client
+        .learn_tokens(&Engine::GPTJ6B, "Hello wérld", &mut vocabulary)
+        .await
+        .expect("tokenize request should succeed");
+    let sent = &requests.lock().expect("lock poisoned")[0];
+    assert!(sent.contains(r#""token_content_type":"base64""#));
+    assert_eq!(ids, [15496, 266, 1000]);
+    assert_eq!(vocabulary.len(), 3);
+    assert_eq!(vocabulary.get(15496), Some(&b"Hello"[..]));
+    assert_eq!(vocabulary.decode(&ids), Ok("Hello wérld".to_string()));
+    assert_eq!(vocabulary.decode(&[266, 1]), Err(UnknownToken(1)));
+}
+
+#[tokio::test]
+async fn missing_token_content() {
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::json(r#"{"tokens":[15496,995]}"#))
+        .build();
+    let mut vocabulary = Vocabulary::default();
+    let error = client
+        .learn_tokens(&Engine::GPTJ6B, "Hello world", &mut vocabulary)
+        .await
+        .expect_err("the answer has no token contents");
+    assert!(matches!(error, tokenize::Error::MissingTokenContent));
+    assert!(vocabulary.is_empty());
+}
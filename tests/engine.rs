@@ -0,0 +1,116 @@
+use elikoga_textsynth::{
+    chat,
+    completions::{
+        self,
+        select::{Candidate, EngineSelector},
+    },
+    define_engine,
+    engine::{self, Endpoint, ENGINES},
+    translate, IsEngine,
+};
+
+define_engine!(LLAMA_7B = "llama_7B" {
+    context_length: 4096,
+    completion: true,
+    cost: 1.0,
+    price_per_kilotoken: Some(0.5),
+});
+
+define_engine!(NLLB = "nllb_3B" {
+    translation: true,
+    languages: &["en", "de"],
+});
+
+#[test]
+fn engine() {
+    let engine = completions::Engine::Custom(&LLAMA_7B);
+    assert_eq!(engine.to_string(), "llama_7B");
+    assert!(engine.is_completion());
+    assert!(!engine.is_translation());
+    assert_eq!(completions::Engine::GPTJ6B.to_string(), "gptj_6B");
+    assert_eq!(engine, completions::Engine::Custom(&LLAMA_7B));
+
+    let engine = translate::Engine::Custom(&NLLB);
+    assert_eq!(engine.to_string(), "nllb_3B");
+    assert!(engine.is_translation());
+    assert_eq!(NLLB.context_length, 1024);
+
+    // the cheap custom engine is preferred over the built-in ones
+    let mut candidates = EngineSelector::default().candidates().to_vec();
+    candidates.push(Candidate::custom(&LLAMA_7B));
+    let selector = EngineSelector::new(candidates).by_cost();
+    assert_eq!(
+        selector.select(3000, 200, "en"),
+        Some(&completions::Engine::Custom(&LLAMA_7B))
+    );
+}
+
+#[test]
+fn other_engine() {
+    let engine = completions::Engine::Other("mistral_7B".to_string());
+    assert_eq!(engine.to_string(), "mistral_7B");
+    assert!(engine.is_completion());
+    let engine = translate::Engine::Other("madlad400_7B".to_string());
+    assert_eq!(engine.to_string(), "madlad400_7B");
+    assert!(engine.is_translation());
+}
+
+#[test]
+fn parse_engine() {
+    assert_eq!(
+        "gptneox_20B".parse::<completions::Engine>(),
+        Ok(completions::Engine::GPTNeoX20B)
+    );
+    assert_eq!(
+        translate::Engine::try_from("m2m100_1_2B"),
+        Ok(translate::Engine::M2M10012B)
+    );
+    let err = "gpt4".parse::<completions::Engine>().unwrap_err();
+    assert_eq!(err.to_string(), "unknown completion engine gpt4");
+    assert_eq!(err.id, "gpt4");
+}
+
+#[test]
+fn engine_info() {
+    let info = completions::Engine::GPTJ6B
+        .info()
+        .expect("built-in engines are known");
+    assert_eq!(info.id, "gptj_6B");
+    assert_eq!(info.family, Some("GPT-J"));
+    assert_eq!(info.parameters, Some(6_000_000_000));
+    assert_eq!(
+        info.context_length,
+        completions::Engine::GPTJ6B.context_length()
+    );
+    assert!(info.supports(Endpoint::Logprob));
+    assert!(!info.supports(Endpoint::Translate));
+
+    let info = completions::Engine::Custom(&LLAMA_7B)
+        .info()
+        .expect("custom engines are described");
+    assert_eq!(info.context_length, Some(4096));
+    assert_eq!(info.cost(2000), Some(1.0));
+    assert!(info.supports(Endpoint::Completions));
+
+    assert!(translate::Engine::M2M10012B
+        .info()
+        .is_some_and(|info| info.supports(Endpoint::Translate)));
+    assert!(chat::Engine::Mistral7BInstruct
+        .info()
+        .supports(Endpoint::Chat));
+    assert!(completions::Engine::Other("unknown".to_string())
+        .info()
+        .is_none());
+    assert!(ENGINES
+        .iter()
+        .all(|info| info.price_per_kilotoken.is_none()));
+    let info = engine::engine_info("gptj_6B")
+        .expect("built-in engines are known")
+        .clone()
+        .with_price(0.2);
+    assert_eq!(info.cost(500), Some(0.1));
+    assert_eq!(
+        engine::engine_info("stable_diffusion").map(|info| info.parameters),
+        Some(None)
+    );
+}
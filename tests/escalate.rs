@@ -0,0 +1,83 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use elikoga_textsynth::{
+    completions::{
+        escalate::{Confidence, EscalationPolicy},
+        Engine, RequestBuilder,
+    },
+    transport::MockTransport,
+    TextSynthClient,
+};
+use reqwest::StatusCode;
+
+#[tokio::test]
+async fn escalate() {
+    // get API Key from env
+    let api_key = std::env::var("TEXT_SYNTH_API_KEY").expect("TEXT_SYNTH_API_KEY not set");
+    let client = TextSynthClient::new(&api_key);
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .max_tokens(4_u32)
+        .build()
+        .expect("failed to build completion request");
+    // an impossible threshold escalates through every tier
+    let policy = EscalationPolicy::new(Engine::GPTJ6B, Confidence::MinOutputTokens(5))
+        .then(Engine::GPTNeoX20B);
+    let escalated = client
+        .complete_escalating(&policy, &request)
+        .await
+        .expect("failed to complete");
+    assert_eq!(escalated.engine, Engine::GPTNeoX20B);
+    assert_eq!(escalated.tier, 1);
+    assert_eq!(escalated.confident, None);
+}
+
+#[tokio::test]
+async fn escalate_last_tier_not_scored() {
+    let logprob_requests = Arc::new(AtomicUsize::new(0));
+    let counted = logprob_requests.clone();
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(move |request| {
+            if request.url().path().ends_with("/logprob") {
+                counted.fetch_add(1, Ordering::SeqCst);
+                return (
+                    StatusCode::OK,
+                    r#"{"logprob":-20.0,"num_tokens":2,"is_greedy":false,"input_tokens":7}"#
+                        .to_string(),
+                );
+            }
+            (
+                StatusCode::OK,
+                r#"{"text":" Paris.","reached_end":true,"input_tokens":5,"output_tokens":2}"#
+                    .to_string(),
+            )
+        }))
+        .build();
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .max_tokens(4_u32)
+        .build()
+        .expect("failed to build completion request");
+    let policy = EscalationPolicy::new(Engine::GPTJ6B, Confidence::MinAverageLogprob(-1.0))
+        .then(Engine::GPTNeoX20B);
+    let escalated = client
+        .complete_escalating(&policy, &request)
+        .await
+        .expect("failed to complete");
+    assert_eq!(escalated.tier, 1);
+    assert_eq!(escalated.confident, None);
+    assert_eq!(logprob_requests.load(Ordering::SeqCst), 1);
+
+    let policy = EscalationPolicy::new(Engine::GPTJ6B, Confidence::MinAverageLogprob(-20.0))
+        .then(Engine::GPTNeoX20B);
+    let escalated = client
+        .complete_escalating(&policy, &request)
+        .await
+        .expect("failed to complete");
+    assert_eq!(escalated.tier, 0);
+    assert_eq!(escalated.confident, Some(true));
+    assert_eq!(logprob_requests.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "streaming")]
+
+use elikoga_textsynth::{
+    completions::{
+        events::{events, StreamEvent, Warning},
+        Engine, RequestBuilder, ResponseChunk,
+    },
+    transport::MockTransport,
+    TextSynthClient,
+};
+use futures::StreamExt;
+
+#[tokio::test]
+async fn stream_events() {
+    let chunks = [
+        r#"{"text":"Hello","reached_end":false,"truncated_prompt":true}"#,
+        r#"{"text":"","reached_end":false,"truncated_prompt":true}"#,
+        r#"{"text":" world","reached_end":true,"input_tokens":3,"output_tokens":2}"#,
+    ]
+    .map(|chunk| serde_json::from_str::<ResponseChunk>(chunk).expect("chunk should parse"));
+    let stream = futures::stream::iter(chunks.map(Ok));
+    let events: Vec<_> = events(stream)
+        .map(|event| event.expect("events are ok"))
+        .collect()
+        .await;
+    assert_eq!(
+        events,
+        [
+            StreamEvent::Warning(Warning::TruncatedPrompt),
+            StreamEvent::Delta {
+                text: vec!["Hello".to_string()],
+                logprobs: None,
+            },
+            StreamEvent::Delta {
+                text: vec![" world".to_string()],
+                logprobs: None,
+            },
+            StreamEvent::Final {
+                input_tokens: Some(3),
+                output_tokens: Some(2),
+            },
+        ]
+    );
+
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::json(
+            r#"{"text":" Paris","reached_end":false}{"text":".","reached_end":true}"#,
+        ))
+        .build();
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .stream(true)
+        .build()
+        .expect("completion request should build");
+    let events: Vec<_> = client
+        .completions_events(&Engine::GPTJ6B, &request)
+        .await
+        .expect("canned answer should be accepted")
+        .collect()
+        .await;
+    assert_eq!(events.len(), 3);
+    assert!(matches!(events.last(), Some(Ok(StreamEvent::Final { .. }))));
+}
@@ -0,0 +1,207 @@
+#![cfg(feature = "ffi")]
+
+mod common;
+
+use std::{
+    ffi::{CStr, CString},
+    os::raw::{c_char, c_void},
+    ptr,
+};
+
+use common::{endpoint, serve_json};
+use elikoga_textsynth::ffi::{
+    textsynth_client_free, textsynth_client_new_with_endpoint, textsynth_complete,
+    textsynth_last_error, textsynth_string_free, textsynth_translate, TextSynthHandle,
+    TEXTSYNTH_INVALID_ARGUMENT, TEXTSYNTH_OK, TEXTSYNTH_REQUEST_FAILED,
+};
+
+/// A client of the api on the local `port`
+fn client(port: u16) -> *mut TextSynthHandle {
+    let endpoint = CString::new(endpoint(port)).expect("endpoint has no nul");
+    let handle = unsafe { textsynth_client_new_with_endpoint(c"key".as_ptr(), endpoint.as_ptr()) };
+    assert!(!handle.is_null());
+    handle
+}
+
+fn last_error() -> String {
+    let error = unsafe { CStr::from_ptr(textsynth_last_error()) };
+    error.to_str().expect("errors are utf-8").to_string()
+}
+
+extern "C" fn collect(text: *const c_char, user_data: *mut c_void) {
+    let texts = unsafe { &mut *(user_data as *mut Vec<String>) };
+    let text = unsafe { CStr::from_ptr(text) };
+    texts.push(text.to_str().expect("text is utf-8").to_string());
+}
+
+#[test]
+fn ffi_invalid_api_key() {
+    // an api key that isn't valid in a header fails instead of aborting
+    let handle = unsafe { textsynth_client_new_with_endpoint(c"key\n".as_ptr(), ptr::null()) };
+    assert!(handle.is_null());
+    assert!(last_error().contains("Invalid api key"));
+
+    let handle = unsafe { textsynth_client_new_with_endpoint(c"key".as_ptr(), ptr::null()) };
+    assert!(!handle.is_null());
+    unsafe { textsynth_client_free(handle) };
+}
+
+#[test]
+fn ffi_complete() {
+    let (port, requests) = serve_json([(
+        "200 OK",
+        r#"{"text":" Paris","reached_end":false}{"text":".","reached_end":true}"#,
+    )]);
+    let handle = client(port);
+    let mut texts: Vec<String> = Vec::new();
+    let status = unsafe {
+        textsynth_complete(
+            handle,
+            c"gptj_6B".as_ptr(),
+            c"The capital of France is".as_ptr(),
+            2,
+            Some(collect),
+            &mut texts as *mut Vec<String> as *mut c_void,
+        )
+    };
+    assert_eq!(status, TEXTSYNTH_OK);
+    assert_eq!(texts, [" Paris", "."]);
+    let request = requests.recv().expect("no request received");
+    assert!(request.starts_with("POST /v1/engines/gptj_6B/completions "));
+    assert!(request.contains(r#""max_tokens":2"#));
+
+    let status = unsafe {
+        textsynth_complete(
+            handle,
+            c"no_such_engine".as_ptr(),
+            c"prompt".as_ptr(),
+            0,
+            Some(collect),
+            ptr::null_mut(),
+        )
+    };
+    assert_eq!(status, TEXTSYNTH_INVALID_ARGUMENT);
+    unsafe { textsynth_client_free(handle) };
+}
+
+#[test]
+fn ffi_translate() {
+    let (port, requests) = serve_json([
+        (
+            "200 OK",
+            r#"{"translations":[{"text":"Bonjour","detected_source_lang":"en"}],"input_tokens":2,"output_tokens":3}"#,
+        ),
+        ("500 Internal Server Error", r#"{"error":"overloaded"}"#),
+    ]);
+    let handle = client(port);
+    let translation = unsafe {
+        textsynth_translate(
+            handle,
+            c"m2m100_1_2B".as_ptr(),
+            c"Hello".as_ptr(),
+            c"en".as_ptr(),
+            c"fr".as_ptr(),
+        )
+    };
+    assert!(!translation.is_null());
+    assert_eq!(
+        unsafe { CStr::from_ptr(translation) }.to_str(),
+        Ok("Bonjour")
+    );
+    unsafe { textsynth_string_free(translation) };
+    let request = requests.recv().expect("no request received");
+    assert!(request.starts_with("POST /v1/engines/m2m100_1_2B/translate "));
+
+    let translation = unsafe {
+        textsynth_translate(
+            handle,
+            c"m2m100_1_2B".as_ptr(),
+            c"Hello".as_ptr(),
+            c"en".as_ptr(),
+            c"fr".as_ptr(),
+        )
+    };
+    assert!(translation.is_null());
+    assert!(last_error().contains("overloaded"));
+    unsafe { textsynth_client_free(handle) };
+}
+
+#[test]
+fn ffi_string_free() {
+    // strings returned by the api are freed with it, null is ignored
+    let (port, _) = serve_json([(
+        "200 OK",
+        r#"{"translations":[{"text":"Hallo","detected_source_lang":"en"}],"input_tokens":2,"output_tokens":3}"#,
+    )]);
+    let handle = client(port);
+    let translation = unsafe {
+        textsynth_translate(
+            handle,
+            c"m2m100_1_2B".as_ptr(),
+            c"Hello".as_ptr(),
+            c"en".as_ptr(),
+            c"de".as_ptr(),
+        )
+    };
+    assert!(!translation.is_null());
+    unsafe {
+        textsynth_string_free(translation);
+        textsynth_string_free(ptr::null_mut());
+        textsynth_client_free(handle);
+    }
+}
+
+#[test]
+fn ffi_within_runtime() {
+    // blocking on the client's runtime from a runtime fails the call
+    let runtime = tokio::runtime::Runtime::new().expect("failed to start a runtime");
+    runtime.block_on(async {
+        let handle = client(common::closed_port());
+        let status = unsafe {
+            textsynth_complete(
+                handle,
+                c"gptj_6B".as_ptr(),
+                c"prompt".as_ptr(),
+                0,
+                Some(collect),
+                ptr::null_mut(),
+            )
+        };
+        assert_eq!(status, TEXTSYNTH_REQUEST_FAILED);
+        assert!(last_error().contains("tokio runtime"));
+        let translation = unsafe {
+            textsynth_translate(
+                handle,
+                c"m2m100_1_2B".as_ptr(),
+                c"Hello".as_ptr(),
+                c"en".as_ptr(),
+                c"de".as_ptr(),
+            )
+        };
+        assert!(translation.is_null());
+        assert!(last_error().contains("tokio runtime"));
+        // dropping the runtime from a runtime panics, which is reported too
+        unsafe { textsynth_client_free(handle) };
+        assert!(last_error().contains("panicked"));
+    });
+}
+
+#[test]
+fn ffi_header() {
+    // every function of the api is declared by the C header
+    let header = include_str!("../include/textsynth.h");
+    let source = include_str!("../src/ffi.rs");
+    for line in source.lines() {
+        if let Some(name) = line
+            .split_once("extern \"C\" fn ")
+            .and_then(|(_, rest)| rest.split_once('('))
+            .map(|(name, _)| name)
+        {
+            assert!(
+                header.contains(&format!("{}(", name)),
+                "{} is missing",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use elikoga_textsynth::completions::{
+    choices::Completions,
+    finish::{FinishReason, DEFAULT_MAX_TOKENS},
+    RequestBuilder, ResponseChunk,
+};
+
+fn chunk(json: &str) -> ResponseChunk {
+    serde_json::from_str(json).expect("chunk should parse")
+}
+
+#[test]
+fn reported() {
+    let request = RequestBuilder::default()
+        .prompt("Hello")
+        .build()
+        .expect("completion request should build");
+    let answer = chunk(r#"{"text":"","reached_end":true,"finish_reason":"length"}"#);
+    assert_eq!(answer.finish_reason, Some(FinishReason::Length));
+    assert_eq!(
+        answer.infer_finish_reason(&request),
+        Some(FinishReason::Length)
+    );
+    let answer = chunk(r#"{"text":"","reached_end":true,"finish_reason":"tired"}"#);
+    assert_eq!(answer.finish_reason, None);
+    let partial = chunk(r#"{"text":"Hel","reached_end":false}"#);
+    assert_eq!(partial.infer_finish_reason(&request), None);
+}
+
+#[test]
+fn inferred() {
+    let request = RequestBuilder::default()
+        .prompt("Hello")
+        .max_tokens(8_u32)
+        .build()
+        .expect("completion request should build");
+    let full = chunk(r#"{"text":"","reached_end":true,"output_tokens":8}"#);
+    assert_eq!(
+        full.infer_finish_reason(&request),
+        Some(FinishReason::Length)
+    );
+    let early = chunk(r#"{"text":"","reached_end":true,"output_tokens":3}"#);
+    assert_eq!(
+        early.infer_finish_reason(&request),
+        Some(FinishReason::EndOfText)
+    );
+
+    let request = RequestBuilder::default()
+        .prompt("Hello")
+        .stop(vec!["\n".into()])
+        .build()
+        .expect("completion request should build");
+    assert_eq!(
+        early.infer_finish_reason(&request),
+        Some(FinishReason::Stop)
+    );
+    let default = chunk(&format!(
+        r#"{{"text":"","reached_end":true,"output_tokens":{}}}"#,
+        DEFAULT_MAX_TOKENS
+    ));
+    assert_eq!(
+        default.infer_finish_reason(&request),
+        Some(FinishReason::Length)
+    );
+
+    let mut completions = Completions::from(chunk(r#"{"text":"Hi","reached_end":false}"#));
+    assert_eq!(completions.infer_finish_reason(&request), None);
+    completions.push(early);
+    assert_eq!(
+        completions.infer_finish_reason(&request),
+        Some(FinishReason::Stop)
+    );
+}
@@ -0,0 +1,32 @@
+use std::env;
+
+use elikoga_textsynth::{BuildError, TextSynthClient};
+
+#[test]
+fn from_env() {
+    // a single test, as the environment is shared by the threads of the tests
+    let api_key = env::var("TEXT_SYNTH_API_KEY").ok();
+    env::remove_var("TEXTSYNTH_API_KEY");
+    env::remove_var("TEXT_SYNTH_API_KEY");
+    assert!(matches!(
+        TextSynthClient::from_env(),
+        Err(BuildError::MissingApiKey)
+    ));
+
+    env::set_var("TEXTSYNTH_API_KEY", "key");
+    env::set_var("TEXTSYNTH_API_URL", "http://127.0.0.1:8080/v1");
+    let client = TextSynthClient::from_env().expect("client should build");
+    assert!(format!("{:?}", client).contains("http://127.0.0.1:8080/v1"));
+
+    env::set_var("TEXTSYNTH_API_KEY", "bad\nkey");
+    assert!(matches!(
+        TextSynthClient::from_env(),
+        Err(BuildError::InvalidApiKey(_))
+    ));
+
+    env::remove_var("TEXTSYNTH_API_KEY");
+    env::remove_var("TEXTSYNTH_API_URL");
+    if let Some(api_key) = api_key {
+        env::set_var("TEXT_SYNTH_API_KEY", api_key);
+    }
+}
@@ -0,0 +1,108 @@
+#![cfg(feature = "streaming")]
+
+use std::{
+    error::Error as _,
+    sync::{Arc, Mutex},
+};
+
+use bytes::Bytes;
+use elikoga_textsynth::{
+    completions::{Engine, Error, RequestBuilder},
+    transport::{HttpBody, HttpClient, HttpClientTransport, HttpFuture},
+    TextSynthClient,
+};
+use futures::{executor::block_on, stream, StreamExt};
+
+/// Answers every request with the chunks of a streamed completion, keeping
+/// the requests sent
+#[derive(Debug, Default)]
+struct InMemory {
+    requests: Arc<Mutex<Vec<http::Request<Vec<u8>>>>>,
+}
+
+impl HttpClient for InMemory {
+    fn send(&self, request: http::Request<Vec<u8>>) -> HttpFuture<'_> {
+        self.requests.lock().expect("lock poisoned").push(request);
+        let chunks = [
+            r#"{"text":" Paris","reached_end":false}"#,
+            "\n\n",
+            r#"{"text":".","reached_end":true,"output_tokens":2}"#,
+        ];
+        let body: HttpBody = Box::pin(stream::iter(chunks.map(|chunk| Ok(Bytes::from(chunk)))));
+        Box::pin(async move {
+            Ok(http::Response::builder()
+                .status(200)
+                .header("content-type", "application/json")
+                .body(body)?)
+        })
+    }
+}
+
+/// Fails every request as if offline
+#[derive(Debug)]
+struct Offline;
+
+impl HttpClient for Offline {
+    fn send(&self, _: http::Request<Vec<u8>>) -> HttpFuture<'_> {
+        Box::pin(async { Err("offline".into()) })
+    }
+}
+
+#[test]
+fn http_client_without_tokio() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let client = TextSynthClient::builder("key")
+        .transport(HttpClientTransport::new(InMemory {
+            requests: requests.clone(),
+        }))
+        .build();
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .build()
+        .expect("completion request should build");
+    let texts: Vec<_> = block_on(async {
+        client
+            .completions(&Engine::GPTJ6B, &request)
+            .await
+            .expect("request should be sent")
+            .map(|chunk| chunk.expect("chunk should parse").text.concat())
+            .collect()
+            .await
+    });
+    assert_eq!(texts, [" Paris", "."]);
+    let requests = requests.lock().expect("lock poisoned");
+    assert_eq!(requests[0].method(), http::Method::POST);
+    assert_eq!(
+        requests[0].uri(),
+        "https://api.textsynth.com/v1/engines/gptj_6B/completions"
+    );
+    assert_eq!(requests[0].headers()["authorization"], "Bearer key");
+    assert_eq!(
+        requests[0].body().as_slice(),
+        br#"{"prompt":"The capital of France is"}"#
+    );
+}
+
+#[test]
+fn http_client_error() {
+    let client = TextSynthClient::builder("key")
+        .transport(HttpClientTransport::new(Offline))
+        .build();
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .build()
+        .expect("completion request should build");
+    let error =
+        block_on(client.complete(&Engine::GPTJ6B, &request)).expect_err("the client is offline");
+    let Error::RequestError(error) = error else {
+        panic!("not a request error: {:?}", error);
+    };
+    let mut source = error.source();
+    while let Some(error) = source {
+        if error.to_string() == "offline" {
+            return;
+        }
+        source = error.source();
+    }
+    panic!("the error of the client is lost: {:?}", error);
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "streaming")]
+
+mod common;
+
+use std::{io::Write, sync::mpsc};
+
+use common::{endpoint, read_request, serve};
+use elikoga_textsynth::{completions, TextSynthClient};
+
+#[test]
+fn completions_iter() {
+    // stream two chunks on a local port, the second only once the first was
+    // received
+    let (received, first_received) = mpsc::channel();
+    let port = serve(move |mut stream| {
+        read_request(&mut stream);
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\nconnection: close\r\n\r\n{\"text\":\" Paris\",\"reached_end\":false}\n\n")
+            .expect("failed to write");
+        first_received.recv().expect("client hung up");
+        stream
+            .write_all(b"{\"text\":\".\",\"reached_end\":true}\n\n")
+            .expect("failed to write");
+    });
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to build runtime");
+    let client = TextSynthClient::new_with_endpoint("key", &endpoint(port));
+    let request = completions::RequestBuilder::default()
+        .prompt("The capital of France is")
+        .stream(true)
+        .build()
+        .expect("failed to build completion request");
+    let mut chunks = client
+        .completions_iter(&runtime, &completions::Engine::GPTJ6B, &request)
+        .expect("failed to complete");
+    let first = chunks
+        .next()
+        .expect("stream ended early")
+        .expect("failed to parse chunk");
+    assert_eq!(first.text, [" Paris"]);
+    received.send(()).expect("server hung up");
+    let rest: Vec<_> = chunks
+        .map(|chunk| chunk.expect("failed to parse chunk").text.concat())
+        .collect();
+    assert_eq!(rest, ["."]);
+}
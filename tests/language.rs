@@ -0,0 +1,93 @@
+use std::sync::{Arc, Mutex};
+
+use elikoga_textsynth::{
+    completions::{language::LanguageCheck, Engine, RequestBuilder},
+    translate::{self, DETECTION_SAMPLE_CHARS},
+    transport::MockTransport,
+    TextSynthClient,
+};
+use reqwest::StatusCode;
+
+#[tokio::test]
+async fn detect_language_sample() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let seen = requests.clone();
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(move |request| {
+            let body = request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .expect("request should have a body");
+            let body: serde_json::Value =
+                serde_json::from_slice(body).expect("body should be json");
+            seen.lock().expect("lock poisoned").push(body);
+            (
+                StatusCode::OK,
+                r#"{"translations":[{"text":"Hello","detected_source_lang":"fr"}],"input_tokens":5,"output_tokens":2}"#
+                    .to_string(),
+            )
+        }))
+        .build();
+    let text = "é".repeat(DETECTION_SAMPLE_CHARS + 10);
+    let detected = client
+        .detect_language(&translate::Engine::M2M10012B, &text)
+        .await
+        .expect("canned answer should parse");
+    assert_eq!(detected, "fr");
+    let requests = requests.lock().expect("lock poisoned");
+    assert_eq!(requests[0]["source_lang"], "auto");
+    assert_eq!(requests[0]["num_beams"], 1);
+    assert_eq!(requests[0]["split_sentences"], false);
+    assert_eq!(
+        requests[0]["text"][0]
+            .as_str()
+            .expect("text should be a string")
+            .chars()
+            .count(),
+        DETECTION_SAMPLE_CHARS
+    );
+}
+
+#[tokio::test]
+async fn language() {
+    // get API Key from env
+    let api_key = std::env::var("TEXT_SYNTH_API_KEY").expect("TEXT_SYNTH_API_KEY not set");
+    let client = TextSynthClient::new(&api_key);
+    let detected = client
+        .detect_language(&translate::Engine::M2M10012B, "Bonjour tout le monde !")
+        .await
+        .expect("translation request should succeed");
+    assert_eq!(detected, "fr");
+    let request = RequestBuilder::default()
+        .prompt("La capitale de la France est")
+        .max_tokens(16_u32)
+        .build()
+        .expect("failed to build completion request");
+    let check = LanguageCheck::new("fr", translate::Engine::M2M10012B).retries(2);
+    let verified = client
+        .complete_in_language(&Engine::Boris6B, &request, &check)
+        .await
+        .expect("failed to complete");
+    assert!((1..=3).contains(&verified.attempts));
+    assert!(verified.matches("fr") || verified.attempts == 3);
+}
+
+#[tokio::test]
+async fn detect_language_missing() {
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::json(
+            r#"{"translations":[],"input_tokens":4,"output_tokens":0}"#,
+        ))
+        .build();
+    let error = client
+        .detect_language(&translate::Engine::M2M10012B, "Bonjour")
+        .await
+        .expect_err("the answer has no translation");
+    assert!(matches!(
+        error,
+        translate::Error::TranslationCount {
+            expected: 1,
+            got: 0
+        }
+    ));
+}
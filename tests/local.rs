@@ -0,0 +1,46 @@
+use elikoga_textsynth::{completions::Engine, transport::MockTransport, TextSynthClient};
+use reqwest::StatusCode;
+
+#[test]
+fn local_endpoint() {
+    for (addr, endpoint) in [
+        ("localhost", "http://localhost:8080/v1"),
+        ("192.168.1.2:9000", "http://192.168.1.2:9000/v1"),
+        ("localhost:80", "http://localhost/v1"),
+        ("[::1]", "http://[::1]:8080/v1"),
+        ("[::1]:80", "http://[::1]/v1"),
+        ("http://host:8080/api/v1/", "http://host:8080/api/v1"),
+        ("https://host", "https://host/v1"),
+    ] {
+        let debug = format!("{:?}", TextSynthClient::new_local(addr));
+        assert!(
+            debug.contains(&format!("base_url: {:?}", endpoint)),
+            "{} should be {}: {}",
+            addr,
+            endpoint,
+            debug
+        );
+    }
+}
+
+#[tokio::test]
+async fn no_authorization() {
+    let client = TextSynthClient::builder_local("localhost")
+        .transport(MockTransport::new(|request| {
+            assert!(request
+                .headers()
+                .get(reqwest::header::AUTHORIZATION)
+                .is_none());
+            assert_eq!(
+                request.url().as_str(),
+                "http://localhost:8080/v1/engines/llama3_8B/tokenize"
+            );
+            (StatusCode::OK, r#"{"tokens":[1,2]}"#.to_string())
+        }))
+        .build();
+    let tokens = client
+        .tokenize_str(&Engine::Other("llama3_8B".to_string()), "Hello")
+        .await
+        .expect("canned answer should parse");
+    assert_eq!(tokens, [1, 2]);
+}
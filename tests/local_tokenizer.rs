@@ -0,0 +1,48 @@
+#![cfg(feature = "local-tokenizer")]
+
+use elikoga_textsynth::{
+    completions::Engine,
+    local_tokenizer::{tokenizer_url, Error, LocalTokenizer},
+};
+
+const TOKENIZER: &str = r#"{
+    "version": "1.0",
+    "truncation": null,
+    "padding": null,
+    "added_tokens": [],
+    "normalizer": null,
+    "pre_tokenizer": {"type": "Whitespace"},
+    "post_processor": null,
+    "decoder": null,
+    "model": {
+        "type": "WordLevel",
+        "vocab": {"hello": 0, "world": 1, "[UNK]": 2},
+        "unk_token": "[UNK]"
+    }
+}"#;
+
+#[tokio::test]
+async fn local_tokenizer() {
+    let tokenizer = LocalTokenizer::from_bytes(TOKENIZER).expect("tokenizer should load");
+    assert_eq!(
+        tokenizer.encode("hello world").expect("text should encode"),
+        [0, 1]
+    );
+    assert_eq!(
+        tokenizer
+            .count_tokens("hello there world")
+            .expect("text should encode"),
+        3
+    );
+    assert_eq!(
+        tokenizer.decode(&[1, 0]).expect("tokens should decode"),
+        "world hello"
+    );
+
+    assert!(LocalTokenizer::from_bytes("{}").is_err());
+    assert!(tokenizer_url(&Engine::GPTNeoX20B).is_some());
+    assert!(matches!(
+        LocalTokenizer::download(&Engine::Other("my_model".to_string())).await,
+        Err(Error::UnknownTokenizer(engine)) if engine == "my_model"
+    ));
+}
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+
+use elikoga_textsynth::{
+    completions::{logit_bias::LogitBias, Engine, RequestBuilder},
+    transport::MockTransport,
+    TextSynthClient,
+};
+use reqwest::StatusCode;
+
+#[tokio::test]
+async fn logit_bias() {
+    // a made up vocabulary, " unicorn" takes two tokens
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(|request| {
+            let body: serde_json::Value = request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .and_then(|body| serde_json::from_slice(body).ok())
+                .expect("requests have a json body");
+            let tokens = match body["text"].as_str() {
+                Some("unicorn") => "[403, 291]",
+                Some(" unicorn") => "[555, 291]",
+                Some("horse") => "[17]",
+                Some(" horse") => "[18]",
+                _ => return (StatusCode::BAD_REQUEST, "{}".to_string()),
+            };
+            (StatusCode::OK, format!(r#"{{"tokens":{}}}"#, tokens))
+        }))
+        .build();
+
+    let logit_bias = client
+        .ban_words(&Engine::GPTJ6B, ["unicorn"])
+        .await
+        .expect("words should tokenize");
+    assert_eq!(
+        logit_bias,
+        HashMap::from([
+            ("403".to_string(), -100.0),
+            ("555".to_string(), -100.0),
+            ("291".to_string(), -100.0),
+        ])
+    );
+
+    let logit_bias = LogitBias::new()
+        .ban(["unicorn"])
+        .bias(["horse"], 5.0)
+        .build(&client, &Engine::GPTJ6B)
+        .await
+        .expect("words should tokenize");
+    assert_eq!(logit_bias.len(), 5);
+    assert_eq!(logit_bias["18"], 5.0);
+    RequestBuilder::default()
+        .prompt("My favourite animal is")
+        .logit_bias(logit_bias)
+        .build()
+        .expect("completion request should build");
+}
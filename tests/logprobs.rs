@@ -0,0 +1,58 @@
+use std::sync::{Arc, Mutex};
+
+use elikoga_textsynth::{
+    completions::{Engine, RequestBuilder},
+    transport::MockTransport,
+    TextSynthClient,
+};
+use reqwest::StatusCode;
+
+#[test]
+fn top_logprobs_requires_logprobs() {
+    let mut request = RequestBuilder::default();
+    request.prompt("Hello").top_logprobs(2_u32);
+    assert!(request.build().is_err());
+    request.logprobs(true);
+    request.build().expect("request should build");
+    assert!(request.try_top_logprobs(21_u32).is_err());
+}
+
+#[tokio::test]
+async fn logprobs() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let seen = requests.clone();
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(move |request| {
+            let body = request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .expect("request should have a body");
+            seen.lock()
+                .expect("lock poisoned")
+                .push(String::from_utf8_lossy(body).into_owned());
+            (
+                StatusCode::OK,
+                r#"{"text":" world","reached_end":true,"logprobs":[{"token":" world","logprob":-0.5,"top_logprobs":[{"token":" world","logprob":-0.5},{"token":" there","logprob":-1.5}]}]}"#
+                    .to_string(),
+            )
+        }))
+        .build();
+    let request = RequestBuilder::default()
+        .prompt("Hello")
+        .logprobs(true)
+        .top_logprobs(2_u32)
+        .build()
+        .expect("completion request should build");
+    let response = client
+        .complete(&Engine::GPTJ6B, &request)
+        .await
+        .expect("completion request should succeed");
+    let sent = &requests.lock().expect("lock poisoned")[0];
+    assert!(sent.contains(r#""logprobs":true"#));
+    assert!(sent.contains(r#""top_logprobs":2"#));
+    let logprobs = response.logprobs.expect("logprobs were requested");
+    assert_eq!(logprobs.len(), 1);
+    assert_eq!(logprobs[0].token, " world");
+    assert_eq!(logprobs[0].logprob, -0.5);
+    assert_eq!(logprobs[0].top_logprobs[1].token, " there");
+}
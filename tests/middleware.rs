@@ -0,0 +1,64 @@
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc, Mutex,
+};
+
+use elikoga_textsynth::{
+    completions::Engine,
+    middleware::{inspect_response, map_request, Middleware, Next},
+    transport::{MockTransport, TransportFuture},
+    TextSynthClient,
+};
+use reqwest::{header::HeaderValue, Request, Response, StatusCode};
+
+/// Answers the first request with 503 Service Unavailable without sending it
+#[derive(Debug, Default)]
+struct FailOnce {
+    failed: AtomicU32,
+}
+
+impl Middleware for FailOnce {
+    fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> TransportFuture<'a> {
+        if self.failed.fetch_add(1, Ordering::SeqCst) == 0 {
+            let response = http::Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .body("overloaded")
+                .expect("response is valid");
+            return Box::pin(async move { Ok(Response::from(response)) });
+        }
+        next.run(request)
+    }
+}
+
+#[tokio::test]
+async fn middleware() {
+    let statuses = Arc::new(Mutex::new(Vec::new()));
+    let client = TextSynthClient::builder("key")
+        .middleware(map_request(|request| {
+            request
+                .headers_mut()
+                .insert("x-request-id", HeaderValue::from_static("42"));
+        }))
+        .middleware(inspect_response({
+            let statuses = statuses.clone();
+            move |response| statuses.lock().unwrap().push(response.status().as_u16())
+        }))
+        .middleware(FailOnce::default())
+        .transport(MockTransport::new(|request| {
+            match request.headers().get("x-request-id") {
+                Some(_) => (StatusCode::OK, r#"{"tokens":[1,2]}"#.to_string()),
+                None => (StatusCode::BAD_REQUEST, r#"{"error":"no id"}"#.to_string()),
+            }
+        }))
+        .build();
+    client
+        .tokenize_str(&Engine::GPTJ6B, "Hello")
+        .await
+        .expect_err("the failure is injected");
+    let tokens = client
+        .tokenize_str(&Engine::GPTJ6B, "Hello")
+        .await
+        .expect("the request is passed on");
+    assert_eq!(tokens, [1, 2]);
+    assert_eq!(*statuses.lock().unwrap(), [503, 200]);
+}
@@ -0,0 +1,108 @@
+use elikoga_textsynth::{
+    chat,
+    completions::{self, ResponseChunk},
+    interop::openai::{
+        ChatCompletionRequest, ChatCompletionResponse, CompletionRequest, CompletionResponse,
+        ConversionError, Usage,
+    },
+};
+
+#[test]
+fn completion_request() {
+    let request: CompletionRequest = serde_json::from_str(
+        r#"{"model":"gptj_6B","prompt":"Hello","max_tokens":8,"stop":"\n","logprobs":2,"user":"me"}"#,
+    )
+    .expect("openai request should parse");
+    assert_eq!(request.engine(), completions::Engine::GPTJ6B);
+    let request = completions::Request::try_from(request).expect("request should convert");
+    assert_eq!(
+        serde_json::to_value(&request).expect("request should serialize"),
+        serde_json::json!({
+            "prompt": "Hello",
+            "max_tokens": 8,
+            "stop": ["\n"],
+            "logprobs": true,
+            "top_logprobs": 2,
+        })
+    );
+
+    let request = CompletionRequest {
+        model: "my_model".to_string(),
+        prompt: "Hello".to_string(),
+        top_p: Some(1.5),
+        ..CompletionRequest::default()
+    };
+    assert_eq!(
+        request.engine(),
+        completions::Engine::Other("my_model".to_string())
+    );
+    assert!(matches!(
+        completions::Request::try_from(request),
+        Err(ConversionError::InvalidRequest(_))
+    ));
+}
+
+#[test]
+fn chat_request() {
+    let request: ChatCompletionRequest = serde_json::from_str(
+        r#"{"model":"mistral_7B_instruct","messages":[{"role":"system","content":"Be brief."},{"role":"user","content":"Hi"}],"stop":["\n","."]}"#,
+    )
+    .expect("openai request should parse");
+    assert_eq!(
+        request.engine().expect("engine is known"),
+        chat::Engine::Mistral7BInstruct
+    );
+    let request = chat::Request::try_from(request).expect("request should convert");
+    assert_eq!(
+        serde_json::to_value(&request).expect("request should serialize"),
+        serde_json::json!({
+            "messages": [
+                {"role": "system", "content": "Be brief."},
+                {"role": "user", "content": "Hi"},
+            ],
+            "stop": ["\n", "."],
+        })
+    );
+
+    let request: ChatCompletionRequest =
+        serde_json::from_str(r#"{"model":"gpt-4","messages":[{"role":"tool","content":"42"}]}"#)
+            .expect("openai request should parse");
+    assert!(matches!(
+        request.engine(),
+        Err(ConversionError::UnknownEngine(_))
+    ));
+    assert!(matches!(
+        chat::Request::try_from(request),
+        Err(ConversionError::UnknownRole(role)) if role == "tool"
+    ));
+}
+
+#[test]
+fn responses() {
+    let chunk: ResponseChunk = serde_json::from_str(
+        r#"{"text":" Paris.","reached_end":true,"input_tokens":5,"output_tokens":2}"#,
+    )
+    .expect("response should parse");
+    let usage = Some(Usage {
+        prompt_tokens: 5,
+        completion_tokens: 2,
+        total_tokens: 7,
+    });
+
+    let response = CompletionResponse::from_chunk("gptj_6B", &chunk);
+    assert!(response.id.starts_with("cmpl-"));
+    assert_eq!(response.object, "text_completion");
+    assert_eq!(response.model, "gptj_6B");
+    assert_eq!(response.choices.len(), 1);
+    assert_eq!(response.choices[0].text, " Paris.");
+    assert_eq!(response.choices[0].finish_reason.as_deref(), Some("stop"));
+    assert_eq!(response.usage, usage);
+
+    let response = ChatCompletionResponse::from_chunk("mistral_7B_instruct", &chunk);
+    assert_eq!(response.object, "chat.completion");
+    assert_eq!(response.choices[0].message.role, "assistant");
+    assert_eq!(response.choices[0].message.content, " Paris.");
+    assert_eq!(response.usage, usage);
+    let json = serde_json::to_value(&response).expect("response should serialize");
+    assert_eq!(json["usage"]["total_tokens"], 7);
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use std::{
+    io::Write,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use common::{endpoint, read_request, serve};
+use elikoga_textsynth::{completions::Engine, HttpVersion, TextSynthClient};
+
+#[tokio::test]
+async fn pool() {
+    for (max_idle, expected_connections) in [(None, 1), (Some(0), 2)] {
+        // connections are kept alive and counted
+        let connections = Arc::new(AtomicUsize::new(0));
+        let accepted = connections.clone();
+        let port = serve(move |mut stream| {
+            accepted.fetch_add(1, Ordering::SeqCst);
+            while !read_request(&mut stream).is_empty() {
+                let body = r#"{"tokens":[1]}"#;
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .expect("failed to write");
+            }
+        });
+        let mut builder = TextSynthClient::builder("key")
+            .endpoint(&endpoint(port))
+            .pool_idle_timeout(Some(Duration::from_secs(60)))
+            .tcp_keepalive(Duration::from_secs(30))
+            .http_version(HttpVersion::Http1Only);
+        if let Some(max_idle) = max_idle {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        let client = builder.build();
+        for _ in 0..2 {
+            client
+                .tokenize_str(&Engine::GPTJ6B, "Hello")
+                .await
+                .expect("tokenize request should succeed");
+        }
+        assert_eq!(connections.load(Ordering::SeqCst), expected_connections);
+    }
+}
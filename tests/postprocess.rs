@@ -0,0 +1,122 @@
+use elikoga_textsynth::{
+    completions::{
+        finish::FinishReason,
+        postprocess::{PostProcess, PostProcessor},
+        Engine, RequestBuilder,
+    },
+    transport::MockTransport,
+    TextSynthClient,
+};
+
+#[test]
+fn postprocess() {
+    let stop = vec!["\nQ:".to_string(), "END".to_string()];
+    let length = Some(FinishReason::Length);
+    let text = "  Paris.\n\n\n \nIt is large.\nQ";
+    assert_eq!(PostProcess::default().apply(text, &stop, length), text);
+
+    let post_process = PostProcess {
+        strip_partial_stop: true,
+        ..PostProcess::default()
+    };
+    assert_eq!(
+        post_process.apply(text, &stop, length),
+        "  Paris.\n\n\n \nIt is large."
+    );
+    assert_eq!(post_process.apply("The EN", &stop, length), "The ");
+    // a complete stop string isn't partial
+    assert_eq!(post_process.apply("The END", &stop, length), "The END");
+    // only a generation cut short can end with part of a stop string
+    let stopped = Some(FinishReason::Stop);
+    assert_eq!(post_process.apply("The EN", &stop, stopped), "The EN");
+    assert_eq!(post_process.apply("The EN", &stop, None), "The EN");
+
+    let post_process = PostProcess {
+        trim: true,
+        strip_partial_stop: true,
+        collapse_blank_lines: true,
+    };
+    assert_eq!(
+        post_process.apply(text, &stop, length),
+        "Paris.\n\nIt is large."
+    );
+}
+
+#[test]
+fn post_processor() {
+    let post_process = PostProcess {
+        trim: true,
+        strip_partial_stop: true,
+        collapse_blank_lines: true,
+    };
+    let chunks = ["  Paris.", "\n\n\n \nIt", " is large.\nQ"];
+
+    let mut processor = PostProcessor::new(post_process.clone(), ["\nQ:", "END"]);
+    let yielded: Vec<_> = chunks.iter().map(|text| processor.push(text)).collect();
+    assert_eq!(yielded, ["Paris.", "\n\nIt", " is large."]);
+    assert_eq!(processor.finish(Some(FinishReason::Length)), "");
+
+    let mut processor = PostProcessor::new(post_process, ["\nQ:", "END"]);
+    let yielded: String = chunks.iter().map(|text| processor.push(text)).collect();
+    assert_eq!(
+        yielded + &processor.finish(Some(FinishReason::Stop)),
+        "Paris.\n\nIt is large.\nQ"
+    );
+}
+
+#[cfg(feature = "streaming")]
+#[tokio::test]
+async fn completions_post_processed() {
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::json(
+            r#"{"text":"  Paris.\n","reached_end":false}{"text":"\nEN","reached_end":true,"output_tokens":5}"#,
+        ))
+        .build();
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .stream(true)
+        .max_tokens(5u32)
+        .stop(vec!["END".into()])
+        .build()
+        .expect("completion request should build");
+    let post_process = PostProcess {
+        trim: true,
+        strip_partial_stop: true,
+        ..PostProcess::default()
+    };
+    let stream = client
+        .completions_post_processed(&Engine::GPTJ6B, &request, &post_process)
+        .await
+        .expect("canned answer should be accepted");
+    let texts: Vec<_> = futures::StreamExt::collect::<Vec<_>>(stream)
+        .await
+        .into_iter()
+        .map(|chunk| chunk.expect("canned chunks should parse").text)
+        .collect();
+    assert_eq!(texts, [vec!["Paris."], vec![""]]);
+}
+
+#[tokio::test]
+async fn complete_post_processed() {
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::json(
+            r#"{"text":"The EN","reached_end":true,"output_tokens":2}"#,
+        ))
+        .build();
+    let request = RequestBuilder::default()
+        .prompt("Say")
+        .max_tokens(5u32)
+        .stop(vec!["END".into()])
+        .build()
+        .expect("completion request should build");
+    let post_process = PostProcess {
+        strip_partial_stop: true,
+        ..PostProcess::default()
+    };
+    // the generation stopped before max_tokens, so it isn't cut short
+    let response = client
+        .complete_post_processed(&Engine::GPTJ6B, &request, &post_process)
+        .await
+        .expect("canned answer should be accepted");
+    assert_eq!(response.text, ["The EN"]);
+}
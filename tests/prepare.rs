@@ -0,0 +1,53 @@
+use elikoga_textsynth::{
+    completions::{Engine, RequestBuilder},
+    translate, RequestOptions, TextSynthClient, DEFAULT_USER_AGENT,
+};
+use reqwest::{header::HeaderValue, Method};
+
+#[test]
+fn prepare() {
+    let client = TextSynthClient::new_with_endpoint("secret-key", "http://127.0.0.1:8080/v1")
+        .with_options(&RequestOptions::new().request_id(HeaderValue::from_static("debug-1")));
+    let request = RequestBuilder::default()
+        .prompt("Hello")
+        .max_tokens(8_u32)
+        .build()
+        .expect("completion request should build");
+    let prepared = client
+        .prepare(&Engine::GPTJ6B, &request)
+        .expect("request should serialize");
+    assert_eq!(prepared.method, Method::POST);
+    assert_eq!(
+        prepared.url,
+        "http://127.0.0.1:8080/v1/engines/gptj_6B/completions"
+    );
+    assert_eq!(prepared.headers["authorization"], "Bearer [redacted]");
+    assert_eq!(prepared.headers["user-agent"], DEFAULT_USER_AGENT);
+    assert_eq!(prepared.headers["x-request-id"], "debug-1");
+    assert!(!format!("{:?}", prepared).contains("secret-key"));
+    assert_eq!(
+        prepared.body,
+        request.to_json().expect("request should serialize")
+    );
+    assert_eq!(prepared.body, r#"{"prompt":"Hello","max_tokens":8}"#);
+}
+
+#[test]
+fn prepare_local() {
+    let client = TextSynthClient::new_local("127.0.0.1");
+    let request = translate::RequestBuilder::default()
+        .text(vec!["Hello".into()])
+        .source_lang("en")
+        .target_lang("de")
+        .build()
+        .expect("translation request should build");
+    let prepared = client
+        .prepare(&translate::Engine::M2M10012B, &request)
+        .expect("request should serialize");
+    assert_eq!(
+        prepared.url,
+        "http://127.0.0.1:8080/v1/engines/m2m100_1_2B/translate"
+    );
+    assert!(!prepared.headers.contains_key("authorization"));
+    assert!(!prepared.headers.contains_key("x-request-id"));
+}
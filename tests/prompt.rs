@@ -0,0 +1,19 @@
+use elikoga_textsynth::{completions::RequestBuilder, prompt};
+
+#[test]
+fn prompt() {
+    let country = "France";
+    let prompt = prompt!(
+        "Q: What is the capital of {country}?\nA: {{answer}}",
+        country = country,
+    );
+    assert_eq!(
+        prompt.as_str(),
+        "Q: What is the capital of France?\nA: {answer}"
+    );
+    assert_eq!(prompt.to_string(), prompt.as_str());
+    RequestBuilder::default()
+        .prompt(prompt)
+        .build()
+        .expect("failed to build completion request");
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::serve_json;
+use elikoga_textsynth::{completions::Engine, ProxyConfig, TextSynthClient};
+
+#[test]
+fn no_proxy() {
+    let proxy = ProxyConfig::new("http://proxy:3128")
+        .no_proxy("internal.example.com")
+        .no_proxy(".local");
+    assert!(proxy.proxies("api.textsynth.com"));
+    assert!(!proxy.proxies("internal.example.com"));
+    assert!(!proxy.proxies("api.internal.example.com"));
+    assert!(proxy.proxies("notinternal.example.com"));
+    assert!(!proxy.proxies("printer.local"));
+}
+
+#[tokio::test]
+async fn proxy() {
+    // act as the proxy for a single request
+    let (port, requests) = serve_json([("200 OK", r#"{"tokens":[1]}"#)]);
+    // the api host doesn't exist, only the proxy can reach it
+    let client = TextSynthClient::builder("key")
+        .endpoint("http://textsynth.invalid/v1")
+        .proxy(ProxyConfig::new(&format!("http://127.0.0.1:{}", port)).basic_auth("user", "secret"))
+        .build();
+    client
+        .tokenize_str(&Engine::GPTJ6B, "Hello")
+        .await
+        .expect("tokenize request should succeed");
+    let received = requests.recv().expect("no request");
+    assert!(received.starts_with("POST http://textsynth.invalid/v1/engines/gptj_6B/tokenize"));
+    assert!(received
+        .to_lowercase()
+        .contains("proxy-authorization: basic dxnlcjpzzwnyzxq="));
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "python")]
+
+mod common;
+
+use common::{endpoint, serve_json};
+use elikoga_textsynth::python;
+use pyo3::{prelude::*, py_run, types::PyModule};
+
+/// Run `code` with the bindings imported as `ts` and the endpoint of the api
+/// on the local `port` as `endpoint`
+fn run_python(port: u16, code: &str) {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let ts = PyModule::new(py, "elikoga_textsynth").expect("failed to create the module");
+        python::register(&ts).expect("failed to add the classes");
+        let endpoint = endpoint(port);
+        py_run!(py, ts endpoint, code);
+    });
+}
+
+#[test]
+fn python_builders() {
+    run_python(
+        common::closed_port(),
+        r#"
+import json
+
+request = ts.CompletionRequestBuilder().prompt("Hi").max_tokens(5).stop(["\n"]).build()
+assert json.loads(request.to_json()) == {"prompt": "Hi", "max_tokens": 5, "stop": ["\n"]}
+
+request = ts.TranslateRequestBuilder().text(["Hello"]).source_lang("en").target_lang("fr").num_beams(2).build()
+assert json.loads(request.to_json()) == {
+    "text": ["Hello"], "source_lang": "en", "target_lang": "fr", "num_beams": 2
+}
+
+try:
+    ts.CompletionRequestBuilder().max_tokens(5).build()
+    raise AssertionError("a request needs a prompt")
+except ValueError:
+    pass
+"#,
+    );
+}
+
+#[test]
+fn python_client() {
+    let (port, requests) = serve_json([
+        ("200 OK", r#"{"text":" Paris.","reached_end":true}"#),
+        (
+            "200 OK",
+            r#"{"translations":[{"text":"Bonjour","detected_source_lang":"en"}],"input_tokens":2,"output_tokens":3}"#,
+        ),
+        (
+            "200 OK",
+            r#"{"text":" Paris","reached_end":false}{"text":".","reached_end":true}"#,
+        ),
+    ]);
+    run_python(
+        port,
+        r#"
+import asyncio
+
+client = ts.Client("key", endpoint)
+request = ts.CompletionRequestBuilder().prompt("The capital of France is").build()
+assert client.complete_request("gptj_6B", request) == " Paris."
+
+request = ts.TranslateRequestBuilder().text(["Hello"]).source_lang("en").target_lang("fr").build()
+assert client.translate_request("m2m100_1_2B", request) == ["Bonjour"]
+
+async def collect(texts):
+    return [text async for text in texts]
+
+request = ts.CompletionRequestBuilder().prompt("The capital of France is").stream(True).build()
+assert asyncio.run(collect(client.astream("gptj_6B", request))) == [" Paris", "."]
+"#,
+    );
+    let paths: Vec<_> = requests
+        .try_iter()
+        .map(|request| request.lines().next().unwrap_or_default().to_string())
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            "POST /v1/engines/gptj_6B/completions HTTP/1.1",
+            "POST /v1/engines/m2m100_1_2B/translate HTTP/1.1",
+            "POST /v1/engines/gptj_6B/completions HTTP/1.1",
+        ]
+    );
+}
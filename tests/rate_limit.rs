@@ -0,0 +1,28 @@
+#![cfg(feature = "rate-limit")]
+
+use std::time::{Duration, Instant};
+
+use elikoga_textsynth::{
+    completions::Engine, rate_limit::RateLimit, transport::MockTransport, TextSynthClient,
+};
+
+#[tokio::test]
+async fn rate_limit() {
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::json(r#"{"tokens":[1]}"#))
+        .rate_limit(RateLimit {
+            burst: 2,
+            max_in_flight: Some(1),
+            ..RateLimit::new(20.0)
+        })
+        .build();
+    let start = Instant::now();
+    for _ in 0..4 {
+        client
+            .tokenize_str(&Engine::GPTJ6B, "Hello")
+            .await
+            .expect("tokenize request should succeed");
+    }
+    // the burst covers two requests, the other two wait 50ms each
+    assert!(start.elapsed() >= Duration::from_millis(90));
+}
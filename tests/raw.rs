@@ -0,0 +1,28 @@
+use elikoga_textsynth::{completions::Engine, TextSynthClient};
+use serde_json::json;
+
+#[tokio::test]
+async fn raw() {
+    // get API Key from env
+    let api_key = std::env::var("TEXT_SYNTH_API_KEY").expect("TEXT_SYNTH_API_KEY not set");
+    let client = TextSynthClient::new(&api_key);
+    let response = client
+        .tokenize_raw(
+            &Engine::GPTJ6B,
+            &json!({ "text": "The quick brown fox jumps over the lazy dog" }),
+        )
+        .await
+        .expect("tokenize request should succeed");
+    assert_eq!(
+        response["tokens"],
+        json!([464, 2068, 7586, 21831, 18045, 625, 262, 16931, 3290])
+    );
+    let response = client
+        .completions_raw(
+            &Engine::GPTJ6B,
+            &json!({ "prompt": "The capital of France is", "max_tokens": 4, "stream": true }),
+        )
+        .await
+        .expect("completion request should succeed");
+    assert!(response["text"].is_string());
+}
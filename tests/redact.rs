@@ -0,0 +1,39 @@
+use elikoga_textsynth::{
+    completions::{self, logprob},
+    redact::Redacted,
+    tokenize,
+};
+
+#[test]
+fn clone_and_debug() {
+    let mut builder = completions::RequestBuilder::default();
+    builder.prompt("Once upon a time").max_tokens(10_u32);
+    let request = builder.clone().build().expect("request should build");
+    assert!(format!("{:?}", builder).contains("Once upon a time"));
+    assert!(format!("{:?}", request.clone()).contains("Once upon a time"));
+
+    let request = tokenize::RequestBuilder::default()
+        .text("Hello")
+        .build()
+        .expect("request should build");
+    assert!(format!("{:?}", request.clone()).contains("Hello"));
+    let builder = logprob::RequestBuilder::default();
+    assert!(format!("{:?}", builder.clone()).starts_with("RequestBuilder"));
+}
+
+#[test]
+fn redacted() {
+    let request = completions::RequestBuilder::default()
+        .prompt("Once upon a time, in a land far away")
+        .stop(vec!["\n".into()])
+        .build()
+        .expect("request should build");
+    assert_eq!(
+        format!("{:?}", Redacted::new(&request, 9)),
+        r#"{"prompt":"Once upon… (27 more characters)","stop":["\n"]}"#
+    );
+    assert_eq!(
+        Redacted::new(&request, 100).to_string(),
+        r#"{"prompt":"Once upon a time, in a land far away","stop":["\n"]}"#
+    );
+}
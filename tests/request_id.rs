@@ -0,0 +1,61 @@
+use std::sync::{Arc, Mutex};
+
+use elikoga_textsynth::{
+    completions::Engine, tokenize::RequestBuilder, transport::MockTransport, Error, RequestOptions,
+    TextSynthClient, REQUEST_ID_HEADER,
+};
+use reqwest::{header::HeaderValue, StatusCode};
+
+#[tokio::test]
+async fn request_id() {
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let seen = sent.clone();
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(move |request| {
+            let id = request.headers()[REQUEST_ID_HEADER]
+                .to_str()
+                .expect("ids are text")
+                .to_string();
+            seen.lock().expect("lock poisoned").push(id);
+            match request.url().path() {
+                "/v1/engines/gptj_6B/tokenize" => {
+                    (StatusCode::OK, r#"{"tokens":[1,2,3]}"#.to_string())
+                }
+                _ => (
+                    StatusCode::PAYMENT_REQUIRED,
+                    r#"{"error":"not enough credits"}"#.to_string(),
+                ),
+            }
+        }))
+        .build();
+    let request = RequestBuilder::default()
+        .text("Hello")
+        .build()
+        .expect("tokenize request should build");
+
+    let first = client
+        .tokenize_with_meta(&Engine::GPTJ6B, &request)
+        .await
+        .expect("canned answer should parse");
+    let second = client
+        .tokenize_with_meta(&Engine::GPTJ6B, &request)
+        .await
+        .expect("canned answer should parse");
+    {
+        let sent = sent.lock().expect("lock poisoned");
+        assert_eq!(first.request_id(), Some(sent[0].as_str()));
+        assert_eq!(second.request_id(), Some(sent[1].as_str()));
+        assert_ne!(sent[0], sent[1]);
+    }
+
+    let options = RequestOptions::new().request_id(HeaderValue::from_static("my-request"));
+    let error = Error::from(
+        client
+            .with_options(&options)
+            .tokenize_str(&Engine::GPTNeoX20B, "Hello")
+            .await
+            .expect_err("the api answered with an error"),
+    );
+    assert_eq!(sent.lock().expect("lock poisoned")[2], "my-request");
+    assert_eq!(error.request_id(), Some("my-request"));
+}
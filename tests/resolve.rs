@@ -0,0 +1,28 @@
+mod common;
+
+use std::net::{IpAddr, Ipv4Addr};
+
+use common::serve_json;
+use elikoga_textsynth::{completions::Engine, tokenize::RequestBuilder, TextSynthClient};
+
+#[tokio::test]
+async fn resolve() {
+    // answer a single tokenize request on a local port
+    let (port, requests) = serve_json([("200 OK", r#"{"tokens":[1,2,3]}"#)]);
+    // the hostname doesn't exist, it only resolves through the override
+    let client = TextSynthClient::builder("key")
+        .endpoint(&format!("http://textsynth.invalid:{}/v1", port))
+        .resolve("textsynth.invalid", IpAddr::V4(Ipv4Addr::LOCALHOST))
+        .build();
+    let request = RequestBuilder::default()
+        .text("The quick brown fox")
+        .build()
+        .expect("tokenize request should build");
+    let response = client
+        .tokenize(&Engine::GPTJ6B, &request)
+        .await
+        .expect("tokenize request should succeed");
+    assert_eq!(response.tokens, [1, 2, 3]);
+    let received = requests.recv().expect("no request");
+    assert!(received.starts_with("POST /v1/engines/gptj_6B/tokenize"));
+}
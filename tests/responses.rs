@@ -0,0 +1,41 @@
+use elikoga_textsynth::{completions, completions::logprob, tokenize, translate};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Parse `json`, returning the response after a round trip through its
+/// serialization
+fn round_trip<T>(json: &str) -> T
+where
+    T: Serialize + DeserializeOwned + Clone + PartialEq + std::fmt::Debug,
+{
+    let response: T = serde_json::from_str(json).expect("response should parse");
+    let serialized = serde_json::to_string(&response).expect("response should serialize");
+    let parsed: T = serde_json::from_str(&serialized).expect("serialized response should parse");
+    assert_eq!(parsed, response.clone());
+    parsed
+}
+
+#[test]
+fn responses() {
+    let chunk: completions::ResponseChunk = round_trip(
+        r#"{"text":" Paris.","reached_end":true,"input_tokens":5,"output_tokens":2,
+            "logprobs":[{"token":" Paris","logprob":-0.5,"top_logprobs":[{"token":" Lyon","logprob":-2.0}]}]}"#,
+    );
+    assert_eq!(chunk.text, [" Paris."]);
+    let chunk: completions::ResponseChunk = round_trip(r#"{"text":"a","reached_end":false}"#);
+    assert_eq!(
+        serde_json::to_value(chunk).expect("response should serialize"),
+        serde_json::json!({"text": ["a"], "reached_end": false})
+    );
+
+    let translation: translate::Response = round_trip(
+        r#"{"translations":[{"text":"Hallo Welt !","detected_source_lang":"en"}],"input_tokens":4,"output_tokens":5}"#,
+    );
+    assert_eq!(translation.translations[0].text, "Hallo Welt !");
+
+    let tokens: tokenize::Response = round_trip(r#"{"tokens":[1,2,3]}"#);
+    assert_eq!(tokens.tokens, [1, 2, 3]);
+
+    let logprob: logprob::Response =
+        round_trip(r#"{"logprob":-1.5,"num_tokens":2,"is_greedy":false,"input_tokens":4}"#);
+    assert_eq!(logprob.num_tokens, 2);
+}
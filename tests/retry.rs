@@ -0,0 +1,126 @@
+#![cfg(feature = "retry")]
+
+mod common;
+
+use std::{sync::Mutex, time::Duration};
+
+use elikoga_textsynth::{
+    completions::Engine, retry::RetryPolicy, tokenize, transport::MockTransport, TextSynthClient,
+};
+use reqwest::StatusCode;
+
+fn policy(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        base_delay: Duration::from_millis(10),
+        ..RetryPolicy::new(max_attempts)
+    }
+}
+
+#[tokio::test]
+async fn retry() {
+    let responses = Mutex::new(
+        [
+            (StatusCode::SERVICE_UNAVAILABLE, r#"{"error":"busy"}"#),
+            (StatusCode::BAD_GATEWAY, r#"{"error":"busy"}"#),
+            (StatusCode::OK, r#"{"tokens":[1,2,3]}"#),
+        ]
+        .into_iter(),
+    );
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(move |_| {
+            let (status, body) = responses
+                .lock()
+                .expect("lock poisoned")
+                .next()
+                .expect("unexpected request");
+            (status, body.to_string())
+        }))
+        .retry(policy(3))
+        .build();
+    let tokens = client
+        .tokenize_str(&Engine::GPTJ6B, "Hello")
+        .await
+        .expect("the third attempt should succeed");
+    assert_eq!(tokens, [1, 2, 3]);
+
+    // client errors aren't retried
+    let responses = Mutex::new(
+        [
+            (StatusCode::BAD_REQUEST, r#"{"error":"bad request"}"#),
+            (StatusCode::OK, r#"{"tokens":[1,2,3]}"#),
+        ]
+        .into_iter(),
+    );
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(move |_| {
+            let (status, body) = responses
+                .lock()
+                .expect("lock poisoned")
+                .next()
+                .expect("unexpected request");
+            (status, body.to_string())
+        }))
+        .retry(policy(3))
+        .build();
+    client
+        .tokenize_str(&Engine::GPTJ6B, "Hello")
+        .await
+        .expect_err("bad requests aren't retried");
+}
+
+#[tokio::test]
+async fn rate_limited() {
+    // the status line is followed by the retry-after header, which the mock
+    // transport can't answer with
+    let (port, _) = common::serve_json([
+        (
+            "429 Too Many Requests\r\nretry-after: 0",
+            r#"{"error":"too many requests"}"#,
+        ),
+        ("200 OK", r#"{"tokens":[1,2,3]}"#),
+    ]);
+    let client = TextSynthClient::builder("key")
+        .endpoint(&common::endpoint(port))
+        .retry(policy(2))
+        .build();
+    let tokens = client
+        .tokenize_str(&Engine::GPTJ6B, "Hello")
+        .await
+        .expect("the retry should succeed");
+    assert_eq!(tokens, [1, 2, 3]);
+
+    // waits longer than the maximum delay aren't retried
+    let (port, _) = common::serve_json([(
+        "429 Too Many Requests\r\nretry-after: 120",
+        r#"{"error":"too many requests"}"#,
+    )]);
+    let client = TextSynthClient::builder("key")
+        .endpoint(&common::endpoint(port))
+        .retry(policy(2))
+        .build();
+    let error = client
+        .tokenize_str(&Engine::GPTJ6B, "Hello")
+        .await
+        .expect_err("the request is rate limited");
+    match error {
+        tokenize::Error::RateLimited(error) => {
+            assert_eq!(error.retry_after, Some(Duration::from_secs(120)));
+            assert_eq!(error.message, "too many requests");
+        }
+        error => panic!("unexpected error {:?}", error),
+    }
+}
+
+#[test]
+fn delay() {
+    let policy = RetryPolicy {
+        jitter: 0.0,
+        ..RetryPolicy::new(5)
+    };
+    assert_eq!(policy.delay(1), Duration::from_millis(500));
+    assert_eq!(policy.delay(3), Duration::from_secs(2));
+    assert_eq!(policy.delay(20), Duration::from_secs(30));
+    let policy = RetryPolicy::new(5);
+    assert!(policy.delay(2) <= Duration::from_secs(1));
+    assert!(policy.delay(2) >= Duration::from_millis(500));
+}
@@ -0,0 +1,105 @@
+use std::sync::{Arc, Mutex};
+
+use elikoga_textsynth::{
+    completions::{score::Score, Engine},
+    transport::MockTransport,
+    TextSynthClient,
+};
+use reqwest::StatusCode;
+
+/// A client answering logprob requests with a log probability of -1 per word
+/// of the continuation, recording the request bodies in `requests`
+fn client(requests: Arc<Mutex<Vec<serde_json::Value>>>) -> TextSynthClient {
+    TextSynthClient::builder("key")
+        .transport(MockTransport::new(move |request| {
+            let body = request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .expect("request should have a body");
+            let request: serde_json::Value =
+                serde_json::from_slice(body).expect("body should be json");
+            let words = request["continuation"]
+                .as_str()
+                .expect("no continuation")
+                .split_whitespace()
+                .count();
+            requests.lock().expect("lock poisoned").push(request);
+            let body = serde_json::json!({
+                "logprob": -(words as f64),
+                "num_tokens": words,
+                "is_greedy": false,
+                "input_tokens": words,
+            });
+            (StatusCode::OK, body.to_string())
+        }))
+        .build()
+}
+
+#[test]
+fn score() {
+    let score = Score {
+        logprob: -4.0,
+        num_tokens: 2,
+    } + Score {
+        logprob: -2.0,
+        num_tokens: 4,
+    };
+    assert_eq!(score.average_logprob(), -1.0);
+    assert!((score.perplexity() - std::f64::consts::E).abs() < 1e-9);
+    assert_eq!(Score::default().perplexity(), 1.0);
+}
+
+#[tokio::test]
+async fn perplexity() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let client = client(requests.clone());
+
+    let score = client
+        .perplexity(&Engine::GPTJ6B, "The quick brown fox")
+        .await
+        .expect("logprob request should succeed");
+    assert_eq!(score.num_tokens, 4);
+    assert_eq!(score.logprob, -4.0);
+    let request = requests.lock().expect("lock poisoned").remove(0);
+    assert_eq!(request["context"], "");
+    assert_eq!(request["continuation"], "The quick brown fox");
+
+    // long texts are scored in chunks, each following the one before
+    let text = "word ".repeat(500);
+    let score = client
+        .perplexity(&Engine::GPTJ6B, &text)
+        .await
+        .expect("logprob requests should succeed");
+    assert_eq!(score.num_tokens, 500);
+    let chunks = requests.lock().expect("lock poisoned").clone();
+    assert!(chunks.len() > 1);
+    let mut scored = String::new();
+    for pair in chunks.windows(2) {
+        assert_eq!(pair[1]["context"], pair[0]["continuation"]);
+    }
+    for chunk in &chunks {
+        let continuation = chunk["continuation"].as_str().expect("no continuation");
+        assert!(continuation.chars().count() <= 1000);
+        scored.push_str(continuation);
+    }
+    assert_eq!(scored, text);
+}
+
+#[cfg(feature = "batch")]
+#[tokio::test]
+async fn score_all() {
+    let client = client(Arc::default());
+    let pairs = [
+        ("The review was", " great"),
+        ("The review was", " not good at all"),
+        ("The review was", ""),
+        ("The review was", " bad"),
+    ];
+    let scores = client
+        .score_all(&Engine::GPTJ6B, pairs, 2)
+        .await
+        .into_result()
+        .expect("logprob requests should succeed");
+    let tokens: Vec<_> = scores.iter().map(|score| score.num_tokens).collect();
+    assert_eq!(tokens, [1, 4, 0, 1]);
+}
@@ -0,0 +1,35 @@
+use elikoga_textsynth::completions::{
+    select::{Candidate, EngineSelector},
+    Engine,
+};
+
+#[test]
+fn select() {
+    let selector = EngineSelector::default();
+    assert_eq!(selector.select(500, 200, "en"), Some(&Engine::GPTJ6B));
+    assert_eq!(selector.select(500, 200, "fr"), Some(&Engine::Boris6B));
+    // too long for boris, falls back to GPT-J
+    assert_eq!(selector.select(1500, 200, "fr"), Some(&Engine::GPTJ6B));
+    assert_eq!(selector.select(2000, 200, "en"), None);
+
+    let selector = EngineSelector::new(vec![
+        Candidate {
+            engine: Engine::GPTNeoX20B,
+            context_length: 1024,
+            cost: 20.0,
+            languages: Vec::new(),
+        },
+        Candidate {
+            engine: Engine::GPTJ6B,
+            context_length: 2048,
+            cost: 6.0,
+            languages: Vec::new(),
+        },
+    ]);
+    assert_eq!(selector.select(500, 200, "en"), Some(&Engine::GPTNeoX20B));
+    assert_eq!(selector.select(1500, 200, "en"), Some(&Engine::GPTJ6B));
+    assert_eq!(
+        selector.by_cost().select(500, 200, "en"),
+        Some(&Engine::GPTJ6B)
+    );
+}
@@ -0,0 +1,104 @@
+use std::sync::{Arc, Mutex};
+
+use elikoga_textsynth::{
+    completions::{self, Engine},
+    transport::MockTransport,
+    TextSynthClient,
+};
+use reqwest::StatusCode;
+
+#[tokio::test]
+async fn session() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let seen = requests.clone();
+    let replies = Mutex::new([" Hi there!", " Paris.\n"].into_iter());
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(move |request| {
+            let body = request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .expect("request should have a body");
+            let body: serde_json::Value =
+                serde_json::from_slice(body).expect("body should be json");
+            seen.lock().expect("lock poisoned").push(body);
+            let reply = replies
+                .lock()
+                .expect("lock poisoned")
+                .next()
+                .expect("unexpected request");
+            let body = serde_json::json!({ "text": reply, "reached_end": true });
+            (StatusCode::OK, body.to_string())
+        }))
+        .build();
+    let mut session = client
+        .session(Engine::GPTJ6B)
+        .prefix("A helpful assistant.")
+        .max_tokens(32)
+        .stop(["\n\n"]);
+
+    let reply = session
+        .say("Hello")
+        .await
+        .expect("first turn should succeed");
+    assert_eq!(reply, "Hi there!");
+    let request = requests.lock().expect("lock poisoned")[0].clone();
+    assert_eq!(
+        request["prompt"],
+        "A helpful assistant.\nUser: Hello\nAssistant:"
+    );
+    assert_eq!(request["max_tokens"], 32);
+    assert_eq!(request["stop"], serde_json::json!(["\nUser:", "\n\n"]));
+
+    let reply = session
+        .say("What is the capital of France?")
+        .await
+        .expect("second turn should succeed");
+    assert_eq!(reply, "Paris.");
+    let request = requests.lock().expect("lock poisoned")[1].clone();
+    assert_eq!(
+        request["prompt"],
+        "A helpful assistant.\nUser: Hello\nAssistant: Hi there!\nUser: What is the capital of France?\nAssistant:"
+    );
+    assert_eq!(session.transcript().len(), 2);
+    assert_eq!(session.transcript()[1].reply, "Paris.");
+
+    session.clear();
+    assert_eq!(
+        session.prompt("Hi"),
+        "A helpful assistant.\nUser: Hi\nAssistant:"
+    );
+}
+
+#[tokio::test]
+async fn session_invalid() {
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(|_| {
+            panic!("invalid requests aren't sent");
+        }))
+        .build();
+    let mut session = client
+        .session(Engine::GPTJ6B)
+        .stop(["a", "b", "c", "d", "e"]);
+    let error = session.say("Hello").await.expect_err("6 stop strings");
+    assert!(
+        matches!(error, completions::Error::BuilderError(ref error) if error.field() == "stop")
+    );
+    assert!(session.transcript().is_empty());
+    let error = client
+        .session(Engine::GPTJ6B)
+        .try_stop(["a", "b", "c", "d", "e"])
+        .err()
+        .expect("6 stop strings");
+    assert_eq!(error.field(), "stop");
+    assert!(client
+        .session(Engine::GPTJ6B)
+        .try_stop(["a", "b", "c", "d"])
+        .is_ok());
+
+    let mut session = client.session(Engine::GPTJ6B).max_tokens(0);
+    let error = session.say("Hello").await.expect_err("no tokens");
+    assert!(
+        matches!(error, completions::Error::BuilderError(ref error) if error.field() == "max_tokens")
+    );
+    assert!(client.session(Engine::GPTJ6B).try_max_tokens(0).is_err());
+}
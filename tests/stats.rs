@@ -0,0 +1,50 @@
+#![cfg(feature = "streaming")]
+
+use std::time::Duration;
+
+use elikoga_textsynth::{
+    completions::{stats::Stats, Engine, RequestBuilder},
+    TextSynthClient,
+};
+use futures::StreamExt;
+
+#[test]
+fn tokens_per_second() {
+    let stats = Stats {
+        chunks: 10,
+        output_tokens: 20,
+        elapsed: Duration::from_secs(4),
+        reached_end: true,
+    };
+    assert_eq!(stats.tokens_per_second(), 5.0);
+    let stats = Stats {
+        elapsed: Duration::ZERO,
+        ..stats
+    };
+    assert_eq!(stats.tokens_per_second(), 0.0);
+}
+
+#[tokio::test]
+async fn stats() {
+    // get API Key from env
+    let api_key = std::env::var("TEXT_SYNTH_API_KEY").expect("TEXT_SYNTH_API_KEY not set");
+    let client = TextSynthClient::new(&api_key);
+    let request = RequestBuilder::default()
+        .prompt("Once upon a time")
+        .max_tokens(8_u32)
+        .stream(true)
+        .build()
+        .expect("failed to build completion request");
+    let (mut response, stats) = client
+        .completions_with_stats(&Engine::GPTJ6B, &request)
+        .await
+        .expect("failed to complete");
+    while let Some(chunk) = response.next().await {
+        chunk.expect("failed to stream");
+        assert!(stats.snapshot().chunks > 0);
+    }
+    let snapshot = stats.snapshot();
+    assert!(snapshot.reached_end);
+    assert_eq!(snapshot.output_tokens, 8);
+    assert_eq!(stats.snapshot().elapsed, snapshot.elapsed);
+}
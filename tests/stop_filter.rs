@@ -0,0 +1,65 @@
+#![cfg(feature = "streaming")]
+
+use elikoga_textsynth::{
+    completions::{
+        collect_text,
+        stop_filter::{filter_stops, StopFilter},
+        Engine, RequestBuilder, ResponseChunk,
+    },
+    transport::MockTransport,
+    TextSynthClient,
+};
+
+#[test]
+fn stop_filter() {
+    let mut filter = StopFilter::new(["\n\n", "END"]);
+    assert_eq!(filter.push("Hello E"), "Hello ");
+    assert_eq!(filter.push("N"), "");
+    assert_eq!(filter.push("joy\n"), "ENjoy");
+    assert_eq!(filter.push("\nmore"), "");
+    assert!(filter.is_stopped());
+    assert_eq!(filter.push("ignored"), "");
+
+    let mut filter = StopFilter::new(["END"]);
+    assert_eq!(filter.push("héllo wörld EN"), "héllo wörld ");
+    filter.finish();
+    assert_eq!(filter.push("x"), "x");
+}
+
+#[tokio::test]
+async fn filter_stream() {
+    let chunks = [
+        r#"{"text":["The E","A"],"reached_end":false}"#,
+        r#"{"text":["ND is near","END"],"reached_end":false}"#,
+        r#"{"text":["","B"],"reached_end":true}"#,
+    ]
+    .map(|chunk| serde_json::from_str::<ResponseChunk>(chunk).expect("chunk should parse"));
+    let stream = futures::stream::iter(chunks.map(Ok));
+    let texts: Vec<_> = futures::StreamExt::collect::<Vec<_>>(filter_stops(stream, ["END"]))
+        .await
+        .into_iter()
+        .map(|chunk| chunk.expect("chunks are ok").text)
+        .collect();
+    assert_eq!(texts, [vec!["The ", "A"], vec!["", ""], vec!["", ""]]);
+
+    // the last chunk holds the start of the stop string
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::json(
+            r#"{"text":" Paris","reached_end":false}{"text":".\n","reached_end":true}"#,
+        ))
+        .build();
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .stream(true)
+        .stop(vec!["\n\n".into()])
+        .build()
+        .expect("completion request should build");
+    let stream = client
+        .completions_stop_filtered(&Engine::GPTJ6B, &request)
+        .await
+        .expect("canned answer should be accepted");
+    let collected = collect_text(stream)
+        .await
+        .expect("canned chunks should parse");
+    assert_eq!(collected.text, " Paris.");
+}
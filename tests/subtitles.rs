@@ -0,0 +1,40 @@
+use elikoga_textsynth::transcript::Response;
+
+fn response() -> Response {
+    serde_json::from_str(
+        r#"{"text":"Hello world. How are you?","language":"en","duration":3725.5,"segments":[
+            {"id":0,"start":0.0,"end":1.5,"text":" Hello world.","words":[
+                {"word":" Hello","start":0.0,"end":0.6},{"word":" world.","start":0.7,"end":1.5}]},
+            {"id":1,"start":2.0,"end":2.1,"text":" "},
+            {"id":2,"start":3723.25,"end":3725.5,"text":" How are you?"}]}"#,
+    )
+    .expect("response should parse")
+}
+
+#[test]
+fn words() {
+    let response = response();
+    let words = &response.segments[0].words;
+    assert_eq!(words[1].word, " world.");
+    assert_eq!(words[1].start, 0.7);
+    assert!(response.segments[2].words.is_empty());
+}
+
+#[test]
+fn srt() {
+    assert_eq!(
+        response().to_srt(),
+        "1\n00:00:00,000 --> 00:00:01,500\nHello world.\n\n\
+         2\n01:02:03,250 --> 01:02:05,500\nHow are you?\n\n"
+    );
+}
+
+#[test]
+fn vtt() {
+    assert_eq!(
+        response().to_vtt(),
+        "WEBVTT\n\n\
+         00:00:00.000 --> 00:00:01.500\nHello world.\n\n\
+         01:02:03.250 --> 01:02:05.500\nHow are you?\n\n"
+    );
+}
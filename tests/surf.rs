@@ -0,0 +1,72 @@
+#![cfg(feature = "surf")]
+
+mod common;
+
+use std::io::Write;
+
+use common::{endpoint, read_request, serve, serve_json};
+use elikoga_textsynth::{
+    completions, surf::SurfClient, transport::HttpClientTransport, TextSynthClient,
+};
+use futures::{executor::block_on, StreamExt};
+
+fn client(port: u16) -> TextSynthClient {
+    TextSynthClient::builder("key")
+        .endpoint(&endpoint(port))
+        .transport(HttpClientTransport::new(SurfClient::new()))
+        .build()
+}
+
+#[test]
+fn surf_complete() {
+    let (port, requests) = serve_json([(
+        "200 OK",
+        r#"{"text":" Paris.","reached_end":true,"input_tokens":5,"output_tokens":2}"#,
+    )]);
+    let request = completions::RequestBuilder::default()
+        .prompt("The capital of France is")
+        .build()
+        .expect("failed to build completion request");
+    let response = block_on(client(port).complete(&completions::Engine::GPTJ6B, &request))
+        .expect("failed to complete");
+    assert_eq!(response.text, [" Paris."]);
+    let request = requests.recv().expect("no request received").to_lowercase();
+    assert!(request.starts_with("post /v1/engines/gptj_6b/completions "));
+    assert!(request.contains("authorization: bearer key"));
+}
+
+#[test]
+fn surf_stream() {
+    let chunks = [
+        "{\"text\":\" Paris\",\"reached_end\":false}\n\n",
+        "{\"text\":\".\",\"reached_end\":true}",
+    ];
+    let mut response = String::from(
+        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ntransfer-encoding: chunked\r\n\r\n",
+    );
+    for chunk in chunks {
+        response += &format!("{:x}\r\n{}\r\n", chunk.len(), chunk);
+    }
+    response += "0\r\n\r\n";
+    let port = serve(move |mut stream| {
+        read_request(&mut stream);
+        stream
+            .write_all(response.as_bytes())
+            .expect("failed to write");
+    });
+    let request = completions::RequestBuilder::default()
+        .prompt("The capital of France is")
+        .stream(true)
+        .build()
+        .expect("failed to build completion request");
+    let texts: Vec<_> = block_on(async {
+        client(port)
+            .completions(&completions::Engine::GPTJ6B, &request)
+            .await
+            .expect("failed to complete")
+            .map(|chunk| chunk.expect("failed to parse chunk").text.concat())
+            .collect()
+            .await
+    });
+    assert_eq!(texts, [" Paris", "."]);
+}
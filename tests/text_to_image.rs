@@ -0,0 +1,97 @@
+use elikoga_textsynth::{
+    text_to_image::{Engine, RequestBuilder, Response},
+    TextSynthClient,
+};
+
+#[test]
+fn text_to_image_request() {
+    let mut request = RequestBuilder::default();
+    request.prompt("an astronaut riding a horse");
+    assert!(request.try_width(500_u32).is_err());
+    assert!(request.try_image_count(5_u32).is_err());
+    request
+        .try_width(768_u32)
+        .expect("768 is a valid width")
+        .negative_prompt("blurry")
+        .seed(42_u32);
+    let json = serde_json::to_value(request.build().expect("request should build"))
+        .expect("request should serialize");
+    assert_eq!(json["width"], 768);
+    assert_eq!(json["negative_prompt"], "blurry");
+
+    let response: Response =
+        serde_json::from_str(r#"{"images":[{"data":"/9j/4A=="}]}"#).expect("response should parse");
+    assert_eq!(response.images[0].data, [0xff, 0xd8, 0xff, 0xe0]);
+}
+
+#[test]
+fn save_image() {
+    let response: Response =
+        serde_json::from_str(r#"{"images":[{"data":"/9j/4A=="}]}"#).expect("response should parse");
+    let path = std::env::temp_dir().join(format!("textsynth-{}.jpg", std::process::id()));
+    response.images[0].save(&path).expect("image should save");
+    let saved = std::fs::read(&path).expect("saved image should read");
+    std::fs::remove_file(&path).expect("saved image should be removed");
+    assert_eq!(saved, response.images[0].as_bytes());
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn decode_image() {
+    let mut jpeg = std::io::Cursor::new(Vec::new());
+    image::DynamicImage::new_rgb8(2, 3)
+        .write_to(&mut jpeg, image::ImageOutputFormat::Jpeg(90))
+        .expect("image should encode");
+    let json = serde_json::json!({"images": [{"data": base64::encode(jpeg.into_inner())}]});
+    let response: Response = serde_json::from_value(json).expect("response should parse");
+    let image = response.images[0]
+        .to_dynamic_image()
+        .expect("image should decode");
+    assert_eq!((image.width(), image.height()), (2, 3));
+    let png = response.images[0]
+        .to_png_bytes()
+        .expect("image should encode as png");
+    assert!(png.starts_with(b"\x89PNG"));
+}
+
+#[test]
+fn text_to_image_validation() {
+    let mut request = RequestBuilder::default();
+    request.prompt("an astronaut riding a horse");
+    assert!(request.try_timesteps(0_u32).is_err());
+    assert!(request.try_guidance_scale(-1.0).is_err());
+    request
+        .try_timesteps(30_u32)
+        .and_then(|request| request.try_guidance_scale(1.0))
+        .expect("valid values should be accepted")
+        .negative_prompt("blurry");
+    let error = request.build().expect_err("negative prompt needs guidance");
+    assert_eq!(error.field(), "negative_prompt");
+    request.guidance_scale(7.5);
+    request.build().expect("request should build");
+
+    let error = RequestBuilder::default()
+        .prompt(" ")
+        .build()
+        .expect_err("prompt is empty");
+    assert_eq!(error.field(), "prompt");
+}
+
+#[tokio::test]
+async fn text_to_image() {
+    // get API Key from env
+    let api_key = std::env::var("TEXT_SYNTH_API_KEY").expect("TEXT_SYNTH_API_KEY not set");
+    let client = TextSynthClient::new(&api_key);
+    let request = RequestBuilder::default()
+        .prompt("an astronaut riding a horse")
+        .image_count(1_u32)
+        .timesteps(10_u32)
+        .build()
+        .expect("failed to build text to image request");
+    let response = client
+        .text_to_image(&Engine::StableDiffusion, &request)
+        .await
+        .expect("failed to generate image");
+    assert_eq!(response.images.len(), 1);
+    assert!(response.images[0].data.starts_with(&[0xff, 0xd8]));
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{endpoint, read_request, serve, wait_closed};
+use elikoga_textsynth::{completions, RequestOptions, TextSynthClient};
+
+#[tokio::test]
+async fn request_timeout() {
+    // the server reads the request and never answers
+    let port = serve(|mut stream| {
+        read_request(&mut stream);
+        wait_closed(&mut stream);
+    });
+    let client = TextSynthClient::builder("key")
+        .endpoint(&endpoint(port))
+        .request_timeout(Duration::from_millis(100))
+        .build();
+    let error = client
+        .tokenize_str(&completions::Engine::GPTJ6B, "Hello")
+        .await
+        .expect_err("the server never answers");
+    match error {
+        elikoga_textsynth::tokenize::Error::RequestError(error) => assert!(error.is_timeout()),
+        error => panic!("unexpected error {:?}", error),
+    }
+}
+
+#[cfg(feature = "streaming")]
+#[tokio::test]
+async fn stream_read_timeout() {
+    use futures::StreamExt;
+
+    let (port, _) =
+        common::serve_stream(br#"{"text":" Paris","reached_end":false}"#.to_vec(), true);
+    let client = TextSynthClient::builder("key")
+        .endpoint(&endpoint(port))
+        .stream_read_timeout(Duration::from_millis(100))
+        .build();
+    let request = completions::RequestBuilder::default()
+        .prompt("The capital of France is")
+        .stream(true)
+        .build()
+        .expect("failed to build completion request");
+    let chunks: Vec<_> = client
+        .completions(&completions::Engine::GPTJ6B, &request)
+        .await
+        .expect("failed to complete")
+        .collect()
+        .await;
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(
+        chunks[0].as_ref().expect("first chunk arrived").text,
+        [" Paris"]
+    );
+    assert!(matches!(
+        chunks[1],
+        Err(completions::Error::StreamTimeout(_))
+    ));
+}
+
+#[tokio::test]
+async fn request_options() {
+    // the server reads the request and never answers
+    let port = serve(|mut stream| {
+        read_request(&mut stream);
+        wait_closed(&mut stream);
+    });
+    let client = TextSynthClient::builder("key")
+        .endpoint(&endpoint(port))
+        .request_timeout(Duration::from_secs(60))
+        .build();
+    let options = RequestOptions::new().timeout(Duration::from_millis(100));
+    let error = client
+        .with_options(&options)
+        .tokenize_str(&completions::Engine::GPTJ6B, "Hello")
+        .await
+        .expect_err("the server never answers");
+    match error {
+        elikoga_textsynth::tokenize::Error::RequestError(error) => assert!(error.is_timeout()),
+        error => panic!("unexpected error {:?}", error),
+    }
+}
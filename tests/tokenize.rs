@@ -1,4 +1,8 @@
-use elikoga_textsynth::{completions::Engine, tokenize::RequestBuilder, TextSynthClient};
+use elikoga_textsynth::{
+    completions::Engine,
+    tokenize::{RequestBuilder, Response, TokenContentType},
+    TextSynthClient,
+};
 
 #[tokio::test]
 async fn tokenize() {
@@ -19,3 +23,29 @@ async fn tokenize() {
         [464, 2068, 7586, 21831, 18045, 625, 262, 16931, 3290]
     );
 }
+
+#[test]
+fn token_content() {
+    let request = RequestBuilder::default()
+        .text("Hello world")
+        .token_content_type(TokenContentType::Base64)
+        .build()
+        .expect("tokenize request should build");
+    assert_eq!(
+        serde_json::to_value(&request).expect("request should serialize"),
+        serde_json::json!({"text": "Hello world", "token_content_type": "base64"})
+    );
+
+    let response: Response =
+        serde_json::from_str(r#"{"tokens":[15496,995],"token_content":["SGVsbG8=","IHdvcmxk"]}"#)
+            .expect("response should parse");
+    let bytes = response
+        .token_bytes()
+        .expect("contents were returned")
+        .expect("contents are base64");
+    assert_eq!(bytes, [&b"Hello"[..], &b" world"[..]]);
+
+    let response: Response =
+        serde_json::from_str(r#"{"tokens":[15496]}"#).expect("response should parse");
+    assert!(response.token_bytes().is_none());
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "tracing")]
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use elikoga_textsynth::{
+    completions::{Engine, RequestBuilder},
+    transport::MockTransport,
+    TextSynthClient,
+};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+/// Collects the fields of all spans and events as `name=value` strings
+#[derive(Default)]
+struct Recorder {
+    next_id: AtomicU64,
+    fields: Arc<Mutex<Vec<String>>>,
+}
+
+impl Visit for &Recorder {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.fields
+            .lock()
+            .expect("lock poisoned")
+            .push(format!("{}={:?}", field.name(), value));
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+        span.record(&mut &*self);
+        span::Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &span::Id, values: &span::Record<'_>) {
+        values.record(&mut &*self);
+    }
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        event.record(&mut &*self);
+    }
+
+    fn enter(&self, _: &span::Id) {}
+
+    fn exit(&self, _: &span::Id) {}
+}
+
+#[tokio::test]
+async fn tracing() {
+    let recorder = Recorder::default();
+    let fields = recorder.fields.clone();
+    let _guard = tracing::subscriber::set_default(recorder);
+
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::json(
+            r#"{"text":" Paris","reached_end":true,"input_tokens":5,"output_tokens":1}"#,
+        ))
+        .build();
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .build()
+        .expect("completion request should build");
+    client
+        .complete(&Engine::GPTJ6B, &request)
+        .await
+        .expect("canned answer should parse");
+
+    let fields = fields.lock().expect("lock poisoned");
+    for field in [
+        "method=POST",
+        "endpoint=/v1/engines/gptj_6B/completions",
+        "engine=\"gptj_6B\"",
+        "request_size=37",
+        "status=200",
+        "input_tokens=5",
+        "output_tokens=1",
+    ] {
+        assert!(
+            fields.iter().any(|recorded| recorded == field),
+            "{} not in {:?}",
+            field,
+            fields
+        );
+    }
+    assert!(fields
+        .iter()
+        .any(|recorded| recorded.starts_with("duration_ms=")));
+}
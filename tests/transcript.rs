@@ -0,0 +1,164 @@
+mod common;
+
+use common::{endpoint, serve_json};
+use elikoga_textsynth::{
+    transcript::{AudioFormat, Engine, RequestBuilder},
+    TextSynthClient,
+};
+
+#[tokio::test]
+async fn transcript() {
+    let (port, requests) = serve_json([(
+        "200 OK",
+        r#"{"text":"Hello world","language":"en","duration":1.5,"segments":[{"id":0,"start":0.0,"end":1.5,"text":"Hello world"}]}"#,
+    )]);
+    let client = TextSynthClient::new_with_endpoint("key", &endpoint(port));
+    let request = RequestBuilder::default()
+        .audio(b"RIFF fake audio".to_vec())
+        .file_name("hello.wav")
+        .language("en")
+        .build()
+        .expect("transcript request should build");
+    let response = client
+        .transcript(&Engine::WhisperLargeV3, &request)
+        .await
+        .expect("transcript request should succeed");
+    assert_eq!(response.text, "Hello world");
+    assert_eq!(response.language, "en");
+    assert_eq!(response.segments[0].end, 1.5);
+    let received = requests.recv().expect("no request");
+    assert!(received.starts_with("POST /v1/engines/whisper_large_v3/transcript"));
+    assert!(received.contains("multipart/form-data"));
+    assert!(received.contains(r#"{"language":"en"}"#));
+    assert!(received.contains(r#"filename="hello.wav""#));
+    assert!(received.contains("RIFF fake audio"));
+}
+
+#[test]
+fn audio_format() {
+    assert_eq!(
+        AudioFormat::from_file_name("talk.MP3"),
+        Some(AudioFormat::Mp3)
+    );
+    assert_eq!(
+        AudioFormat::from_file_name("talk.m4a"),
+        Some(AudioFormat::M4a)
+    );
+    assert_eq!(AudioFormat::from_file_name("talk"), None);
+    assert_eq!(
+        AudioFormat::sniff(b"RIFF\0\0\0\0WAVEfmt "),
+        Some(AudioFormat::Wav)
+    );
+    assert_eq!(AudioFormat::sniff(b"OggS\0"), Some(AudioFormat::Ogg));
+    assert_eq!(
+        AudioFormat::sniff(b"\0\0\0\x20ftypM4A "),
+        Some(AudioFormat::M4a)
+    );
+    assert_eq!(AudioFormat::sniff(b"ID3\x04"), Some(AudioFormat::Mp3));
+    assert_eq!(
+        AudioFormat::sniff(&[0xFF, 0xFB, 0x90]),
+        Some(AudioFormat::Mp3)
+    );
+    assert_eq!(AudioFormat::sniff(b"RIFF fake audio"), None);
+    assert_eq!(AudioFormat::Ogg.mime(), "audio/ogg");
+}
+
+#[tokio::test]
+async fn sniffed_bytes() {
+    let (port, requests) = serve_json([("200 OK", r#"{"text":"Hello world","language":"en"}"#)]);
+    let client = TextSynthClient::new_with_endpoint("key", &endpoint(port));
+    let request = RequestBuilder::default()
+        .audio(&b"RIFF\0\0\0\0WAVEfmt "[..])
+        .build()
+        .expect("transcript request should build");
+    client
+        .transcript(&Engine::WhisperLargeV3, &request)
+        .await
+        .expect("transcript request should succeed");
+    let received = requests.recv().expect("no request");
+    assert!(received.contains(r#"filename="audio.wav""#));
+    assert!(received.to_lowercase().contains("content-type: audio/wav"));
+}
+
+#[cfg(feature = "streaming")]
+#[tokio::test]
+async fn streamed_file() {
+    let path = std::env::temp_dir().join(format!("textsynth-{}.mp3", std::process::id()));
+    std::fs::write(&path, b"ID3 audio from disk").expect("failed to write audio");
+    let (port, requests) = serve_json([("200 OK", r#"{"text":"Hello world","language":"en"}"#)]);
+    let client = TextSynthClient::new_with_endpoint("key", &endpoint(port));
+    let request = RequestBuilder::default()
+        .audio(path.as_path())
+        .build()
+        .expect("transcript request should build");
+    let response = client.transcript(&Engine::WhisperLargeV3, &request).await;
+    std::fs::remove_file(&path).expect("failed to remove audio");
+    assert_eq!(
+        response.expect("transcript request should succeed").text,
+        "Hello world"
+    );
+    let received = requests.recv().expect("no request");
+    let file_name = path.file_name().expect("no file name").to_string_lossy();
+    assert!(received.contains(&format!(r#"filename="{}""#, file_name)));
+    assert!(received.to_lowercase().contains("content-type: audio/mpeg"));
+    assert!(received.contains("ID3 audio from disk"));
+}
+
+#[cfg(feature = "streaming")]
+#[tokio::test]
+async fn streamed_reader() {
+    let (port, requests) = serve_json([("200 OK", r#"{"text":"Hello world","language":"en"}"#)]);
+    let client = TextSynthClient::new_with_endpoint("key", &endpoint(port));
+    let request = RequestBuilder::default()
+        .audio(elikoga_textsynth::transcript::Audio::from_reader(
+            std::io::Cursor::new(b"OggS audio from a reader".to_vec()),
+        ))
+        .file_name("speech.ogg")
+        .build()
+        .expect("transcript request should build");
+    client
+        .transcript(&Engine::WhisperLargeV3, &request)
+        .await
+        .expect("transcript request should succeed");
+    let received = requests.recv().expect("no request");
+    assert!(received.contains("transfer-encoding: chunked"));
+    assert!(received.to_lowercase().contains("content-type: audio/ogg"));
+    assert!(received.contains("OggS audio from a reader"));
+    let error = client
+        .transcript(&Engine::WhisperLargeV3, &request)
+        .await
+        .expect_err("the reader is already read");
+    assert!(matches!(error, elikoga_textsynth::transcript::Error::Io(_)));
+}
+
+#[cfg(feature = "streaming")]
+#[tokio::test]
+async fn live_stream() {
+    use futures::{channel::mpsc, SinkExt};
+
+    let (port, requests) = serve_json([("200 OK", r#"{"text":"Hello world","language":"en"}"#)]);
+    let client = TextSynthClient::new_with_endpoint("key", &endpoint(port));
+    let (mut microphone, recording) = mpsc::channel(1);
+    let request = RequestBuilder::default()
+        .audio(elikoga_textsynth::transcript::Audio::from_stream(recording))
+        .format(AudioFormat::Wav)
+        .build()
+        .expect("transcript request should build");
+    let capture = tokio::spawn(async move {
+        for chunk in ["RIFF first chunk ", "second chunk"] {
+            microphone
+                .send(bytes::Bytes::from(chunk))
+                .await
+                .expect("failed to send chunk");
+        }
+    });
+    client
+        .transcript(&Engine::WhisperLargeV3, &request)
+        .await
+        .expect("transcript request should succeed");
+    capture.await.expect("capture panicked");
+    let received = requests.recv().expect("no request");
+    assert!(received.contains(r#"filename="audio.wav""#));
+    assert!(received.contains("RIFF first chunk "));
+    assert!(received.contains("second chunk"));
+}
@@ -1,5 +1,7 @@
 use elikoga_textsynth::{
-    translate::{Engine, RequestBuilder},
+    translate::{self, Engine, Language, RequestBuilder},
+    transport::MockTransport,
+    validation::ValidationError,
     TextSynthClient,
 };
 
@@ -22,3 +24,62 @@ async fn translate() {
         .expect("Request should succeed");
     assert_eq!(response.translations[0].text, "Hallo Welt !");
 }
+
+#[test]
+fn language() {
+    assert_eq!(Language::ALL.len(), 100);
+    assert_eq!("de".parse(), Ok(Language::German));
+    assert_eq!("auto".parse(), Ok(Language::Auto));
+    assert!("zz".parse::<Language>().is_err());
+    assert_eq!(Language::from("xx"), Language::Other("xx".to_string()));
+    assert_eq!(Language::Iloko.to_string(), "ilo");
+
+    let request = RequestBuilder::default()
+        .text(["Hello".into()])
+        .source_lang(Language::Auto)
+        .target_lang(Language::German)
+        .build()
+        .expect("failed to build translation request");
+    let body = serde_json::to_value(&request).expect("request should serialize");
+    assert_eq!(body["source_lang"], "auto");
+    assert_eq!(body["target_lang"], "de");
+
+    let mut request = RequestBuilder::default();
+    request.text(["Hello".into()]).source_lang("en");
+    assert!(request.try_source_lang("zz").is_err());
+    assert!(request.try_target_lang("auto").is_err());
+    assert!(request.target_lang(Language::Auto).build().is_err());
+    assert!(request.target_lang("english").build().is_err());
+    let error = request
+        .target_lang("abc")
+        .build()
+        .expect_err("unsupported codes are rejected");
+    assert_eq!(
+        error,
+        ValidationError::Invalid {
+            field: "target_lang",
+            constraint: "a language supported by M2M100",
+            value: "abc".to_string(),
+        }
+    );
+}
+
+#[tokio::test]
+async fn translate_one_missing() {
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::json(
+            r#"{"translations":[],"input_tokens":4,"output_tokens":0}"#,
+        ))
+        .build();
+    let error = client
+        .translate_one(&Engine::M2M10012B, "Hello, world!", "en", "de")
+        .await
+        .expect_err("the answer has no translation");
+    assert!(matches!(
+        error,
+        translate::Error::TranslationCount {
+            expected: 1,
+            got: 0
+        }
+    ));
+}
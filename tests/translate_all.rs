@@ -0,0 +1,100 @@
+#![cfg(feature = "batch")]
+
+use std::sync::Arc;
+
+use elikoga_textsynth::{
+    translate::{self, Engine, RequestBuilder},
+    transport::MockTransport,
+    TextSynthClient,
+};
+use reqwest::StatusCode;
+
+/// Answers translation requests by upper-casing their texts, dropping the
+/// last translation of requests holding `"text 100"`
+fn uppercase() -> MockTransport {
+    MockTransport::new(|request| {
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .expect("request should have a body");
+        let body: serde_json::Value = serde_json::from_slice(body).expect("body should be json");
+        let texts = body["text"].as_array().expect("no texts");
+        assert!(texts.len() <= translate::MAX_TEXTS);
+        let mut translations: Vec<_> = texts
+            .iter()
+            .map(|text| {
+                serde_json::json!({
+                    "text": text.as_str().expect("text is a string").to_uppercase(),
+                    "detected_source_lang": "en",
+                })
+            })
+            .collect();
+        if texts.iter().any(|text| text == "text 100") {
+            translations.pop();
+        }
+        let body = serde_json::json!({
+            "translations": translations,
+            "input_tokens": texts.len(),
+            "output_tokens": 2 * texts.len(),
+        });
+        (StatusCode::OK, body.to_string())
+    })
+}
+
+#[tokio::test]
+async fn translate_all() {
+    let client = TextSynthClient::builder("key")
+        .transport(uppercase())
+        .build();
+    let mut request = RequestBuilder::default();
+    request.source_lang("en").target_lang("de");
+    let texts: Vec<Arc<str>> = (0..150)
+        .filter(|&i| i != 100)
+        .map(|i| format!("text {}", i).into())
+        .collect();
+    let response = client
+        .translate_all(&Engine::M2M10012B, &request, texts, 2)
+        .await;
+    assert!(response.translations.is_all_ok());
+    assert_eq!(response.translations.len(), 149);
+    for (i, translation) in response.translations.successes() {
+        let i = if i < 100 { i } else { i + 1 };
+        assert_eq!(translation.text, format!("TEXT {}", i));
+    }
+    assert_eq!(response.input_tokens, 149);
+    assert_eq!(response.output_tokens, 298);
+}
+
+#[tokio::test]
+async fn translate_all_count_mismatch() {
+    let client = TextSynthClient::builder("key")
+        .transport(uppercase())
+        .build();
+    let mut request = RequestBuilder::default();
+    request.source_lang("en").target_lang("de");
+    let texts: Vec<Arc<str>> = (0..150).map(|i| format!("text {}", i).into()).collect();
+    let response = client
+        .translate_all(&Engine::M2M10012B, &request, texts, 2)
+        .await;
+    assert_eq!(response.translations.len(), 150);
+    let failed: Vec<_> = response.translations.errors().map(|(i, _)| i).collect();
+    assert_eq!(failed, (64..128).collect::<Vec<_>>());
+    for (_, err) in response.translations.errors() {
+        assert!(matches!(
+            **err,
+            translate::Error::TranslationCount {
+                expected: 64,
+                got: 63
+            }
+        ));
+    }
+    assert_eq!(
+        response.translations[128]
+            .as_ref()
+            .expect("the last request succeeded")
+            .text,
+        "TEXT 128"
+    );
+    assert_eq!(response.input_tokens, 86);
+    assert_eq!(response.output_tokens, 172);
+}
@@ -0,0 +1,72 @@
+use elikoga_textsynth::{
+    translate::{
+        self,
+        memory::{similarity, InMemoryStore, TranslationMemory},
+        Engine, RequestBuilder, Translation,
+    },
+    transport::MockTransport,
+    TextSynthClient,
+};
+
+#[test]
+fn translation_memory() {
+    let mut store = InMemoryStore::new();
+    store.insert(
+        "Save changes",
+        "en",
+        "de",
+        &Translation {
+            text: "Änderungen speichern".into(),
+            detected_source_lang: "en".into(),
+        },
+    );
+    assert_eq!(store.len(), 1);
+    let exact = store
+        .lookup("Save changes", "en", "de", 1.0)
+        .expect("exact match should be found");
+    assert_eq!(exact.text, "Änderungen speichern");
+    assert_eq!(exact.similarity, 1.0);
+    assert!(store.lookup("Save changes", "en", "fr", 0.0).is_none());
+    assert!(store.lookup("Save change", "en", "de", 1.0).is_none());
+    let fuzzy = store
+        .lookup("Save change", "en", "de", 0.9)
+        .expect("fuzzy match should be found");
+    assert_eq!(fuzzy.source, "Save changes");
+    assert!(store.lookup("Discard", "en", "de", 0.9).is_none());
+}
+
+#[test]
+fn translation_memory_similarity() {
+    assert_eq!(similarity("", ""), 1.0);
+    assert_eq!(similarity("kitten", "kitten"), 1.0);
+    assert_eq!(similarity("abc", "xyz"), 0.0);
+    assert!((similarity("kitten", "sitting") - (1.0 - 3.0 / 7.0)).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn translation_memory_count_mismatch() {
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::json(
+            r#"{"translations":[{"text":"Hallo","detected_source_lang":"en"}],"input_tokens":4,"output_tokens":2}"#,
+        ))
+        .build();
+    let request = RequestBuilder::default()
+        .text(["Hello".into(), "World".into()])
+        .source_lang("en")
+        .target_lang("de")
+        .build()
+        .expect("translation request should build");
+    let mut store = InMemoryStore::new();
+    let error = client
+        .translate_with_memory(&Engine::M2M10012B, &request, &mut store, 1.0)
+        .await
+        .expect_err("one translation for two texts");
+    assert!(matches!(
+        error,
+        translate::Error::TranslationCount {
+            expected: 2,
+            got: 1
+        }
+    ));
+    assert!(store.is_empty());
+}
@@ -0,0 +1,84 @@
+use elikoga_textsynth::{
+    translate::{
+        self,
+        pipeline::{Context, MaskPlaceholders, MatchInitialCase, Pipeline},
+        Engine, RequestBuilder,
+    },
+    transport::MockTransport,
+    TextSynthClient,
+};
+
+#[test]
+fn translation_pipeline() {
+    let pipeline = Pipeline::new()
+        .pre(MaskPlaceholders::default())
+        .post(MatchInitialCase);
+    let source = "Hello {name}, you have {count} messages";
+    let mut context = Context::new(source);
+    let masked = pipeline.pre_process(source.to_string(), &mut context);
+    assert_eq!(masked, "Hello [0], you have [1] messages");
+    let translated = "hallo [0], du hast [1] Nachrichten".to_string();
+    assert_eq!(
+        pipeline.post_process(translated, &context),
+        "Hallo {name}, du hast {count} Nachrichten"
+    );
+}
+
+#[test]
+fn translation_pipeline_bracketed_source() {
+    let pipeline = Pipeline::new().pre(MaskPlaceholders::default());
+    let source = "See [0] and [2] for {name}";
+    let mut context = Context::new(source);
+    let masked = pipeline.pre_process(source.to_string(), &mut context);
+    assert_eq!(masked, "See [0] and [2] for [1]");
+    let translated = "Siehe [0] und [2] für [1]".to_string();
+    assert_eq!(
+        pipeline.post_process(translated, &context),
+        "Siehe [0] und [2] für {name}"
+    );
+}
+
+#[test]
+fn mask_placeholders_delimiters() {
+    let mask = MaskPlaceholders::new("<<", ">>").expect("delimiters are valid");
+    assert_eq!((mask.open(), mask.close()), ("<<", ">>"));
+    let mut context = Context::new("Hi <<name>>");
+    let masked = Pipeline::new()
+        .pre(mask)
+        .pre_process("Hi <<name>>".to_string(), &mut context);
+    assert_eq!(masked, "Hi [0]");
+    let error = MaskPlaceholders::new("", "").expect_err("empty delimiters never match");
+    assert_eq!(error.field(), "open");
+    let error = MaskPlaceholders::new("{", "").expect_err("empty delimiters never match");
+    assert_eq!(error.field(), "close");
+}
+
+#[tokio::test]
+async fn translation_pipeline_count_mismatch() {
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::json(
+            r#"{"translations":[{"text":"Hallo [0]","detected_source_lang":"en"}],"input_tokens":4,"output_tokens":4}"#,
+        ))
+        .build();
+    let request = RequestBuilder::default()
+        .text(vec!["Hello {name}".into(), "Bye {name}".into()])
+        .source_lang("en")
+        .target_lang("de")
+        .build()
+        .expect("translate request should build");
+    let error = client
+        .translate_with_pipeline(
+            &Engine::M2M10012B,
+            &request,
+            &Pipeline::new().pre(MaskPlaceholders::default()),
+        )
+        .await
+        .expect_err("one translation is missing");
+    assert!(matches!(
+        error,
+        translate::Error::TranslationCount {
+            expected: 2,
+            got: 1
+        }
+    ));
+}
@@ -0,0 +1,127 @@
+use std::sync::{Arc, Mutex};
+
+use elikoga_textsynth::{
+    completions::{Engine, RequestBuilder},
+    transport::MockTransport,
+    TextSynthClient,
+};
+use reqwest::StatusCode;
+
+#[tokio::test]
+async fn mock_transport() {
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::json(r#"{"tokens":[1,2,3]}"#))
+        .build();
+    let tokens = client
+        .tokenize_str(&Engine::GPTJ6B, "Hello")
+        .await
+        .expect("canned answer should parse");
+    assert_eq!(tokens, [1, 2, 3]);
+}
+
+#[tokio::test]
+async fn mock_transport_handler() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let seen = requests.clone();
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::new(move |request| {
+            let body = request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(|body| String::from_utf8_lossy(body).into_owned());
+            seen.lock()
+                .expect("lock poisoned")
+                .push((request.url().path().to_string(), body));
+            (
+                StatusCode::PAYMENT_REQUIRED,
+                r#"{"error":"not enough credits"}"#.to_string(),
+            )
+        }))
+        .build();
+    let request = RequestBuilder::default()
+        .prompt("Hello")
+        .build()
+        .expect("completion request should build");
+    let error = client
+        .complete(&Engine::GPTJ6B, &request)
+        .await
+        .expect_err("the mock answers with an error");
+    assert_eq!(
+        elikoga_textsynth::Error::from(error)
+            .api_error()
+            .expect("not an api error")
+            .message,
+        "not enough credits"
+    );
+    let requests = requests.lock().expect("lock poisoned");
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].0, "/v1/engines/gptj_6B/completions");
+    assert_eq!(requests[0].1.as_deref(), Some(r#"{"prompt":"Hello"}"#));
+}
+
+#[cfg(feature = "streaming")]
+#[tokio::test]
+async fn mock_transport_stream() {
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::json(
+            "{\"text\":\" Paris\",\"reached_end\":false}\n\n{\"text\":\".\",\"reached_end\":true}\n\n",
+        ))
+        .build();
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .stream(true)
+        .build()
+        .expect("completion request should build");
+    let stream = client
+        .completions(&Engine::GPTJ6B, &request)
+        .await
+        .expect("canned answer should be accepted");
+    let collected = elikoga_textsynth::completions::collect_text(stream)
+        .await
+        .expect("canned chunks should parse");
+    assert_eq!(collected.text, " Paris.");
+    assert!(collected.reached_end);
+}
+
+#[cfg(feature = "streaming")]
+#[tokio::test]
+async fn mock_transport_sse_stream() {
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .stream(true)
+        .build()
+        .expect("completion request should build");
+    for body in [
+        "data: {\"text\":\" Paris\",\"reached_end\":false}\n\ndata: {\"text\":\".\",\"reached_end\":true}\n\n",
+        // comments, crlf line endings and values split over several lines
+        ": keep-alive\r\n\r\nevent: chunk\r\ndata: {\"text\":\" Paris\",\r\ndata: \"reached_end\":false}\r\n\r\ndata: {\"text\":\".\",\"reached_end\":true}\r\n\r\n",
+        // the stream ends at the done marker
+        "data: {\"text\":\" Paris\",\"reached_end\":false}\n\ndata: {\"text\":\".\",\"reached_end\":true}\n\ndata: [DONE]\n\ndata: {\"text\":\"!\"}\n\n",
+        // the last event may miss its blank line
+        "data: {\"text\":\" Paris\",\"reached_end\":false}\n\ndata: {\"text\":\".\",\"reached_end\":true}",
+    ] {
+        let client = TextSynthClient::builder("key")
+            .transport(MockTransport::json(body))
+            .build();
+        let stream = client
+            .completions(&Engine::GPTJ6B, &request)
+            .await
+            .expect("canned answer should be accepted");
+        let collected = elikoga_textsynth::completions::collect_text(stream)
+            .await
+            .expect("canned events should parse");
+        assert_eq!(collected.text, " Paris.", "body {:?}", body);
+        assert!(collected.reached_end);
+    }
+
+    let client = TextSynthClient::builder("key")
+        .transport(MockTransport::json("data: {\"text\":\n\n"))
+        .build();
+    let stream = client
+        .completions(&Engine::GPTJ6B, &request)
+        .await
+        .expect("canned answer should be accepted");
+    assert!(elikoga_textsynth::completions::collect_text(stream)
+        .await
+        .is_err());
+}
@@ -0,0 +1,113 @@
+use elikoga_textsynth::{completions, translate};
+
+#[test]
+fn try_setters() {
+    let mut request = completions::RequestBuilder::default();
+    request.prompt("Hello");
+    assert!(request.try_top_p(1.5).is_err());
+    assert!(request.try_n(0_u32).is_err());
+    request
+        .try_top_p(0.9)
+        .and_then(|request| request.try_top_k(40_u32))
+        .expect("valid values should be accepted");
+    request.build().expect("request should build");
+
+    let mut request = translate::RequestBuilder::default();
+    assert!(request.try_source_lang("english").is_err());
+    assert!(request.try_num_beams(6_u32).is_err());
+    assert!(request.try_text(Vec::new()).is_err());
+    request
+        .try_text(["Hello".into()])
+        .and_then(|request| request.try_source_lang("en"))
+        .and_then(|request| request.try_target_lang("de"))
+        .expect("valid values should be accepted");
+    request.build().expect("request should build");
+}
+
+#[test]
+fn constraint() {
+    use completions::Constraint;
+
+    let mut request = completions::RequestBuilder::default();
+    request
+        .prompt("Answer in JSON:")
+        .grammar("root ::= \"yes\" | \"no\"")
+        .schema(serde_json::json!({"type": "object"}));
+    assert!(request.build().is_err());
+
+    request.constraint(Constraint::JsonSchema(
+        serde_json::json!({"type": "object"}),
+    ));
+    let body = serde_json::to_value(request.build().expect("request should build"))
+        .expect("request should serialize");
+    assert_eq!(body["schema"], serde_json::json!({"type": "object"}));
+    assert!(body.get("grammar").is_none());
+
+    request.constraint(Constraint::Grammar("root ::= \"yes\"".to_string()));
+    let body = serde_json::to_value(request.build().expect("request should build"))
+        .expect("request should serialize");
+    assert_eq!(body["grammar"], "root ::= \"yes\"");
+    assert!(body.get("schema").is_none());
+}
+
+#[test]
+fn remaining_constraints() {
+    let mut request = completions::RequestBuilder::default();
+    request.prompt("Hello");
+    assert!(request.try_max_tokens(0_u32).is_err());
+    assert!(request.try_repetition_penalty(0.0).is_err());
+    assert!(request
+        .try_stop(vec![
+            "a".into(),
+            "b".into(),
+            "c".into(),
+            "d".into(),
+            "e".into(),
+            "f".into()
+        ])
+        .is_err());
+    assert!(request
+        .try_logit_bias(std::collections::HashMap::from([("1".to_string(), -101.0)]))
+        .is_err());
+    request
+        .try_max_tokens(16_u32)
+        .and_then(|request| request.try_repetition_penalty(1.1))
+        .and_then(|request| request.try_stop(vec!["\n".into()]))
+        .and_then(|request| {
+            request.try_logit_bias(std::collections::HashMap::from([("1".to_string(), -100.0)]))
+        })
+        .expect("valid values should be accepted");
+    request.build().expect("request should build");
+
+    let mut request = completions::RequestBuilder::default();
+    request.prompt("Hello").max_tokens(0_u32);
+    assert!(request.build().is_err());
+}
+
+#[test]
+fn validation_error() {
+    use elikoga_textsynth::{tokenize, validation::ValidationError};
+
+    let error = completions::RequestBuilder::default()
+        .prompt("Hello")
+        .top_k(0_u32)
+        .build()
+        .unwrap_err();
+    assert_eq!(error.field(), "top_k");
+    assert_eq!(
+        error,
+        ValidationError::Invalid {
+            field: "top_k",
+            constraint: "between 1 and 1000",
+            value: "0".to_string(),
+        }
+    );
+
+    let error = translate::RequestBuilder::default()
+        .try_num_beams(6_u32)
+        .unwrap_err();
+    assert_eq!(error.field(), "num_beams");
+
+    let error = tokenize::RequestBuilder::default().build().unwrap_err();
+    assert_eq!(error, ValidationError::UninitializedField("text"));
+}
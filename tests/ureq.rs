@@ -0,0 +1,118 @@
+#![cfg(feature = "ureq")]
+
+mod common;
+
+use std::error::Error as _;
+
+use common::{closed_port, endpoint, read_request, respond_with, serve, serve_json};
+use elikoga_textsynth::{
+    completions::{self, Engine, RequestBuilder},
+    transcript,
+    ureq::TextSynthUreqClient,
+};
+
+fn client(port: u16) -> TextSynthUreqClient {
+    TextSynthUreqClient::new_with_endpoint("key", &endpoint(port))
+}
+
+#[test]
+fn ureq_complete() {
+    let (port, requests) = serve_json([("200 OK", r#"{"text":" Paris.","reached_end":true}"#)]);
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .stream(true)
+        .build()
+        .expect("failed to build completion request");
+    let response = client(port)
+        .complete(&Engine::GPTJ6B, &request)
+        .expect("failed to complete");
+    assert_eq!(response.text, [" Paris."]);
+    let request = requests.recv().expect("no request");
+    assert!(request.starts_with("POST /v1/engines/gptj_6B/completions "));
+    assert!(request
+        .to_lowercase()
+        .contains("authorization: bearer key\r\n"));
+    assert!(request.ends_with(r#"{"prompt":"The capital of France is"}"#));
+}
+
+#[test]
+fn ureq_completions() {
+    let port = serve(|mut stream| {
+        read_request(&mut stream);
+        respond_with(
+            &mut stream,
+            "200 OK",
+            "content-type: text/event-stream\r\n",
+            b"data: {\"text\":\" Paris\",\"reached_end\":false}\n\ndata: {\"text\":\".\",\"reached_end\":true}\n\ndata: [DONE]\n\n",
+        );
+    });
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .stream(true)
+        .build()
+        .expect("failed to build completion request");
+    let text: String = client(port)
+        .completions(&Engine::GPTJ6B, &request)
+        .expect("failed to complete")
+        .map(|chunk| chunk.expect("failed to stream").text.concat())
+        .collect();
+    assert_eq!(text, " Paris.");
+}
+
+#[test]
+fn ureq_error_answer() {
+    let (port, _) = serve_json([("401 Unauthorized", r#"{"error":"invalid api key"}"#)]);
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .build()
+        .expect("failed to build completion request");
+    match client(port).complete(&Engine::GPTJ6B, &request) {
+        Err(completions::Error::Unauthorized(error)) => {
+            assert_eq!(error.message, "invalid api key");
+            assert!(error.request_id.is_some());
+        }
+        other => panic!("not unauthorized: {:?}", other),
+    }
+}
+
+#[test]
+fn ureq_connection_error() {
+    let port = closed_port();
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .build()
+        .expect("failed to build completion request");
+    let error = client(port)
+        .complete(&Engine::GPTJ6B, &request)
+        .expect_err("nothing listens on the port");
+    let completions::Error::RequestError(error) = error else {
+        panic!("not a request error: {:?}", error);
+    };
+    let mut source = error.source();
+    while let Some(error) = source {
+        if error.is::<ureq::Error>() {
+            return;
+        }
+        source = error.source();
+    }
+    panic!("the error of ureq is lost: {:?}", error);
+}
+
+#[test]
+fn ureq_transcript() {
+    let (port, requests) = serve_json([("200 OK", r#"{"text":"Hello world","language":"en"}"#)]);
+    let request = transcript::Request::builder(b"ID3 audio".to_vec())
+        .file_name("hello.mp3")
+        .build()
+        .expect("failed to build transcript request");
+    let response = client(port)
+        .transcript(&transcript::Engine::WhisperLargeV3, &request)
+        .expect("failed to transcribe");
+    assert_eq!(response.text, "Hello world");
+    let request = requests.recv().expect("no request");
+    assert!(request.contains("multipart/form-data; boundary="));
+    assert!(request.contains("Content-Disposition: form-data; name=\"json\"\r\n\r\n{"));
+    assert!(request.contains(
+        "Content-Disposition: form-data; name=\"file\"; filename=\"hello.mp3\"\r\nContent-Type: audio/mpeg\r\n\r\nID3 audio\r\n"
+    ));
+}
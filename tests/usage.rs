@@ -0,0 +1,101 @@
+use elikoga_textsynth::{
+    completions::{Engine, RequestBuilder},
+    transport::MockTransport,
+    usage::UsageRecorder,
+    TextSynthClient,
+};
+use reqwest::StatusCode;
+
+#[tokio::test]
+async fn usage() {
+    let usage = UsageRecorder::new();
+    let client = TextSynthClient::builder("key")
+        .usage_recorder(usage.clone())
+        .transport(MockTransport::new(|request| {
+            if request.url().path().ends_with("/completions") {
+                (
+                    StatusCode::OK,
+                    r#"{"text":" Paris","reached_end":true,"input_tokens":5,"output_tokens":1}"#
+                        .to_string(),
+                )
+            } else {
+                (
+                    StatusCode::PAYMENT_REQUIRED,
+                    r#"{"error":"not enough credits"}"#.to_string(),
+                )
+            }
+        }))
+        .build();
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .build()
+        .expect("completion request should build");
+    for _ in 0..2 {
+        client
+            .complete(&Engine::GPTJ6B, &request)
+            .await
+            .expect("canned answer should parse");
+    }
+    client
+        .tokenize_str(&Engine::GPTNeoX20B, "Hello")
+        .await
+        .expect_err("the mock answers with an error");
+
+    let snapshot = usage.snapshot();
+    let gptj = &snapshot.engines["gptj_6B"];
+    assert_eq!(gptj.requests, 2);
+    assert_eq!(gptj.errors, 0);
+    assert_eq!(gptj.input_tokens, 10);
+    assert_eq!(gptj.output_tokens, 2);
+    assert_eq!(gptj.latency.count(), 2);
+    let neox = &snapshot.engines["gptneox_20B"];
+    assert_eq!(neox.requests, 1);
+    assert_eq!(neox.errors, 1);
+    let total = snapshot.total();
+    assert_eq!(total.requests, 3);
+    assert_eq!(total.errors, 1);
+    assert_eq!(total.latency.count(), 3);
+
+    usage.reset();
+    assert!(usage.snapshot().engines.is_empty());
+}
+
+#[cfg(feature = "streaming")]
+#[tokio::test]
+async fn stream_usage() {
+    let client = TextSynthClient::builder("key")
+        .usage_recorder(UsageRecorder::new())
+        .transport(MockTransport::new(|request| {
+            let body = request.body().and_then(|body| body.as_bytes());
+            let body = if body.is_some_and(|body| body.ends_with(br#""stream":true}"#)) {
+                r#"{"text":" Paris","reached_end":false}{"text":".","reached_end":true,"input_tokens":5,"output_tokens":2}"#
+            } else {
+                r#"{"text":" Paris.","reached_end":true,"input_tokens":5,"output_tokens":2}"#
+            };
+            (StatusCode::OK, body.to_string())
+        }))
+        .build();
+    let request = RequestBuilder::default()
+        .prompt("The capital of France is")
+        .stream(true)
+        .build()
+        .expect("completion request should build");
+    let stream = client
+        .completions(&Engine::GPTJ6B, &request)
+        .await
+        .expect("canned answer should be sent");
+    elikoga_textsynth::completions::collect_text(stream)
+        .await
+        .expect("canned answer should parse");
+    client
+        .completions_raw(&Engine::GPTJ6B, &serde_json::json!({"prompt": "Hello"}))
+        .await
+        .expect("canned answer should parse");
+
+    let usage = client.usage().expect("the client records its usage");
+    let gptj = &usage.engines["gptj_6B"];
+    assert_eq!(gptj.requests, 2);
+    assert_eq!(gptj.input_tokens, 10);
+    assert_eq!(gptj.output_tokens, 4);
+    assert!(TextSynthClient::new("key").usage().is_none());
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "vcr")]
+
+use std::error::Error as _;
+
+use elikoga_textsynth::{
+    completions::{self, Engine, RequestBuilder},
+    transport::MockTransport,
+    vcr::{Cassette, Mode},
+    TextSynthClient,
+};
+
+#[tokio::test]
+async fn record_replay() {
+    let fixture = std::env::temp_dir().join(format!("textsynth-vcr-{}.json", std::process::id()));
+    let request = RequestBuilder::default()
+        .prompt("Hello")
+        .build()
+        .expect("completion request should build");
+
+    let cassette = Cassette::new(&fixture, Mode::Record)
+        .expect("cassette should be created")
+        .transport(MockTransport::json(
+            r#"{"text":" world","reached_end":true,"input_tokens":1,"output_tokens":1}"#,
+        ));
+    assert!(cassette.is_recording());
+    let client = TextSynthClient::builder("secret-key")
+        .transport(cassette)
+        .build();
+    let recorded = client
+        .complete(&Engine::GPTJ6B, &request)
+        .await
+        .expect("recorded request should succeed");
+    assert_eq!(recorded.text, [" world"]);
+    let saved = std::fs::read_to_string(&fixture).expect("fixture should be written");
+    assert!(saved.contains("/v1/engines/gptj_6B/completions"));
+    assert!(!saved.contains("secret-key"));
+
+    let cassette = Cassette::new(&fixture, Mode::Auto).expect("fixture should load");
+    std::fs::remove_file(&fixture).expect("fixture should be removed");
+    assert!(!cassette.is_recording());
+    assert_eq!(cassette.interactions().len(), 1);
+    let client = TextSynthClient::builder("").transport(cassette).build();
+    let replayed = client
+        .complete(&Engine::GPTJ6B, &request)
+        .await
+        .expect("replayed request should succeed");
+    assert_eq!(replayed.text, recorded.text);
+
+    let error = client
+        .complete(&Engine::GPTJ6B, &request)
+        .await
+        .expect_err("interactions are replayed once");
+    let error = elikoga_textsynth::Error::from(error);
+    let api_error = error.api_error().expect("not an api error");
+    assert!(api_error.message.starts_with("no interaction recorded"));
+}
+
+#[tokio::test]
+async fn record_write_error() {
+    // the fixture can't be written below a regular file
+    let file = std::env::temp_dir().join(format!("textsynth-vcr-file-{}", std::process::id()));
+    std::fs::write(&file, "").expect("file should be written");
+    let cassette = Cassette::new(file.join("fixture.json"), Mode::Record)
+        .expect("cassette should be created")
+        .transport(MockTransport::json(
+            r#"{"text":" world","reached_end":true,"input_tokens":1,"output_tokens":1}"#,
+        ));
+    let client = TextSynthClient::builder("key").transport(cassette).build();
+    let request = RequestBuilder::default()
+        .prompt("Hello")
+        .build()
+        .expect("completion request should build");
+    let error = client
+        .complete(&Engine::GPTJ6B, &request)
+        .await
+        .expect_err("the fixture can't be written");
+    std::fs::remove_file(&file).expect("file should be removed");
+    let completions::Error::RequestError(error) = error else {
+        panic!("not a request error: {:?}", error);
+    };
+    let mut source = error.source();
+    while let Some(error) = source {
+        if error.is::<std::io::Error>() {
+            return;
+        }
+        source = error.source();
+    }
+    panic!("the io error is lost: {:?}", error);
+}
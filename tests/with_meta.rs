@@ -0,0 +1,31 @@
+mod common;
+
+use common::{endpoint, read_request, respond_with, serve};
+use elikoga_textsynth::{completions::Engine, tokenize::RequestBuilder, TextSynthClient};
+
+#[tokio::test]
+async fn with_meta() {
+    // answer a single tokenize request on a local port
+    let port = serve(|mut stream| {
+        read_request(&mut stream);
+        respond_with(
+            &mut stream,
+            "200 OK",
+            "content-type: application/json\r\nx-ratelimit-remaining: 41\r\n",
+            br#"{"tokens":[1,2,3]}"#,
+        );
+    });
+    let client = TextSynthClient::new_with_endpoint("key", &endpoint(port));
+    let request = RequestBuilder::default()
+        .text("The quick brown fox")
+        .build()
+        .expect("tokenize request should build");
+    let response = client
+        .tokenize_with_meta(&Engine::GPTJ6B, &request)
+        .await
+        .expect("tokenize request should succeed");
+    assert_eq!(response.response.tokens, [1, 2, 3]);
+    assert!(response.status.is_success());
+    assert_eq!(response.header("x-ratelimit-remaining"), Some("41"));
+    assert_eq!(response.header("x-missing"), None);
+}